@@ -44,7 +44,8 @@ export function Login() {
     } catch (err) {
       if (err instanceof HTTPError) {
         if (err.response.status === 401) {
-          toast.error("invalid username or password");
+          // the message tells a wrong password, a locked and a disabled account apart
+          toast.error(err.message);
           return;
         }
         toastErrorHandler(err);
@@ -1,12 +1,15 @@
 import * as z from "zod";
 
+/**
+ * Names of the built-in roles, exactly as the API sends them. Custom roles can
+ * have any other name.
+ */
 export const ROLES = {
   Administrator: "Administrator",
-  BasicUser: "Basic User",
-  RecipeUser: "Recipe User",
+  RecipeUser: "RecipeUser",
 } as const;
 
-const RoleNameSchema = z.enum(Object.values(ROLES));
+const RoleNameSchema = z.string().min(1);
 
 // alias for reuse
 export type RoleName = z.infer<typeof RoleNameSchema>;
@@ -17,7 +20,7 @@ export type RoleName = z.infer<typeof RoleNameSchema>;
 export const RoleSchema = z.object({
   id: z.uuidv7(),
   name: RoleNameSchema,
-  // Add other role fields as needed
+  isSystem: z.boolean(),
 });
 
 export type Role = z.infer<typeof RoleSchema>;
@@ -3,16 +3,56 @@ import ky from "ky";
 import * as z from "zod";
 
 /**
- * Error response schema matching the backend ErrorResponse struct
+ * A validation rule broken by one field of a request body, matching the backend
+ * FieldViolation struct
+ */
+export const FieldViolationSchema = z.object({
+  field: z.string(),
+  code: z.string(),
+  message: z.string(),
+});
+
+export type FieldViolation = z.infer<typeof FieldViolationSchema>;
+
+/**
+ * Error response schema matching the backend ProblemDetails struct (RFC 9457).
+ * Refused logins are told apart by code: invalid_credentials, account_locked and
+ * account_disabled.
  */
 export const ErrorResponseSchema = z.object({
-  error: z.string(),
+  type: z.string(),
+  title: z.string(),
+  status: z.number(),
+  detail: z.string(),
+  code: z.string(),
+  requestId: z.string().optional(),
+  violations: z.array(FieldViolationSchema).optional(),
+  /** Seconds to wait before retrying, for rate_limited and account_locked */
+  retryAfter: z.number().optional(),
 });
 
 export type ErrorResponse = z.infer<typeof ErrorResponseSchema>;
 
 let queryClient: QueryClient | null = null;
 
+/** Cookie set by the backend alongside the session, readable by scripts. */
+const CSRF_COOKIE = "csrf_token";
+
+/** Header that state-changing requests must echo the CSRF cookie in. */
+const CSRF_HEADER = "X-CSRF-Token";
+
+const SAFE_METHODS = new Set(["GET", "HEAD", "OPTIONS"]);
+
+function readCookie(name: string): string | null {
+  const prefix = `${name}=`;
+  const cookie = document.cookie
+    .split(";")
+    .map((part) => part.trim())
+    .find((part) => part.startsWith(prefix));
+
+  return cookie ? decodeURIComponent(cookie.slice(prefix.length)) : null;
+}
+
 export function setHttpClientQueryClient(client: QueryClient) {
   queryClient = client;
 }
@@ -20,6 +60,7 @@ export function setHttpClientQueryClient(client: QueryClient) {
 /**
  * httpClient is used to make HTTP requests to the backend API.
  * All requests are automatically prefixed with '/api' and include credentials (cookies).
+ * Requests that change state also echo the CSRF cookie in the X-CSRF-Token header.
  *
  * @example
  * // POST to /api/auth/signout
@@ -34,14 +75,30 @@ export const httpClient = ky.create({
   credentials: "include",
   timeout: 10_000,
   hooks: {
+    beforeRequest: [
+      (request) => {
+        if (SAFE_METHODS.has(request.method)) {
+          return;
+        }
+
+        const csrfToken = readCookie(CSRF_COOKIE);
+        if (csrfToken) {
+          request.headers.set(CSRF_HEADER, csrfToken);
+        }
+      },
+    ],
     beforeError: [
       async (error) => {
         const { response } = error;
         if (response && response.body) {
-          const body = await response.json();
-          const errorResponse = ErrorResponseSchema.safeParse(body);
-          if (errorResponse.error) {
-            error.message = z.prettifyError(errorResponse.error);
+          const body = await response.json().catch(() => null);
+          const problem = ErrorResponseSchema.safeParse(body);
+          if (problem.success) {
+            const { detail, violations = [], retryAfter } = problem.data;
+            const fields = violations.map(({ field, message }) => `${field}: ${message}`);
+            const retry =
+              retryAfter === undefined ? [] : [`Retry in ${Math.ceil(retryAfter / 60)} min.`];
+            error.message = [detail, ...fields, ...retry].join("\n");
           }
         }
 
@@ -8,7 +8,7 @@ export const administratorRoleRoute = createRoute({
   loader: async ({ context }) => {
     const user = await context.queryClient.ensureQueryData(currentUserQueryOptions);
 
-    if (user && !user.hasRole("Administrator")) {
+    if (user && !user.isAdmin) {
       throw notFound();
     }
   },
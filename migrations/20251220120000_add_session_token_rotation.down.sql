@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE sessions DROP COLUMN rotated_at;
+ALTER TABLE sessions DROP COLUMN previous_token;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE sessions ADD COLUMN previous_token TEXT;
+ALTER TABLE sessions ADD COLUMN rotated_at DATETIME;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+-- Existing sessions were hashed with the single SESSION_HMAC_KEY, which the keyring exposes as 'default'.
+ALTER TABLE sessions ADD COLUMN token_key_id TEXT NOT NULL DEFAULT 'default';
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE password_policy;
+ALTER TABLE sessions DROP COLUMN password_change_required;
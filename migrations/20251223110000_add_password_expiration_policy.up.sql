@@ -0,0 +1,10 @@
+-- Add up migration script here
+ALTER TABLE sessions ADD COLUMN password_change_required BOOLEAN NOT NULL DEFAULT false;
+
+CREATE TABLE password_policy (
+    id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+    expiration_days INTEGER,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO password_policy (id, expiration_days) VALUES (1, NULL);
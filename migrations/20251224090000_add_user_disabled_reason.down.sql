@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE users DROP COLUMN disabled_at;
+ALTER TABLE users DROP COLUMN disabled_reason;
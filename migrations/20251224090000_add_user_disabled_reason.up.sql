@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE users ADD COLUMN disabled_reason TEXT;
+ALTER TABLE users ADD COLUMN disabled_at DATETIME;
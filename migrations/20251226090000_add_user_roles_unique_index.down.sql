@@ -0,0 +1,2 @@
+-- Add down migration script here
+DROP INDEX idx_user_roles_user_id_role_id;
@@ -0,0 +1,9 @@
+-- Add up migration script here
+DELETE FROM user_roles
+WHERE rowid NOT IN (
+    SELECT MIN(rowid)
+    FROM user_roles
+    GROUP BY user_id, role_id
+);
+
+CREATE UNIQUE INDEX idx_user_roles_user_id_role_id ON user_roles(user_id, role_id);
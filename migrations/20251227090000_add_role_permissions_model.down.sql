@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE role_permissions;
+ALTER TABLE roles DROP COLUMN is_system;
@@ -0,0 +1,24 @@
+-- Add up migration script here
+ALTER TABLE roles ADD COLUMN is_system BOOLEAN NOT NULL DEFAULT false;
+
+UPDATE roles SET is_system = true WHERE name IN ('Administrator', 'RecipeUser');
+
+CREATE TABLE role_permissions (
+    role_id BLOB NOT NULL REFERENCES roles(id) ON DELETE CASCADE,
+    permission TEXT NOT NULL,
+    PRIMARY KEY (role_id, permission)
+);
+
+INSERT INTO role_permissions (role_id, permission)
+SELECT r.id, p.permission
+FROM roles r
+INNER JOIN (
+    SELECT 'Administrator' AS role_name, 'recipes:read' AS permission
+    UNION ALL SELECT 'Administrator', 'recipes:write'
+    UNION ALL SELECT 'Administrator', 'users:manage'
+    UNION ALL SELECT 'Administrator', 'roles:manage'
+    UNION ALL SELECT 'Administrator', 'sessions:manage'
+    UNION ALL SELECT 'RecipeUser', 'recipes:read'
+    UNION ALL SELECT 'RecipeUser', 'recipes:write'
+) p
+    ON p.role_name = r.name;
@@ -0,0 +1,16 @@
+-- Add up migration script here
+CREATE TABLE user_applications (
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    application TEXT NOT NULL,
+    granted_by BLOB REFERENCES users(id) ON DELETE SET NULL,
+    granted_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, application)
+);
+
+-- keep recipes available to everyone who could already use them
+INSERT INTO user_applications (user_id, application)
+SELECT DISTINCT ur.user_id, 'recipes'
+FROM user_roles ur
+INNER JOIN role_permissions rp
+    ON rp.role_id = ur.role_id
+WHERE rp.permission = 'recipes:read';
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DELETE FROM role_permissions WHERE permission = 'audit:read';
+
+DROP TABLE audit_events;
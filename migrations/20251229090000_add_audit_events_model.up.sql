@@ -0,0 +1,34 @@
+-- Add up migration script here
+-- actor_id is deliberately not a foreign key so events outlive the users they mention
+CREATE TABLE audit_events (
+    id BLOB PRIMARY KEY NOT NULL,
+    occurred_at DATETIME NOT NULL,
+    actor_id BLOB,
+    action TEXT NOT NULL,
+    target_type TEXT,
+    target_id TEXT,
+    ip_address TEXT,
+    outcome TEXT NOT NULL,
+    details TEXT
+);
+
+CREATE INDEX idx_audit_events_occurred_at ON audit_events(occurred_at);
+CREATE INDEX idx_audit_events_actor_id ON audit_events(actor_id);
+CREATE INDEX idx_audit_events_action ON audit_events(action);
+
+CREATE TRIGGER audit_events_no_update
+BEFORE UPDATE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit events are append-only');
+END;
+
+CREATE TRIGGER audit_events_no_delete
+BEFORE DELETE ON audit_events
+BEGIN
+    SELECT RAISE(ABORT, 'audit events are append-only');
+END;
+
+INSERT INTO role_permissions (role_id, permission)
+SELECT id, 'audit:read'
+FROM roles
+WHERE name = 'Administrator';
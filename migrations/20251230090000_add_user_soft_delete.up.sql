@@ -0,0 +1,55 @@
+-- Add up migration script here
+ALTER TABLE users ADD COLUMN deleted_at DATETIME;
+ALTER TABLE users ADD COLUMN recipe_handling TEXT;
+ALTER TABLE users ADD COLUMN recipe_transfer_to BLOB;
+
+CREATE INDEX idx_users_deleted_at ON users(deleted_at);
+
+-- Recipes are rebuilt so that anonymized recipes can outlive their owner. Migrations
+-- run in a transaction with foreign keys on, and dropping the old table would cascade
+-- into ingredients and instructions, so those are moved over to the new table first.
+ALTER TABLE recipes RENAME TO recipes_old;
+
+CREATE TABLE recipes (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    author TEXT,
+    description TEXT,
+    difficulty TEXT,
+    estimated_duration TEXT,
+    is_public BOOLEAN NOT NULL DEFAULT false
+);
+
+INSERT INTO recipes (id, user_id, name, author, description, difficulty, estimated_duration, is_public)
+SELECT id, user_id, name, author, description, difficulty, estimated_duration, is_public
+FROM recipes_old;
+
+CREATE TABLE recipe_ingredients_new (
+    id BLOB PRIMARY KEY NOT NULL,
+    recipe_id BLOB NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    description TEXT NOT NULL
+);
+
+INSERT INTO recipe_ingredients_new (id, recipe_id, position, description)
+SELECT id, recipe_id, position, description
+FROM recipe_ingredients;
+
+CREATE TABLE recipe_instructions_new (
+    id BLOB PRIMARY KEY NOT NULL,
+    recipe_id BLOB NOT NULL REFERENCES recipes(id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    description TEXT NOT NULL
+);
+
+INSERT INTO recipe_instructions_new (id, recipe_id, position, description)
+SELECT id, recipe_id, position, description
+FROM recipe_instructions;
+
+DROP TABLE recipe_ingredients;
+DROP TABLE recipe_instructions;
+DROP TABLE recipes_old;
+
+ALTER TABLE recipe_ingredients_new RENAME TO recipe_ingredients;
+ALTER TABLE recipe_instructions_new RENAME TO recipe_instructions;
@@ -0,0 +1,19 @@
+-- Add up migration script here
+CREATE TABLE api_tokens (
+    id BLOB PRIMARY KEY NOT NULL,
+    user_id BLOB NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL,
+    token_key_id TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME,
+    last_used_at DATETIME
+);
+
+CREATE INDEX idx_api_tokens_user_id ON api_tokens(user_id);
+
+CREATE TABLE api_token_scopes (
+    token_id BLOB NOT NULL REFERENCES api_tokens(id) ON DELETE CASCADE,
+    permission TEXT NOT NULL,
+    PRIMARY KEY (token_id, permission)
+);
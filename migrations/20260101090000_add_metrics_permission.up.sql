@@ -0,0 +1,5 @@
+-- Add up migration script here
+INSERT INTO role_permissions (role_id, permission)
+SELECT id, 'metrics:read'
+FROM roles
+WHERE name = 'Administrator';
@@ -0,0 +1,4 @@
+-- Add down migration script here
+-- The re-enabled accounts cannot be told apart afterwards, and locking them for
+-- good again would bring back the behaviour this migration removed.
+SELECT 1;
@@ -0,0 +1,9 @@
+-- Add up migration script here
+-- The old permanent lockout disabled an account after 5 failed logins without
+-- recording a reason, while administrators always give one. Re-enable those
+-- accounts so that the time-based lockout applies to them instead.
+UPDATE users
+SET is_disabled = FALSE
+WHERE is_disabled = TRUE
+    AND disabled_reason IS NULL
+    AND failed_login_attempts >= 5;
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::roles::Permission;
+
+/// How stale the recorded last use of a token may get before it is written again,
+/// so that a busy script does not cause a database write on every request.
+pub const LAST_USED_PRECISION: Duration = Duration::minutes(1);
+
+/// A personal access token that lets scripts call the API with an
+/// `Authorization: Bearer` header instead of a session cookie. Only an HMAC hash
+/// of the secret is stored, in the same way as session tokens.
+#[derive(Debug, Clone)]
+pub struct ApiToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub token_hash: String,
+    /// The id of the HMAC key the token hash was made with.
+    pub token_key_id: String,
+    /// The permissions the token may use. A request made with the token only gets
+    /// the ones the owner still holds through their roles.
+    pub scopes: Vec<Permission>,
+    pub created_at: OffsetDateTime,
+    /// When the token stops working, or `None` if it never expires.
+    pub expires_at: Option<OffsetDateTime>,
+    pub last_used_at: Option<OffsetDateTime>,
+}
+
+impl ApiToken {
+    pub fn is_expired(&self, now: OffsetDateTime) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Whether the recorded last use is old enough to be updated.
+    pub fn is_last_used_stale(&self, now: OffsetDateTime) -> bool {
+        self.last_used_at
+            .is_none_or(|last_used_at| now - last_used_at >= LAST_USED_PRECISION)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiTokenRequest {
+    /// A label to recognise the token by, e.g. the script that uses it.
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    /// The permissions the token may use. Each must be held by the user creating it.
+    #[validate(length(min = 1))]
+    pub scopes: Vec<Permission>,
+    /// When the token stops working. Leave out for a token that does not expire.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub expires_at: Option<OffsetDateTime>,
+}
+
+/// A token as listed to its owner. The secret is never included.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiTokenResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub scopes: Vec<Permission>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub expires_at: Option<OffsetDateTime>,
+    /// Accurate to about a minute.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_used_at: Option<OffsetDateTime>,
+    pub expired: bool,
+}
+
+impl From<ApiToken> for ApiTokenResponse {
+    fn from(token: ApiToken) -> Self {
+        Self {
+            expired: token.is_expired(OffsetDateTime::now_utc()),
+            id: token.id,
+            name: token.name,
+            scopes: token.scopes,
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+        }
+    }
+}
+
+/// A newly created token. This is the only time the secret is ever shown.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedApiTokenResponse {
+    /// The value to send as `Authorization: Bearer <token>`.
+    pub token: String,
+    #[serde(flatten)]
+    pub details: ApiTokenResponse,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_used_at(last_used_at: Option<OffsetDateTime>) -> ApiToken {
+        ApiToken {
+            id: Uuid::now_v7(),
+            user_id: Uuid::now_v7(),
+            name: "script".into(),
+            token_hash: "hash".into(),
+            token_key_id: "key".into(),
+            scopes: vec![Permission::RecipesRead],
+            created_at: OffsetDateTime::now_utc(),
+            expires_at: None,
+            last_used_at,
+        }
+    }
+
+    #[test]
+    fn test_token_without_expiry_never_expires() {
+        let token = token_used_at(None);
+        assert!(!token.is_expired(OffsetDateTime::now_utc() + Duration::weeks(520)));
+    }
+
+    #[test]
+    fn test_last_used_only_refreshed_after_precision() {
+        let now = OffsetDateTime::now_utc();
+
+        assert!(token_used_at(None).is_last_used_stale(now));
+        assert!(!token_used_at(Some(now - Duration::seconds(10))).is_last_used_stale(now));
+        assert!(token_used_at(Some(now - LAST_USED_PRECISION)).is_last_used_stale(now));
+    }
+}
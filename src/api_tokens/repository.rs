@@ -0,0 +1,215 @@
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{api_tokens::ApiToken, errors::RepositoryError, roles::parse_permissions};
+
+#[async_trait::async_trait]
+pub trait IApiTokenRepository: Send + Sync {
+    /// Store a new token together with its scopes.
+    async fn create(&self, token: &ApiToken) -> Result<(), RepositoryError>;
+
+    async fn get_by_id(&self, id: Uuid) -> Result<ApiToken, RepositoryError>;
+
+    /// Get every token of a user, newest first.
+    async fn get_for_user(&self, user_id: Uuid) -> Result<Vec<ApiToken>, RepositoryError>;
+
+    /// Save when the token was last used and its hash, which is replaced when the
+    /// token is moved to the active HMAC key.
+    async fn record_use(&self, token: &ApiToken) -> Result<(), RepositoryError>;
+
+    /// Delete a token of the given user. `false` means the user has no such token.
+    async fn delete(&self, id: Uuid, user_id: Uuid) -> Result<bool, RepositoryError>;
+
+    /// Delete every token of a user.
+    async fn delete_all_for_user(&self, user_id: Uuid) -> Result<(), RepositoryError>;
+}
+
+pub struct SqlxApiTokenRepository {
+    pub pool: SqlitePool,
+}
+
+impl SqlxApiTokenRepository {
+    pub const fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    async fn get_scopes(&self, token: &mut ApiToken) -> Result<(), RepositoryError> {
+        let names = sqlx::query_scalar!(
+            "SELECT permission FROM api_token_scopes WHERE token_id = ? ORDER BY permission",
+            token.id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        token.scopes = parse_permissions(names);
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl IApiTokenRepository for SqlxApiTokenRepository {
+    async fn create(&self, token: &ApiToken) -> Result<(), RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO api_tokens (
+                id,
+                user_id,
+                name,
+                token_hash,
+                token_key_id,
+                created_at,
+                expires_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+            token.id,
+            token.user_id,
+            token.name,
+            token.token_hash,
+            token.token_key_id,
+            token.created_at,
+            token.expires_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for scope in &token.scopes {
+            let permission = scope.as_str();
+            sqlx::query!(
+                "INSERT OR IGNORE INTO api_token_scopes (token_id, permission) VALUES (?, ?)",
+                token.id,
+                permission
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<ApiToken, RepositoryError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                id AS "id: uuid::Uuid",
+                user_id AS "user_id: uuid::Uuid",
+                name,
+                token_hash,
+                token_key_id,
+                created_at,
+                expires_at,
+                last_used_at
+            FROM api_tokens
+            WHERE id = ?
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or(RepositoryError::NotFound {
+            entity: "API token",
+            property: "id",
+            value: id.to_string(),
+        })?;
+
+        let mut token = ApiToken {
+            id: row.id,
+            user_id: row.user_id,
+            name: row.name,
+            token_hash: row.token_hash,
+            token_key_id: row.token_key_id,
+            scopes: Vec::new(),
+            created_at: row.created_at,
+            expires_at: row.expires_at,
+            last_used_at: row.last_used_at,
+        };
+        self.get_scopes(&mut token).await?;
+
+        Ok(token)
+    }
+
+    async fn get_for_user(&self, user_id: Uuid) -> Result<Vec<ApiToken>, RepositoryError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT
+                id AS "id: uuid::Uuid",
+                user_id AS "user_id: uuid::Uuid",
+                name,
+                token_hash,
+                token_key_id,
+                created_at,
+                expires_at,
+                last_used_at
+            FROM api_tokens
+            WHERE user_id = ?
+            ORDER BY created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut tokens = Vec::with_capacity(rows.len());
+        for row in rows {
+            let mut token = ApiToken {
+                id: row.id,
+                user_id: row.user_id,
+                name: row.name,
+                token_hash: row.token_hash,
+                token_key_id: row.token_key_id,
+                scopes: Vec::new(),
+                created_at: row.created_at,
+                expires_at: row.expires_at,
+                last_used_at: row.last_used_at,
+            };
+            self.get_scopes(&mut token).await?;
+            tokens.push(token);
+        }
+
+        Ok(tokens)
+    }
+
+    async fn record_use(&self, token: &ApiToken) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            r#"
+            UPDATE api_tokens
+            SET last_used_at = ?,
+                token_hash = ?,
+                token_key_id = ?
+            WHERE id = ?
+            "#,
+            token.last_used_at,
+            token.token_hash,
+            token.token_key_id,
+            token.id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid, user_id: Uuid) -> Result<bool, RepositoryError> {
+        let result = sqlx::query!(
+            "DELETE FROM api_tokens WHERE id = ? AND user_id = ?",
+            id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_all_for_user(&self, user_id: Uuid) -> Result<(), RepositoryError> {
+        sqlx::query!("DELETE FROM api_tokens WHERE user_id = ?", user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::HeaderValue,
+    response::IntoResponse,
+    routing::{delete, get},
+};
+use hyper::{HeaderMap, StatusCode, header};
+use uuid::Uuid;
+
+use crate::{
+    api_tokens::{ApiTokenResponse, CreateApiTokenRequest, CreatedApiTokenResponse},
+    errors::ApiError,
+    extractors::{ValidatedJson, authenticated_user::AuthenticatedUser},
+    services::ServiceContainer,
+};
+
+// Clippy lint triggered by utoipa macro expansion, not our code
+#[allow(clippy::needless_for_each)]
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(
+        crate::api_tokens::get_tokens,
+        crate::api_tokens::create_token,
+        crate::api_tokens::revoke_token,
+    ),
+    components(
+        schemas(ApiTokenResponse, CreateApiTokenRequest, CreatedApiTokenResponse)
+    ),
+    tags(
+        (
+            name = "API Tokens",
+            description = "Personal access tokens for scripts and integrations"
+        )
+    )
+)]
+pub struct ApiTokensApiDoc;
+
+pub fn router() -> Router<ServiceContainer> {
+    Router::new()
+        .route("/", get(get_tokens).post(create_token))
+        .route("/{id}", delete(revoke_token))
+}
+
+#[utoipa::path(
+    get,
+    summary = "List API Tokens",
+    path = "/api/auth/tokens",
+    tag = "API Tokens",
+    responses(
+        (status = 200, description = "The current user's API tokens", body = Vec<ApiTokenResponse>),
+        (status = 401, description = "Unauthorized - invalid or expired session"),
+        (status = 403, description = "Forbidden - tokens can only be managed from a session login"),
+    ),
+    description = "Lists the personal access tokens of the current user, newest first, with \
+        their scopes, expiry and when they were last used. The secrets are never returned."
+)]
+pub async fn get_tokens(
+    auth: AuthenticatedUser,
+    State(container): State<ServiceContainer>,
+) -> Result<Json<Vec<ApiTokenResponse>>, ApiError> {
+    auth.require_session()?;
+    let tokens = container
+        .api_token_service()
+        .get_for_user(auth.user.id)
+        .await?;
+    Ok(Json(tokens))
+}
+
+#[utoipa::path(
+    post,
+    summary = "Create API Token",
+    path = "/api/auth/tokens",
+    tag = "API Tokens",
+    request_body = CreateApiTokenRequest,
+    responses(
+        (status = 201, description = "Token created; the secret is only shown in this response",
+            body = CreatedApiTokenResponse,
+            headers(
+                ("Location" = String, description = "URI of the newly created token")
+            )
+        ),
+        (status = 400, description = "Invalid request body, a scope the user does not hold, or an expiry in the past"),
+        (status = 401, description = "Unauthorized - invalid or expired session"),
+        (status = 403, description = "Forbidden - tokens can only be managed from a session login"),
+    ),
+    description = "Creates a personal access token for the current user. Send it as \
+        `Authorization: Bearer <token>` to call the API without a session cookie. Requests \
+        made with the token only get the permissions in its scopes that the user still holds, \
+        and cannot manage tokens, change the password or log out. The token stops working \
+        at expiresAt, when it is revoked, or when the account is disabled or deleted."
+)]
+pub async fn create_token(
+    auth: AuthenticatedUser,
+    State(container): State<ServiceContainer>,
+    ValidatedJson(req): ValidatedJson<CreateApiTokenRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    auth.require_session()?;
+    let created = container
+        .api_token_service()
+        .create(&auth.user, req)
+        .await?;
+
+    let location_str = format!("/auth/tokens/{}", created.details.id);
+    let location = HeaderValue::from_str(&location_str).map_err(|err| anyhow::anyhow!(err))?;
+    let mut headers = HeaderMap::new();
+    headers.insert(header::LOCATION, location);
+    Ok((StatusCode::CREATED, headers, Json(created)))
+}
+
+#[utoipa::path(
+    delete,
+    summary = "Revoke API Token",
+    path = "/api/auth/tokens/{id}",
+    tag = "API Tokens",
+    params(
+        ("id" = Uuid, Path, description = "Unique identifier of the token to revoke")
+    ),
+    responses(
+        (status = 204, description = "Token revoked"),
+        (status = 401, description = "Unauthorized - invalid or expired session"),
+        (status = 403, description = "Forbidden - tokens can only be managed from a session login"),
+        (status = 404, description = "The user has no token with this id"),
+    ),
+    description = "Deletes one of the current user's tokens. Requests made with it are \
+        rejected from then on."
+)]
+pub async fn revoke_token(
+    auth: AuthenticatedUser,
+    Path(id): Path<Uuid>,
+    State(container): State<ServiceContainer>,
+) -> Result<impl IntoResponse, ApiError> {
+    auth.require_session()?;
+    container
+        .api_token_service()
+        .revoke(auth.user.id, id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,248 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+use uuid::Uuid;
+
+use crate::{
+    api_tokens::{
+        ApiToken, ApiTokenResponse, CreateApiTokenRequest, CreatedApiTokenResponse,
+        IApiTokenRepository,
+    },
+    applications::IApplicationRepository,
+    audit::{AuditAction, AuditEvent, IAuditRepository},
+    config::Config,
+    errors::{RepositoryError, ServiceError},
+    extractors::authenticated_user::{AuthenticatedUser, Credential},
+    roles::IRoleRepository,
+    token::{self, ApiTokenSecret},
+    users::{IUserRepository, User, UserResponse},
+};
+
+#[async_trait]
+pub trait IApiTokenService: Send + Sync {
+    /// Get every token of a user, newest first.
+    async fn get_for_user(&self, user_id: Uuid) -> Result<Vec<ApiTokenResponse>, ServiceError>;
+
+    /// Create a token for a user. The scopes must be permissions the user holds.
+    async fn create(
+        &self,
+        user: &UserResponse,
+        request: CreateApiTokenRequest,
+    ) -> Result<CreatedApiTokenResponse, ServiceError>;
+
+    /// Delete one of the user's tokens so it can no longer be used.
+    async fn revoke(&self, user_id: Uuid, token_id: Uuid) -> Result<(), ServiceError>;
+
+    /// Check a bearer token and load its owner, limited to the token's scopes.
+    async fn authenticate(&self, secret: ApiTokenSecret)
+    -> Result<AuthenticatedUser, ServiceError>;
+}
+
+pub struct ApiTokenService {
+    api_tokens: Arc<dyn IApiTokenRepository>,
+    users: Arc<dyn IUserRepository>,
+    roles: Arc<dyn IRoleRepository>,
+    applications: Arc<dyn IApplicationRepository>,
+    audit: Arc<dyn IAuditRepository>,
+    config: Arc<Config>,
+}
+
+impl ApiTokenService {
+    pub fn new(
+        api_token_repo: Arc<dyn IApiTokenRepository>,
+        user_repo: Arc<dyn IUserRepository>,
+        role_repo: Arc<dyn IRoleRepository>,
+        application_repo: Arc<dyn IApplicationRepository>,
+        audit_repo: Arc<dyn IAuditRepository>,
+        config: Arc<Config>,
+    ) -> Self {
+        Self {
+            api_tokens: api_token_repo,
+            users: user_repo,
+            roles: role_repo,
+            applications: application_repo,
+            audit: audit_repo,
+            config,
+        }
+    }
+
+    fn hash(&self, secret: &ApiTokenSecret) -> Result<String, ServiceError> {
+        secret
+            .hash_token(self.config.session_keyring.active_key())
+            .map_err(|_| ServiceError::Internal(anyhow::anyhow!("Failed to hash token")))
+    }
+
+    /// Find the token behind a secret, rejecting unknown, wrong and expired tokens.
+    async fn verify(
+        &self,
+        secret: &ApiTokenSecret,
+        now: OffsetDateTime,
+    ) -> Result<ApiToken, ServiceError> {
+        let token = self
+            .api_tokens
+            .get_by_id(secret.token_id)
+            .await
+            .map_err(|err| {
+                if let RepositoryError::NotFound { .. } = err {
+                    return ServiceError::Unauthorized("unknown API token".into());
+                }
+
+                err.into()
+            })?;
+
+        let key = self
+            .config
+            .session_keyring
+            .get(&token.token_key_id)
+            .ok_or_else(|| ServiceError::Unauthorized("API token key has been retired".into()))?;
+
+        let matches = token::verify_token(&secret.raw_token, &token.token_hash, key)
+            .map_err(|_| ServiceError::Internal(anyhow::anyhow!("Failed to verify token")))?;
+        if !matches {
+            return Err(ServiceError::Unauthorized("invalid API token".into()));
+        }
+
+        if token.is_expired(now) {
+            return Err(ServiceError::Unauthorized("API token is expired".into()));
+        }
+
+        Ok(token)
+    }
+}
+
+#[async_trait]
+impl IApiTokenService for ApiTokenService {
+    async fn get_for_user(&self, user_id: Uuid) -> Result<Vec<ApiTokenResponse>, ServiceError> {
+        let tokens = self.api_tokens.get_for_user(user_id).await?;
+        Ok(tokens.into_iter().map(Into::into).collect())
+    }
+
+    async fn create(
+        &self,
+        user: &UserResponse,
+        request: CreateApiTokenRequest,
+    ) -> Result<CreatedApiTokenResponse, ServiceError> {
+        if let Some(scope) = request
+            .scopes
+            .iter()
+            .find(|scope| !user.has_permission(**scope))
+        {
+            return Err(ServiceError::BadRequest(format!(
+                "cannot grant the `{scope}` permission to a token without holding it"
+            )));
+        }
+
+        let now = OffsetDateTime::now_utc();
+        if request
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= now)
+        {
+            return Err(ServiceError::BadRequest(
+                "expiresAt must be in the future".into(),
+            ));
+        }
+
+        let secret = token::generate_api_token(Uuid::now_v7());
+        let api_token = ApiToken {
+            id: secret.token_id,
+            user_id: user.id,
+            name: request.name.trim().to_string(),
+            token_hash: self.hash(&secret)?,
+            token_key_id: self.config.session_keyring.active_key_id().to_string(),
+            scopes: request.scopes,
+            created_at: now,
+            expires_at: request.expires_at,
+            last_used_at: None,
+        };
+
+        self.api_tokens.create(&api_token).await?;
+        self.audit
+            .record(
+                &AuditEvent::success(AuditAction::ApiTokenCreated)
+                    .target("api_token", api_token.id)
+                    .details(json!({
+                        "name": api_token.name,
+                        "scopes": api_token.scopes,
+                        "expiresAt": api_token.expires_at.and_then(|at| at.format(&Rfc3339).ok()),
+                    })),
+            )
+            .await;
+
+        tracing::info!(user_id = %user.id, token_id = %api_token.id, "API token created");
+
+        Ok(CreatedApiTokenResponse {
+            token: secret.encode(),
+            details: api_token.into(),
+        })
+    }
+
+    async fn revoke(&self, user_id: Uuid, token_id: Uuid) -> Result<(), ServiceError> {
+        if !self.api_tokens.delete(token_id, user_id).await? {
+            return Err(ServiceError::NotFound {
+                entity: "API token",
+                property: "id",
+                value: token_id.to_string(),
+            });
+        }
+
+        self.audit
+            .record(
+                &AuditEvent::success(AuditAction::ApiTokenRevoked).target("api_token", token_id),
+            )
+            .await;
+
+        tracing::info!(user_id = %user_id, token_id = %token_id, "API token revoked");
+
+        Ok(())
+    }
+
+    async fn authenticate(
+        &self,
+        secret: ApiTokenSecret,
+    ) -> Result<AuthenticatedUser, ServiceError> {
+        let now = OffsetDateTime::now_utc();
+        let mut api_token = self.verify(&secret, now).await?;
+
+        let user_base = self
+            .users
+            .get_by_id(api_token.user_id)
+            .await
+            .map_err(|err| {
+                if let RepositoryError::NotFound { .. } = err {
+                    return ServiceError::Unauthorized("owner of API token was not found".into());
+                }
+
+                err.into()
+            })?;
+
+        if user_base.is_disabled {
+            return Err(ServiceError::AccountDisabled);
+        }
+
+        // Tokens hashed with an older key are rehashed straight away so that the old
+        // key can be retired, in the same way session tokens are rotated.
+        let uses_old_key = api_token.token_key_id != self.config.session_keyring.active_key_id();
+        if uses_old_key || api_token.is_last_used_stale(now) {
+            if uses_old_key {
+                api_token.token_hash = self.hash(&secret)?;
+                api_token.token_key_id = self.config.session_keyring.active_key_id().to_string();
+            }
+            api_token.last_used_at = Some(now);
+            self.api_tokens.record_use(&api_token).await?;
+        }
+
+        let mut user: User = user_base.into();
+        user.roles = self.roles.get_by_user_id(user.id).await?;
+        user.permissions = self.roles.get_permissions_for_user(user.id).await?;
+        user.permissions
+            .retain(|permission| api_token.scopes.contains(permission));
+        user.applications = self.applications.get_for_user(user.id).await?;
+
+        Ok(AuthenticatedUser {
+            user: user.into(),
+            credential: Credential::ApiToken(api_token),
+        })
+    }
+}
@@ -0,0 +1,90 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// The applications hosted by the platform. Users only get into an application
+/// once an administrator has enabled it for them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Application {
+    Recipes,
+}
+
+impl Application {
+    pub const ALL: &[Self] = &[Self::Recipes];
+
+    /// Identifier used in URLs and stored in the database.
+    pub const fn slug(self) -> &'static str {
+        match self {
+            Self::Recipes => "recipes",
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Recipes => "Recipes",
+        }
+    }
+
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::Recipes => "Private and shared recipe collections",
+        }
+    }
+}
+
+impl Display for Application {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.slug())
+    }
+}
+
+impl FromStr for Application {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|application| application.slug() == value)
+            .ok_or_else(|| anyhow::anyhow!("unknown application `{value}`"))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApplicationResponse {
+    pub id: Application,
+    pub name: String,
+    pub description: String,
+}
+
+impl From<Application> for ApplicationResponse {
+    fn from(application: Application) -> Self {
+        Self {
+            id: application,
+            name: application.name().to_string(),
+            description: application.description().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slugs_round_trip() {
+        for application in Application::ALL {
+            assert_eq!(
+                application.slug().parse::<Application>().unwrap(),
+                *application
+            );
+            assert_eq!(
+                serde_json::to_string(application).unwrap(),
+                format!("\"{application}\"")
+            );
+        }
+    }
+}
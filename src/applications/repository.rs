@@ -0,0 +1,83 @@
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{applications::Application, errors::RepositoryError};
+
+#[async_trait]
+pub trait IApplicationRepository: Send + Sync {
+    /// Get the applications enabled for a user.
+    async fn get_for_user(&self, user_id: Uuid) -> Result<Vec<Application>, RepositoryError>;
+
+    /// Enable an application for a user. Enabling it twice does nothing.
+    async fn grant(
+        &self,
+        user_id: Uuid,
+        application: Application,
+        granted_by: Uuid,
+    ) -> Result<(), RepositoryError>;
+
+    /// Disable an application for a user.
+    async fn revoke(&self, user_id: Uuid, application: Application) -> Result<(), RepositoryError>;
+}
+
+pub struct SqlxApplicationRepository {
+    pub pool: SqlitePool,
+}
+
+impl SqlxApplicationRepository {
+    pub const fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl IApplicationRepository for SqlxApplicationRepository {
+    async fn get_for_user(&self, user_id: Uuid) -> Result<Vec<Application>, RepositoryError> {
+        let slugs = sqlx::query_scalar!(
+            "SELECT application FROM user_applications WHERE user_id = ? ORDER BY application",
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        // applications that have since been removed are simply skipped
+        Ok(slugs.iter().filter_map(|slug| slug.parse().ok()).collect())
+    }
+
+    async fn grant(
+        &self,
+        user_id: Uuid,
+        application: Application,
+        granted_by: Uuid,
+    ) -> Result<(), RepositoryError> {
+        let slug = application.slug();
+
+        sqlx::query!(
+            r#"INSERT OR IGNORE INTO user_applications (user_id, application, granted_by)
+            VALUES (?, ?, ?)
+            "#,
+            user_id,
+            slug,
+            granted_by
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn revoke(&self, user_id: Uuid, application: Application) -> Result<(), RepositoryError> {
+        let slug = application.slug();
+
+        sqlx::query!(
+            "DELETE FROM user_applications WHERE user_id = ? AND application = ?",
+            user_id,
+            slug
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+use axum::{Json, Router, extract::State, routing::get};
+
+use crate::{
+    applications::{Application, ApplicationResponse},
+    extractors::authenticated_user::AuthenticatedUser,
+    services::ServiceContainer,
+};
+
+pub fn router() -> Router<ServiceContainer> {
+    Router::new().route("/", get(get_all_applications))
+}
+
+// Clippy lint triggered by utoipa macro expansion, not our code
+#[allow(clippy::needless_for_each)]
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(crate::applications::get_all_applications),
+    components(schemas(Application, ApplicationResponse)),
+    tags(
+        (
+            name = "Applications",
+            description = "The applications hosted by the platform"
+        )
+    )
+)]
+pub struct ApplicationsApiDoc;
+
+#[utoipa::path(
+    get,
+    summary = "List Applications",
+    path = "/api/applications",
+    tag = "Applications",
+    responses(
+        (status = 200, description = "Every application hosted by the platform", body = Vec<ApplicationResponse>),
+        (status = 401, description = "Unauthorized"),
+    ),
+    description = "Lists every application hosted by the platform. Which of them the \
+        current user may use is listed in the applications field of /api/auth/me. \
+        Requires a valid session_id cookie."
+)]
+pub async fn get_all_applications(
+    _: AuthenticatedUser,
+    State(container): State<ServiceContainer>,
+) -> Json<Vec<ApplicationResponse>> {
+    Json(container.application_service().get_all())
+}
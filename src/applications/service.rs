@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    applications::{Application, ApplicationResponse, IApplicationRepository},
+    audit::{AuditAction, AuditEvent, IAuditRepository},
+    errors::ServiceError,
+    roles::{IRoleRepository, Permission, ensure_can_delegate},
+    users::IUserRepository,
+};
+
+#[async_trait]
+pub trait IApplicationService: Send + Sync {
+    /// List every application hosted by the platform.
+    fn get_all(&self) -> Vec<ApplicationResponse>;
+
+    /// Enable an application for a user on behalf of an administrator. The change
+    /// applies to the user's next request. Users holding permissions the
+    /// administrator, given as `admin_permissions`, does not hold are refused.
+    async fn grant(
+        &self,
+        user_id: Uuid,
+        application: Application,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError>;
+
+    /// Disable an application for a user on behalf of an administrator.
+    async fn revoke(
+        &self,
+        user_id: Uuid,
+        application: Application,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError>;
+}
+
+pub struct ApplicationService {
+    applications: Arc<dyn IApplicationRepository>,
+    users: Arc<dyn IUserRepository>,
+    roles: Arc<dyn IRoleRepository>,
+    audit: Arc<dyn IAuditRepository>,
+}
+
+impl ApplicationService {
+    pub fn new(
+        application_repo: Arc<dyn IApplicationRepository>,
+        user_repo: Arc<dyn IUserRepository>,
+        role_repo: Arc<dyn IRoleRepository>,
+        audit_repo: Arc<dyn IAuditRepository>,
+    ) -> Self {
+        Self {
+            applications: application_repo,
+            users: user_repo,
+            roles: role_repo,
+            audit: audit_repo,
+        }
+    }
+
+    /// Refuse to act on an account holding permissions the administrator does not.
+    async fn ensure_can_manage(
+        &self,
+        user_id: Uuid,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError> {
+        let permissions = self.roles.get_permissions_for_user(user_id).await?;
+        ensure_can_delegate(admin_permissions, &permissions)
+    }
+}
+
+#[async_trait]
+impl IApplicationService for ApplicationService {
+    fn get_all(&self) -> Vec<ApplicationResponse> {
+        Application::ALL
+            .iter()
+            .copied()
+            .map(ApplicationResponse::from)
+            .collect()
+    }
+
+    async fn grant(
+        &self,
+        user_id: Uuid,
+        application: Application,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError> {
+        // make sure the user exists so a bad id is reported as not found
+        self.users.get_by_id(user_id).await?;
+        self.ensure_can_manage(user_id, admin_permissions).await?;
+        self.applications
+            .grant(user_id, application, admin_id)
+            .await?;
+        self.audit
+            .record(
+                &AuditEvent::success(AuditAction::ApplicationGranted)
+                    .target("user", user_id)
+                    .details(json!({ "application": application })),
+            )
+            .await;
+
+        tracing::info!(
+            user_id = %user_id,
+            admin_id = %admin_id,
+            application = %application,
+            "Application enabled for user"
+        );
+
+        Ok(())
+    }
+
+    async fn revoke(
+        &self,
+        user_id: Uuid,
+        application: Application,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError> {
+        self.users.get_by_id(user_id).await?;
+        self.ensure_can_manage(user_id, admin_permissions).await?;
+        self.applications.revoke(user_id, application).await?;
+        self.audit
+            .record(
+                &AuditEvent::success(AuditAction::ApplicationRevoked)
+                    .target("user", user_id)
+                    .details(json!({ "application": application })),
+            )
+            .await;
+
+        tracing::info!(
+            user_id = %user_id,
+            admin_id = %admin_id,
+            application = %application,
+            "Application disabled for user"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,279 @@
+use std::{fmt::Display, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::request_context::RequestContext;
+
+macro_rules! define_audit_actions {
+    ($($(#[$meta:meta])* $variant:ident => $name:literal),* $(,)?) => {
+        /// Something worth keeping a durable record of.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+        pub enum AuditAction {
+            $(
+                $(#[$meta])*
+                #[serde(rename = $name)]
+                $variant,
+            )*
+        }
+
+        impl AuditAction {
+            #[allow(unused)]
+            pub const ALL: &[Self] = &[$(Self::$variant),*];
+
+            pub const fn as_str(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)*
+                }
+            }
+        }
+
+        impl FromStr for AuditAction {
+            type Err = anyhow::Error;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                match value {
+                    $($name => Ok(Self::$variant),)*
+                    _ => Err(anyhow::anyhow!("unknown audit action `{value}`")),
+                }
+            }
+        }
+    };
+}
+
+define_audit_actions! {
+    /// A login attempt, successful or not.
+    Login => "auth.login",
+    /// An account was locked after repeated failed logins.
+    AccountLocked => "auth.account_locked",
+    Logout => "auth.logout",
+    /// A rotated session token was used after its grace period and the session revoked.
+    SessionReplayed => "auth.session_replayed",
+    UserCreated => "user.created",
+    UserUpdated => "user.updated",
+    /// An account was deleted and will be purged after the retention period.
+    UserDeleted => "user.deleted",
+    UserRestored => "user.restored",
+    /// A user downloaded everything held about them.
+    UserDataExported => "user.data_exported",
+    /// A deleted account was permanently removed.
+    UserPurged => "user.purged",
+    /// An administrator set a user's password.
+    PasswordSet => "user.password_set",
+    /// A user changed their own password.
+    PasswordChanged => "user.password_changed",
+    UserUnlocked => "user.unlocked",
+    UserDisabled => "user.disabled",
+    UserEnabled => "user.enabled",
+    RoleGranted => "user.role_granted",
+    RoleRevoked => "user.role_revoked",
+    ApplicationGranted => "user.application_granted",
+    ApplicationRevoked => "user.application_revoked",
+    ApiTokenCreated => "api_token.created",
+    ApiTokenRevoked => "api_token.revoked",
+    PasswordPolicyUpdated => "password_policy.updated",
+    PasswordResetCodeIssued => "password_reset.code_issued",
+    /// An attempt to redeem a password reset code, successful or not.
+    PasswordResetRedeemed => "password_reset.redeemed",
+    RoleCreated => "role.created",
+    RoleUpdated => "role.updated",
+    RoleDeleted => "role.deleted",
+    RecipeCreated => "recipe.created",
+    RecipeUpdated => "recipe.updated",
+    RecipeDeleted => "recipe.deleted",
+}
+
+impl Display for AuditAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+impl AuditOutcome {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Failure => "failure",
+        }
+    }
+}
+
+/// A new entry for the audit log. The actor and IP address are taken from the
+/// request being handled.
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    pub id: Uuid,
+    pub occurred_at: OffsetDateTime,
+    pub actor_id: Option<Uuid>,
+    pub action: AuditAction,
+    pub target_type: Option<&'static str>,
+    pub target_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub outcome: AuditOutcome,
+    pub details: Option<serde_json::Value>,
+}
+
+impl AuditEvent {
+    pub fn new(action: AuditAction, outcome: AuditOutcome) -> Self {
+        let context = RequestContext::current();
+
+        Self {
+            id: Uuid::now_v7(),
+            occurred_at: OffsetDateTime::now_utc(),
+            actor_id: context.actor_id,
+            action,
+            target_type: None,
+            target_id: None,
+            ip_address: context.ip_address.map(|ip| ip.to_string()),
+            outcome,
+            details: None,
+        }
+    }
+
+    pub fn success(action: AuditAction) -> Self {
+        Self::new(action, AuditOutcome::Success)
+    }
+
+    pub fn failure(action: AuditAction) -> Self {
+        Self::new(action, AuditOutcome::Failure)
+    }
+
+    /// Attribute the event to someone other than the requester, e.g. the user who
+    /// just logged in.
+    #[must_use]
+    pub const fn actor(mut self, actor_id: Uuid) -> Self {
+        self.actor_id = Some(actor_id);
+        self
+    }
+
+    /// The kind and id of the thing that was acted on, e.g. `("user", id)`.
+    #[must_use]
+    pub fn target(mut self, target_type: &'static str, target_id: impl Display) -> Self {
+        self.target_type = Some(target_type);
+        self.target_id = Some(target_id.to_string());
+        self
+    }
+
+    #[must_use]
+    pub fn details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+/// An audit event as stored.
+#[derive(Debug, Clone)]
+pub struct AuditEventRecord {
+    pub id: Uuid,
+    pub occurred_at: OffsetDateTime,
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub outcome: String,
+    pub details: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEventResponse {
+    pub id: Uuid,
+    #[serde(with = "time::serde::rfc3339")]
+    pub occurred_at: OffsetDateTime,
+    pub actor_id: Option<Uuid>,
+    /// One of the [`AuditAction`] names, e.g. `user.created`.
+    pub action: String,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub ip_address: Option<String>,
+    /// `success` or `failure`.
+    pub outcome: String,
+    #[schema(value_type = Option<Object>)]
+    pub details: Option<serde_json::Value>,
+}
+
+impl From<AuditEventRecord> for AuditEventResponse {
+    fn from(record: AuditEventRecord) -> Self {
+        Self {
+            id: record.id,
+            occurred_at: record.occurred_at,
+            actor_id: record.actor_id,
+            action: record.action,
+            target_type: record.target_type,
+            target_id: record.target_id,
+            ip_address: record.ip_address,
+            outcome: record.outcome,
+            details: record.details.map(|details| {
+                serde_json::from_str(&details).unwrap_or(serde_json::Value::String(details))
+            }),
+        }
+    }
+}
+
+/// Which events to return from the audit log. Every filter is optional.
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct AuditFilters {
+    /// Only events caused by this user.
+    pub actor_id: Option<Uuid>,
+    /// Only events of this kind, e.g. `auth.login`.
+    pub action: Option<AuditAction>,
+    /// Only events at or after this time (RFC 3339).
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub from: Option<OffsetDateTime>,
+    /// Only events before this time (RFC 3339).
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub to: Option<OffsetDateTime>,
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(default = "default_page_size")]
+    pub page_size: i64,
+}
+
+const fn default_page() -> i64 {
+    1
+}
+
+const fn default_page_size() -> i64 {
+    50
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_names_round_trip() {
+        for action in AuditAction::ALL {
+            assert_eq!(action.as_str().parse::<AuditAction>().unwrap(), *action);
+            assert_eq!(
+                serde_json::to_string(action).unwrap(),
+                format!("\"{action}\"")
+            );
+        }
+    }
+
+    #[test]
+    fn test_event_outside_request_has_no_actor_or_ip() {
+        let event = AuditEvent::success(AuditAction::UserCreated).target("user", Uuid::nil());
+
+        assert_eq!(event.actor_id, None);
+        assert_eq!(event.ip_address, None);
+        assert_eq!(event.target_type, Some("user"));
+        assert_eq!(
+            event.target_id.as_deref(),
+            Some("00000000-0000-0000-0000-000000000000")
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    audit::{AuditAction, AuditEvent, AuditEventRecord},
+    errors::RepositoryError,
+    shared_models::page_offset,
+};
+
+/// The audit log only ever grows, so there is no way to change or remove events.
+#[async_trait]
+pub trait IAuditRepository: Send + Sync {
+    /// Append an event to the audit log. The audited action has already happened by
+    /// the time it is recorded, so a failure to write the event is logged rather than
+    /// returned.
+    async fn record(&self, event: &AuditEvent);
+
+    /// Get a page of the events matching the filters, newest first, along with the
+    /// total number of matching events.
+    async fn search(
+        &self,
+        actor_id: Option<Uuid>,
+        action: Option<AuditAction>,
+        from: Option<OffsetDateTime>,
+        to: Option<OffsetDateTime>,
+        page: i64,
+        page_size: i64,
+    ) -> Result<(Vec<AuditEventRecord>, i64), RepositoryError>;
+}
+
+pub struct SqlxAuditRepository {
+    pub pool: SqlitePool,
+}
+
+impl SqlxAuditRepository {
+    pub const fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    async fn insert(&self, event: &AuditEvent) -> Result<(), RepositoryError> {
+        let action = event.action.as_str();
+        let outcome = event.outcome.as_str();
+        let details = event.details.as_ref().map(ToString::to_string);
+
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_events (
+                id,
+                occurred_at,
+                actor_id,
+                action,
+                target_type,
+                target_id,
+                ip_address,
+                outcome,
+                details
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+            event.id,
+            event.occurred_at,
+            event.actor_id,
+            action,
+            event.target_type,
+            event.target_id,
+            event.ip_address,
+            outcome,
+            details
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl IAuditRepository for SqlxAuditRepository {
+    async fn record(&self, event: &AuditEvent) {
+        if let Err(err) = self.insert(event).await {
+            tracing::error!(
+                error = %err,
+                event_id = %event.id,
+                action = event.action.as_str(),
+                "Failed to write audit event"
+            );
+        }
+    }
+
+    async fn search(
+        &self,
+        actor_id: Option<Uuid>,
+        action: Option<AuditAction>,
+        from: Option<OffsetDateTime>,
+        to: Option<OffsetDateTime>,
+        page: i64,
+        page_size: i64,
+    ) -> Result<(Vec<AuditEventRecord>, i64), RepositoryError> {
+        let offset = page_offset(page, page_size)?;
+
+        let action = action.map(AuditAction::as_str);
+
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) FROM audit_events
+            WHERE (? IS NULL OR actor_id = ?)
+                AND (? IS NULL OR action = ?)
+                AND (? IS NULL OR occurred_at >= ?)
+                AND (? IS NULL OR occurred_at < ?)
+            "#,
+            actor_id,
+            actor_id,
+            action,
+            action,
+            from,
+            from,
+            to,
+            to
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let events = sqlx::query_as!(
+            AuditEventRecord,
+            r#"
+            SELECT
+                id AS "id: uuid::Uuid",
+                occurred_at AS "occurred_at: time::OffsetDateTime",
+                actor_id AS "actor_id: uuid::Uuid",
+                action,
+                target_type,
+                target_id,
+                ip_address,
+                outcome,
+                details
+            FROM audit_events
+            WHERE (? IS NULL OR actor_id = ?)
+                AND (? IS NULL OR action = ?)
+                AND (? IS NULL OR occurred_at >= ?)
+                AND (? IS NULL OR occurred_at < ?)
+            ORDER BY occurred_at DESC, id DESC
+            LIMIT ? OFFSET ?
+            "#,
+            actor_id,
+            actor_id,
+            action,
+            action,
+            from,
+            from,
+            to,
+            to,
+            page_size,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok((events, total))
+    }
+}
@@ -0,0 +1,57 @@
+use axum::{
+    Json, Router,
+    extract::{Query, State},
+    routing::get,
+};
+
+use crate::{
+    audit::{AuditAction, AuditEventResponse, AuditFilters, AuditOutcome},
+    errors::ApiError,
+    extractors::RequirePermission,
+    roles::permissions::AuditRead,
+    services::ServiceContainer,
+    shared_models::PaginatedResponse,
+};
+
+// Clippy lint triggered by utoipa macro expansion, not our code
+#[allow(clippy::needless_for_each)]
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(crate::audit::search_audit_events),
+    components(schemas(AuditEventResponse, AuditAction, AuditOutcome)),
+    tags(
+        (
+            name = "Audit",
+            description = "Durable record of security-relevant and administrative actions"
+        )
+    )
+)]
+pub struct AuditApiDoc;
+
+pub fn router() -> Router<ServiceContainer> {
+    Router::new().route("/", get(search_audit_events))
+}
+
+#[utoipa::path(
+    get,
+    summary = "Search Audit Log",
+    path = "/api/audit",
+    tag = "Audit",
+    params(AuditFilters),
+    responses(
+        (status = 200, description = "A page of audit events, newest first"),
+        (status = 400, description = "Invalid filters or page size"),
+        (status = 401, description = "Unauthorized - invalid or expired session"),
+        (status = 403, description = "Forbidden - requires the audit:read permission"),
+    ),
+    description = "Searches the audit log of logins, account changes and other administrative \
+        actions. Events can be filtered by the user who caused them, by action and by time range."
+)]
+pub async fn search_audit_events(
+    _: RequirePermission<AuditRead>,
+    State(container): State<ServiceContainer>,
+    Query(filters): Query<AuditFilters>,
+) -> Result<Json<PaginatedResponse<AuditEventResponse>>, ApiError> {
+    let events = container.audit_service().search(filters).await?;
+    Ok(Json(events))
+}
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use time::UtcOffset;
+
+use crate::{
+    audit::{AuditEventResponse, AuditFilters, IAuditRepository},
+    errors::ServiceError,
+    shared_models::{PaginatedResponse, check_page_size},
+};
+
+/// Largest page of audit events that can be requested at once.
+const MAX_PAGE_SIZE: i64 = 200;
+
+#[async_trait]
+pub trait IAuditService: Send + Sync {
+    /// Search the audit log, newest events first.
+    async fn search(
+        &self,
+        filters: AuditFilters,
+    ) -> Result<PaginatedResponse<AuditEventResponse>, ServiceError>;
+}
+
+pub struct AuditService {
+    audit: Arc<dyn IAuditRepository>,
+}
+
+impl AuditService {
+    pub fn new(audit_repo: Arc<dyn IAuditRepository>) -> Self {
+        Self { audit: audit_repo }
+    }
+}
+
+#[async_trait]
+impl IAuditService for AuditService {
+    async fn search(
+        &self,
+        filters: AuditFilters,
+    ) -> Result<PaginatedResponse<AuditEventResponse>, ServiceError> {
+        let AuditFilters {
+            actor_id,
+            action,
+            from,
+            to,
+            page,
+            page_size,
+        } = filters;
+
+        check_page_size(page_size, MAX_PAGE_SIZE)?;
+
+        // events are stored in UTC, which keeps the stored timestamps comparable
+        let from = from.map(|from| from.to_offset(UtcOffset::UTC));
+        let to = to.map(|to| to.to_offset(UtcOffset::UTC));
+
+        let (events, total) = self
+            .audit
+            .search(actor_id, action, from, to, page, page_size)
+            .await?;
+
+        Ok(PaginatedResponse::new(
+            events.into_iter().map(AuditEventResponse::from).collect(),
+            page,
+            page_size,
+            total,
+        ))
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
 use utoipa::ToSchema;
 
 use crate::{extractors::authenticated_user::AuthenticatedUser, sessions::Session, users::User};
@@ -15,15 +16,138 @@ pub struct LoginDetails {
     pub user: User,
 }
 
-impl From<LoginDetails> for AuthenticatedUser {
-    fn from(value: LoginDetails) -> Self {
-        Self {
-            user: value.user.into(),
-            session: value.session,
+/// How long an account stays locked after repeated failed logins.
+///
+/// Reaching `threshold` consecutive failures locks the account for `base_duration`,
+/// and every further failure after the lock runs out doubles the window, up to
+/// `max_duration`. The lock lifts by itself once the window has passed.
+#[derive(Debug, Clone, Copy)]
+pub struct LockoutPolicy {
+    pub threshold: i64,
+    pub base_duration: Duration,
+    pub max_duration: Duration,
+}
+
+impl LockoutPolicy {
+    /// The lockout window for the given number of consecutive failures, if any.
+    pub fn window(&self, failed_attempts: i64) -> Option<Duration> {
+        let excess = failed_attempts.checked_sub(self.threshold)?;
+        if excess < 0 {
+            return None;
         }
+
+        let factor = u32::try_from(excess)
+            .ok()
+            .and_then(|excess| 2_i32.checked_pow(excess))
+            .unwrap_or(i32::MAX);
+
+        Some(
+            self.base_duration
+                .checked_mul(factor)
+                .map_or(self.max_duration, |window| window.min(self.max_duration)),
+        )
     }
+
+    /// Every distinct lockout window, each with the failure count it starts at. The
+    /// last window also applies to every higher count.
+    pub fn windows(&self) -> Vec<(i64, Duration)> {
+        let mut windows: Vec<(i64, Duration)> = Vec::new();
+        // the doubling factor saturates after 31 doublings, so later counts add nothing
+        for excess in 0..=31 {
+            let Some(failed_attempts) = self.threshold.checked_add(excess) else {
+                break;
+            };
+            let Some(window) = self.window(failed_attempts) else {
+                continue;
+            };
+            if windows.last().is_some_and(|&(_, last)| last == window) {
+                continue;
+            }
+            windows.push((failed_attempts, window));
+            if window >= self.max_duration {
+                break;
+            }
+        }
+        windows
+    }
+
+    /// When the account unlocks, or `None` if it is not locked at `now`.
+    pub fn locked_until(
+        &self,
+        failed_attempts: i64,
+        last_failed_attempt: Option<OffsetDateTime>,
+        now: OffsetDateTime,
+    ) -> Option<OffsetDateTime> {
+        let until = last_failed_attempt?.saturating_add(self.window(failed_attempts)?);
+        (until > now).then_some(until)
+    }
+}
+
+/// The outcome of validating a session cookie during refresh.
+#[derive(Debug, Clone)]
+pub struct RefreshedSession {
+    pub auth_user: AuthenticatedUser,
+    /// Whether the client should be sent the token of the session in `auth_user`.
+    /// This is false when a superseded token was presented within the grace period,
+    /// since re-sending it would overwrite the rotated cookie in the browser.
+    pub reissue_cookie: bool,
 }
 
 /// Marker indicating the handler explicitly managed the session cookie
 #[derive(Debug, Clone, Copy)]
 pub struct SessionCookieHandled;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POLICY: LockoutPolicy = LockoutPolicy {
+        threshold: 5,
+        base_duration: Duration::minutes(1),
+        max_duration: Duration::minutes(60),
+    };
+
+    #[test]
+    fn test_no_lockout_below_threshold() {
+        assert_eq!(POLICY.window(0), None);
+        assert_eq!(POLICY.window(4), None);
+    }
+
+    #[test]
+    fn test_lockout_window_doubles_up_to_max() {
+        assert_eq!(POLICY.window(5), Some(Duration::minutes(1)));
+        assert_eq!(POLICY.window(6), Some(Duration::minutes(2)));
+        assert_eq!(POLICY.window(8), Some(Duration::minutes(8)));
+        assert_eq!(POLICY.window(11), Some(Duration::minutes(60)));
+        assert_eq!(POLICY.window(i64::MAX), Some(Duration::minutes(60)));
+    }
+
+    #[test]
+    fn test_windows_lists_each_distinct_window() {
+        assert_eq!(
+            POLICY.windows(),
+            vec![
+                (5, Duration::minutes(1)),
+                (6, Duration::minutes(2)),
+                (7, Duration::minutes(4)),
+                (8, Duration::minutes(8)),
+                (9, Duration::minutes(16)),
+                (10, Duration::minutes(32)),
+                (11, Duration::minutes(60)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lock_expires_after_window() {
+        let now = OffsetDateTime::now_utc();
+        let failed_at = now - Duration::seconds(90);
+
+        assert_eq!(
+            POLICY.locked_until(6, Some(failed_at), now),
+            Some(failed_at + Duration::minutes(2))
+        );
+        assert_eq!(POLICY.locked_until(5, Some(failed_at), now), None);
+        assert_eq!(POLICY.locked_until(6, None, now), None);
+    }
+}
@@ -62,12 +62,15 @@ impl IAuthenticationRepository for SqlxAuthenticationRepository {
         );
 
         sqlx::query!(
-            r#"INSERT INTO sessions (id, token, user_id, expires_at)
-    VALUES (?, ?, ?, ?)"#,
+            r#"INSERT INTO sessions (id, token, token_key_id, user_id, expires_at, rotated_at, password_change_required)
+    VALUES (?, ?, ?, ?, ?, ?, ?)"#,
             session.id,
             session.token,
+            session.token_key_id,
             session.user_id,
-            session.expires_at
+            session.expires_at,
+            session.rotated_at,
+            session.password_change_required
         )
         .execute(&mut *tx)
         .await?;
@@ -83,7 +86,8 @@ impl IAuthenticationRepository for SqlxAuthenticationRepository {
             r#"
             SELECT
                 r.id AS "id: uuid::Uuid",
-                r.name
+                r.name,
+                r.is_system
             FROM roles r
             INNER JOIN user_roles ur
                 ON r.id = ur.role_id
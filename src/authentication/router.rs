@@ -1,8 +1,10 @@
 use crate::{
-    authentication::{LoginRequest, SessionCookieHandled},
+    authentication::{LoginDetails, LoginRequest, SessionCookieHandled},
     cookies,
-    errors::ApiError,
-    extractors::authenticated_user::AuthenticatedUser,
+    errors::{ApiError, ServiceError},
+    extractors::{JsonBody, authenticated_user::AuthenticatedUser},
+    metrics::LoginOutcome,
+    request_context::RequestContext,
     services::ServiceContainer,
     users::UserResponse,
 };
@@ -13,10 +15,7 @@ use axum::{
     response::{IntoResponse, Response},
     routing::{get, post},
 };
-use axum_extra::extract::{
-    CookieJar,
-    cookie::{self, Cookie},
-};
+use axum_extra::extract::CookieJar;
 use hyper::StatusCode;
 
 // Clippy lint triggered by utoipa macro expansion, not our code
@@ -59,18 +58,12 @@ pub fn router() -> Router<ServiceContainer> {
     description = "Retrieves the currently authenticated user's details and refreshes \
                   their session cookie. This endpoint is typically called when the \
                   client application loads or refreshes to restore user state from \
-                  the session cookie."
+                  the session cookie. The response lists the user's permissions and \
+                  the applications enabled for them."
 )]
 pub async fn refresh(auth: AuthenticatedUser) -> Result<impl IntoResponse, ApiError> {
-    let cookie = Cookie::build(("session_id", auth.session.token))
-        .path("/")
-        .http_only(true)
-        .secure(true)
-        .same_site(cookie::SameSite::Strict)
-        .expires(auth.session.expires_at)
-        .build();
-    let jar = CookieJar::new().add(cookie);
-    Ok((jar, Json(auth.user)))
+    // The auth middleware refreshes (and rotates) the session cookie on every request.
+    Ok(Json(auth.user))
 }
 
 #[utoipa::path(
@@ -82,23 +75,68 @@ pub async fn refresh(auth: AuthenticatedUser) -> Result<impl IntoResponse, ApiEr
     responses(
         (status = 200, description = "Login successful", body = UserResponse),
         (status = 400, description = "Invalid request body"),
-        (status = 401, description = "Invalid username or password"),
+        (status = 401, description = "Login refused, with the code `invalid_credentials`, \
+                                      `account_locked` (with `retryAfter` and a `Retry-After` \
+                                      header) or `account_disabled`"),
+        (status = 429, description = "Too many login attempts from this address or for this \
+                                      username; retry after the Retry-After header's seconds"),
     ),
     description = "Authenticates a user with their username and password. On successful \
                   authentication, creates a new session and returns a secure, HTTP-only \
                   session cookie along with the user's details. The session cookie is \
-                  used for all subsequent authenticated requests."
+                  used for all subsequent authenticated requests. A readable csrf_token \
+                  cookie is set alongside it, and its value must be sent in the \
+                  X-CSRF-Token header of every POST, PUT, PATCH and DELETE request made \
+                  with the session; requests without it are only accepted when their \
+                  Origin or Referer header names this server, and are otherwise refused \
+                  with 403 Forbidden. Repeated failed \
+                  attempts lock the account for a period that grows with every further \
+                  failure; an administrator can lift the lock early. Until the right \
+                  password is given, a locked account is refused as invalid credentials. \
+                  Login attempts are also rate limited per client address and per username, answered with \
+                  429 Too Many Requests and a Retry-After header. If the password has \
+                  expired (passwordExpired is true), the session may only change the \
+                  password, fetch the current user, or log out until the password is \
+                  changed; every other endpoint responds with 403 Forbidden."
 )]
 pub async fn login(
     State(container): State<ServiceContainer>,
-    Json(login): Json<LoginRequest>,
+    JsonBody(login): JsonBody<LoginRequest>,
 ) -> Result<Response<Body>, ApiError> {
-    let AuthenticatedUser { user, session } = container.auth_service().login(login).await?;
+    let metrics = container.metrics();
+
+    container
+        .login_limiter()
+        .check(RequestContext::current().ip_address, &login.username)
+        .map_err(|(limit, retry_after)| {
+            metrics.record_login(LoginOutcome::RateLimited);
+            // the username is left out of the logs, since it could be a mistyped password
+            ApiError::TooManyRequests {
+                reason: format!("too many login attempts {limit}"),
+                retry_after,
+            }
+        })?;
+
+    let result = container.auth_service().login(login).await;
+    let outcome = match &result {
+        Ok(_) => Some(LoginOutcome::Success),
+        Err(ServiceError::InvalidUsernameOrPassword) => Some(LoginOutcome::InvalidCredentials),
+        Err(ServiceError::AccountLocked { .. }) => Some(LoginOutcome::Locked),
+        Err(ServiceError::AccountDisabled) => Some(LoginOutcome::Disabled),
+        Err(_) => None,
+    };
+    if let Some(outcome) = outcome {
+        metrics.record_login(outcome);
+    }
+
+    let LoginDetails { user, session } = result?;
 
     let cookie = cookies::build_session_cookie(session.token, session.expires_at);
-    let jar = CookieJar::new().add(cookie);
+    let csrf_cookie =
+        cookies::build_csrf_cookie(cookies::generate_csrf_token(), session.expires_at);
+    let jar = CookieJar::new().add(cookie).add(csrf_cookie);
     // Build response manually so we can attach extensions
-    let mut response = (StatusCode::OK, jar, Json(user)).into_response();
+    let mut response = (StatusCode::OK, jar, Json(UserResponse::from(user))).into_response();
 
     response.extensions_mut().insert(SessionCookieHandled);
 
@@ -113,6 +151,7 @@ pub async fn login(
     responses(
         (status = 200, description = "Logout successful"),
         (status = 401, description = "Unauthorized - no valid session"),
+        (status = 403, description = "Forbidden - API tokens cannot log out"),
     ),
     description = "Logs out the currently authenticated user by invalidating their session \
                   and clearing the session cookie. The session is removed from the server \
@@ -123,11 +162,16 @@ pub async fn logout(
     auth: AuthenticatedUser,
     State(container): State<ServiceContainer>,
 ) -> Result<Response<Body>, ApiError> {
-    container.auth_service().logout(auth.session.id).await?;
+    container
+        .auth_service()
+        .logout(auth.require_session()?.id)
+        .await?;
 
     let cookie = cookies::build_expired_session_cookie();
 
-    let cookie_jar = CookieJar::new().add(cookie);
+    let cookie_jar = CookieJar::new()
+        .add(cookie)
+        .add(cookies::build_expired_csrf_cookie());
 
     // Build response manually so we can attach extensions
     let mut response = (
@@ -1,26 +1,27 @@
 use std::sync::Arc;
 
+use serde_json::json;
 use time::{Duration, OffsetDateTime};
 use uuid::Uuid;
 
 use crate::{
-    authentication::{IAuthenticationRepository, LoginRequest},
+    applications::IApplicationRepository,
+    audit::{AuditAction, AuditEvent, IAuditRepository},
+    authentication::{IAuthenticationRepository, LoginDetails, LoginRequest, RefreshedSession},
+    config::Config,
     errors::{RepositoryError, ServiceError},
-    extractors::authenticated_user::AuthenticatedUser,
+    extractors::authenticated_user::{AuthenticatedUser, Credential},
     roles::IRoleRepository,
     sessions::{ISessionRepository, Session},
     token::{self, SessionToken},
     users::{IUserRepository, Password, User},
 };
 
-const MAX_FAILED_LOGIN_ATTEMPTS: i64 = 5;
-
 #[async_trait::async_trait]
 pub trait IAuthenticationService: Send + Sync {
-    async fn login(&self, request: LoginRequest) -> Result<AuthenticatedUser, ServiceError>;
+    async fn login(&self, request: LoginRequest) -> Result<LoginDetails, ServiceError>;
     async fn logout(&self, session_id: Uuid) -> Result<(), ServiceError>;
-    async fn refresh(&self, session_token: SessionToken)
-    -> Result<AuthenticatedUser, ServiceError>;
+    async fn refresh(&self, session_token: SessionToken) -> Result<RefreshedSession, ServiceError>;
 }
 
 #[derive(Clone)]
@@ -29,6 +30,9 @@ pub struct AuthenticationService {
     users: Arc<dyn IUserRepository>,
     roles: Arc<dyn IRoleRepository>,
     sessions: Arc<dyn ISessionRepository>,
+    applications: Arc<dyn IApplicationRepository>,
+    audit: Arc<dyn IAuditRepository>,
+    config: Arc<Config>,
 }
 
 impl AuthenticationService {
@@ -37,98 +41,208 @@ impl AuthenticationService {
         user_repo: Arc<dyn IUserRepository>,
         role_repo: Arc<dyn IRoleRepository>,
         session_repo: Arc<dyn ISessionRepository>,
+        application_repo: Arc<dyn IApplicationRepository>,
+        audit_repo: Arc<dyn IAuditRepository>,
+        config: Arc<Config>,
     ) -> Self {
         Self {
             authentication: auth_repo,
             users: user_repo,
             roles: role_repo,
             sessions: session_repo,
+            applications: application_repo,
+            audit: audit_repo,
+            config,
+        }
+    }
+
+    async fn audit_failed_login(&self, user_id: Uuid, reason: &str) {
+        self.audit
+            .record(
+                &AuditEvent::failure(AuditAction::Login)
+                    .target("user", user_id)
+                    .details(json!({ "reason": reason })),
+            )
+            .await;
+    }
+
+    /// Count a wrong password towards the lockout, locking the account once too many
+    /// have been tried, and return the error to refuse the login with. A new lock is
+    /// not reported, since a wrong password must not reveal that the username exists.
+    async fn refuse_wrong_password(&self, user_id: Uuid, now: OffsetDateTime) -> ServiceError {
+        let failed_login_attempts = match self.users.record_failed_login(user_id, now).await {
+            Ok(failed_login_attempts) => failed_login_attempts,
+            Err(err) => return err.into(),
+        };
+        self.audit
+            .record(
+                &AuditEvent::failure(AuditAction::Login)
+                    .target("user", user_id)
+                    .details(json!({
+                        "reason": "invalid password",
+                        "failedLoginAttempts": failed_login_attempts,
+                    })),
+            )
+            .await;
+
+        if let Some(window) = self.config.lockout.window(failed_login_attempts) {
+            tracing::warn!(
+                user_id = %user_id,
+                failed_login_attempts,
+                lockout_seconds = window.whole_seconds(),
+                "Security event: account locked after repeated failed logins"
+            );
+            self.audit
+                .record(
+                    &AuditEvent::success(AuditAction::AccountLocked)
+                        .target("user", user_id)
+                        .details(json!({
+                            "failedLoginAttempts": failed_login_attempts,
+                            "lockoutSeconds": window.whole_seconds(),
+                        })),
+                )
+                .await;
         }
+
+        ServiceError::InvalidUsernameOrPassword
+    }
+
+    /// Replace the session's token with a new secret hashed with the active key,
+    /// keeping the presented token valid as the previous token for the grace period.
+    ///
+    /// Returns `None` when a concurrent request already rotated the token.
+    async fn rotate_session_token(
+        &self,
+        session: &mut Session,
+        presented_token: &SessionToken,
+        now: OffsetDateTime,
+    ) -> Result<Option<SessionToken>, ServiceError> {
+        let active_key = self.config.session_keyring.active_key();
+        let rotated_token = token::generate_session_token(session.id);
+        let rotated_hash = rotated_token
+            .hash_token(active_key)
+            .map_err(|_| ServiceError::Internal(anyhow::anyhow!("Failed to hash token")))?;
+        let previous_hash = presented_token
+            .hash_token(active_key)
+            .map_err(|_| ServiceError::Internal(anyhow::anyhow!("Failed to hash token")))?;
+
+        let expected_token = std::mem::replace(&mut session.token, rotated_hash);
+        session.token_key_id = self.config.session_keyring.active_key_id().to_string();
+        session.previous_token = Some(previous_hash);
+        session.rotated_at = Some(now);
+
+        let rotated = self.sessions.rotate_token(session, &expected_token).await?;
+        Ok(rotated.then_some(rotated_token))
     }
 }
 
 #[async_trait::async_trait]
 impl IAuthenticationService for AuthenticationService {
-    async fn login(&self, request: LoginRequest) -> Result<AuthenticatedUser, ServiceError> {
-        let mut user_base = self
-            .users
-            .get_by_username(&request.username)
-            .await
-            .map_err(|err| {
-                if let RepositoryError::NotFound { .. } = err {
-                    // generate random password hash and verify against input to prevent timing attacks.
-                    let fake_pw = Password::new("fake password");
-                    if let Ok(pw) = fake_pw {
-                        _ = pw.verify(request.password.as_bytes());
-                    }
-
-                    return ServiceError::InvalidUsernameOrPassword;
+    async fn login(&self, request: LoginRequest) -> Result<LoginDetails, ServiceError> {
+        let mut user_base = match self.users.get_by_username(&request.username).await {
+            Ok(user_base) => user_base,
+            Err(RepositoryError::NotFound { .. }) => {
+                // generate random password hash and verify against input to prevent timing attacks.
+                let fake_pw = Password::new("fake password");
+                if let Ok(pw) = fake_pw {
+                    _ = pw.verify(request.password.as_bytes());
                 }
 
-                err.into()
-            })?;
+                // the username is left out, since it could be a mistyped password
+                self.audit
+                    .record(
+                        &AuditEvent::failure(AuditAction::Login)
+                            .details(json!({ "reason": "unknown username" })),
+                    )
+                    .await;
 
+                return Err(ServiceError::InvalidUsernameOrPassword);
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let now = OffsetDateTime::now_utc();
         let is_valid = user_base.password_hash.verify(request.password.as_bytes());
+
+        // While locked, even the right password is refused, and failures are not counted
+        // so that the lock cannot be extended by hammering the account. Like a disabled
+        // account, the lock is only revealed to someone who knows the password, so that
+        // a locked username cannot be told apart from an unknown one.
+        if let Some(locked_until) = self.config.lockout.locked_until(
+            user_base.failed_login_attempts,
+            user_base.last_failed_login_attempt,
+            now,
+        ) {
+            tracing::info!(
+                user_id = %user_base.id,
+                locked_until = %locked_until,
+                "Login attempt on locked account"
+            );
+            self.audit_failed_login(user_base.id, "account locked")
+                .await;
+            if !is_valid {
+                return Err(ServiceError::InvalidUsernameOrPassword);
+            }
+            return Err(ServiceError::AccountLocked { locked_until });
+        }
+
         if is_valid {
-            user_base.last_login = Some(OffsetDateTime::now_utc());
+            // only reveal that the account is disabled to someone who knows the password
+            if user_base.is_disabled {
+                self.audit_failed_login(user_base.id, "account disabled")
+                    .await;
+                return Err(ServiceError::AccountDisabled);
+            }
+
+            user_base.last_login = Some(now);
             user_base.failed_login_attempts = 0;
             user_base.last_failed_login_attempt = None;
-            user_base.updated_at = OffsetDateTime::now_utc();
+            user_base.updated_at = now;
 
             let session_id = Uuid::now_v7();
             let session_token = token::generate_session_token(session_id);
 
-            // Get HMAC key and hash the token for storage
-            let hmac_key_hex = std::env::var("SESSION_HMAC_KEY")
-                .map_err(|_| ServiceError::Internal(anyhow::anyhow!("HMAC key not configured")))?;
-            let hmac_key = hex::decode(hmac_key_hex)
-                .map_err(|_| ServiceError::Internal(anyhow::anyhow!("Invalid HMAC key format")))?;
-
+            // Hash the token for storage with the active HMAC key
             let token_hash = session_token
-                .hash_token(&hmac_key)
+                .hash_token(self.config.session_keyring.active_key())
                 .map_err(|_| ServiceError::Internal(anyhow::anyhow!("Failed to hash token")))?;
 
-            let session = Session::new(session_id, user_base.id, token_hash);
-
-            let user = self.authentication.login(user_base, session).await?;
-            let mut auth_user: AuthenticatedUser = user.into();
-            auth_user.session.token = session_token.encode();
-            return Ok(auth_user);
+            let mut session = Session::new(
+                session_id,
+                user_base.id,
+                token_hash,
+                self.config.session_keyring.active_key_id().to_string(),
+            );
+
+            // An expired password still lets the user in, but only to change it
+            session.password_change_required = user_base.is_password_expired(now);
+
+            let mut user = self.authentication.login(user_base, session).await?;
+            self.audit
+                .record(
+                    &AuditEvent::success(AuditAction::Login)
+                        .actor(user.user.id)
+                        .target("user", user.user.id),
+                )
+                .await;
+            user.user.permissions = self.roles.get_permissions_for_user(user.user.id).await?;
+            user.user.applications = self.applications.get_for_user(user.user.id).await?;
+            user.session.token = session_token.encode();
+            return Ok(user);
         }
 
-        user_base.failed_login_attempts += 1;
-        user_base.last_failed_login_attempt = Some(OffsetDateTime::now_utc());
-        user_base.updated_at = OffsetDateTime::now_utc();
-
-        if user_base.failed_login_attempts >= MAX_FAILED_LOGIN_ATTEMPTS {
-            user_base.is_disabled = true;
-            self.sessions.delete_all_for_user(user_base.id).await?;
-        }
-
-        self.users.update_base(&user_base).await?;
-
-        if user_base.is_disabled {
-            return Err(ServiceError::AccountLocked);
-        }
-
-        return Err(ServiceError::InvalidUsernameOrPassword);
+        Err(self.refuse_wrong_password(user_base.id, now).await)
     }
 
     async fn logout(&self, session_id: Uuid) -> Result<(), ServiceError> {
         self.sessions.delete(session_id).await?;
+        self.audit
+            .record(&AuditEvent::success(AuditAction::Logout).target("session", session_id))
+            .await;
         Ok(())
     }
 
-    async fn refresh(
-        &self,
-        session_token: SessionToken,
-    ) -> Result<AuthenticatedUser, ServiceError> {
-        // Get the HMAC key from environment
-        let hmac_key_hex = std::env::var("SESSION_HMAC_KEY")
-            .map_err(|_| ServiceError::Internal(anyhow::anyhow!("HMAC key not configured")))?;
-        let hmac_key = hex::decode(hmac_key_hex)
-            .map_err(|_| ServiceError::Internal(anyhow::anyhow!("Invalid HMAC key format")))?;
-
+    async fn refresh(&self, session_token: SessionToken) -> Result<RefreshedSession, ServiceError> {
         // get a session
         let mut session = self
             .sessions
@@ -142,20 +256,57 @@ impl IAuthenticationService for AuthenticationService {
                 err.into()
             })?;
 
-        // Verify the token hash matches what's stored
-        let is_valid = crate::token::verify_token(
-            &session_token.raw_token,
-            &session.token, // You'll need to add this field to your Session struct
-            &hmac_key,
-        )
-        .map_err(|_| ServiceError::Unauthorized("token verification failed".into()))?;
+        // Verify with whichever key the session was hashed with
+        let Some(hmac_key) = self.config.session_keyring.get(&session.token_key_id) else {
+            return Err(ServiceError::Unauthorized(format!(
+                "session token was hashed with retired HMAC key `{}`",
+                session.token_key_id
+            )));
+        };
+
+        let now = OffsetDateTime::now_utc();
+
+        // Verify the token hash matches what's stored, falling back to the token
+        // that was replaced by the most recent rotation.
+        let matches_current =
+            token::verify_token(&session_token.raw_token, &session.token, hmac_key)
+                .map_err(|_| ServiceError::Unauthorized("token verification failed".into()))?;
+
+        let matches_previous = match (&session.previous_token, matches_current) {
+            (Some(previous_token), false) => {
+                token::verify_token(&session_token.raw_token, previous_token, hmac_key)
+                    .map_err(|_| ServiceError::Unauthorized("token verification failed".into()))?
+            }
+            _ => false,
+        };
 
-        if !is_valid {
+        if !matches_current && !matches_previous {
             return Err(ServiceError::Unauthorized("invalid session token".into()));
         }
 
+        // A rotated token showing up after the grace period means two parties hold
+        // copies of the same session, so neither of them can be trusted.
+        if matches_previous && !session.is_within_grace_period(now) {
+            tracing::warn!(
+                session_id = %session.id,
+                user_id = %session.user_id,
+                "Security event: rotated session token replayed, revoking session"
+            );
+            self.sessions.delete(session.id).await?;
+            self.audit
+                .record(
+                    &AuditEvent::failure(AuditAction::SessionReplayed)
+                        .actor(session.user_id)
+                        .target("session", session.id),
+                )
+                .await;
+            return Err(ServiceError::Unauthorized(
+                "rotated session token was replayed".into(),
+            ));
+        }
+
         // validate it's not expired
-        if session.expires_at < OffsetDateTime::now_utc() {
+        if session.expires_at < now {
             return Err(ServiceError::Unauthorized("session is expired".into()));
         }
 
@@ -171,14 +322,34 @@ impl IAuthenticationService for AuthenticationService {
         })?;
 
         if user_base.is_disabled {
-            return Err(ServiceError::AccountLocked);
+            return Err(ServiceError::AccountDisabled);
         }
 
         // update session expires_at
-        session.expires_at = OffsetDateTime::now_utc().saturating_add(Duration::hours(2));
-
-        // tell repo to save the details
-        self.sessions.update(&session).await?;
+        session.expires_at = now.saturating_add(Duration::hours(2));
+
+        let mut cookie_token = session_token;
+        let mut reissue_cookie = matches_current;
+
+        // Sessions hashed with an older key are rotated straight away so that both
+        // hashes move to the active key and the old key can be retired.
+        let uses_old_key = session.token_key_id != self.config.session_keyring.active_key_id();
+
+        if matches_current && (uses_old_key || session.is_rotation_due(now)) {
+            if let Some(rotated_token) = self
+                .rotate_session_token(&mut session, &cookie_token, now)
+                .await?
+            {
+                cookie_token = rotated_token;
+            } else {
+                // A concurrent request rotated the token first and its response
+                // carries the new cookie, so leave the client's cookie alone.
+                reissue_cookie = false;
+            }
+        } else if matches_current {
+            // tell repo to save the details
+            self.sessions.update(&session).await?;
+        }
 
         let mut user: User = user_base.into();
         let roles = self.roles.get_by_user_id(user.id).await.map_err(|err| {
@@ -190,14 +361,70 @@ impl IAuthenticationService for AuthenticationService {
         })?;
 
         user.roles = roles;
+        user.permissions = self.roles.get_permissions_for_user(user.id).await?;
+        user.applications = self.applications.get_for_user(user.id).await?;
 
-        let mut auth_user = AuthenticatedUser {
+        session.token = cookie_token.encode();
+        let auth_user = AuthenticatedUser {
             user: user.into(),
-            session,
+            credential: Credential::Session(session),
         };
 
-        auth_user.session.token = session_token.encode();
         // return user and session details
-        Ok(auth_user)
+        Ok(RefreshedSession {
+            auth_user,
+            reissue_cookie,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::Database, roles::Permission, services::ServiceContainer, users::CreateUserRequest,
+    };
+
+    fn login(password: &str) -> LoginRequest {
+        LoginRequest {
+            username: "target".to_string(),
+            password: password.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lock_is_only_revealed_with_the_right_password() {
+        let db = Database::in_memory().await;
+        let config = Config::for_tests();
+        let threshold = config.lockout.threshold;
+        let container = ServiceContainer::new(db.pool, config);
+        let roles = container.role_service().get_all().await.unwrap();
+        container
+            .user_service()
+            .create(
+                CreateUserRequest {
+                    first_name: "Test".to_string(),
+                    last_name: "User".to_string(),
+                    email: "target@example.com".to_string(),
+                    username: "target".to_string(),
+                    raw_password: "Password12!!xY".to_string(),
+                    password_expiration: OffsetDateTime::now_utc() + Duration::days(90),
+                    roles: vec![roles[0].id],
+                },
+                Permission::ALL,
+            )
+            .await
+            .unwrap();
+        let auth = container.auth_service();
+
+        // the attempt that locks the account and those made while it is locked look
+        // just like a wrong password for an unknown username
+        for _ in 0..=threshold {
+            let err = auth.login(login("wrong password")).await.unwrap_err();
+            assert!(matches!(err, ServiceError::InvalidUsernameOrPassword));
+        }
+
+        let err = auth.login(login("Password12!!xY")).await.unwrap_err();
+        assert!(matches!(err, ServiceError::AccountLocked { .. }));
     }
 }
@@ -1,2 +1,8 @@
 pub mod session_cleanup;
 pub use session_cleanup::*;
+
+pub mod user_purge;
+pub use user_purge::*;
+
+pub mod tracker;
+pub use tracker::*;
@@ -1,11 +1,13 @@
-use crate::sessions::ISessionRepository;
+use crate::{metrics::Metrics, sessions::ISessionRepository};
 use std::sync::Arc;
 use tokio::time::{Duration, interval};
 
 /// Spawns a background task that cleans up expired sessions every 5 minutes.
 /// The task will be cancelled when the returned `JoinHandle` is dropped or the tokio runtime shuts down.
+/// The outcome of every run is recorded in `metrics`.
 pub fn spawn_cleanup_task(
     session_repo: Arc<dyn ISessionRepository>,
+    metrics: Arc<Metrics>,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         let mut ticker = interval(Duration::from_mins(5));
@@ -17,6 +19,7 @@ pub fn spawn_cleanup_task(
 
             match session_repo.delete_expired().await {
                 Ok(count) => {
+                    metrics.record_session_cleanup(Some(count));
                     if count > 0 {
                         tracing::info!("Cleaned up {} expired session(s)", count);
                     } else {
@@ -24,6 +27,7 @@ pub fn spawn_cleanup_task(
                     }
                 }
                 Err(err) => {
+                    metrics.record_session_cleanup(None);
                     tracing::error!("Failed to clean up expired sessions: {:?}", err);
                 }
             }
@@ -0,0 +1,33 @@
+use std::sync::{Mutex, PoisonError};
+use tokio::task::JoinHandle;
+
+/// Keeps the handles of the spawned background jobs, so that the readiness probe
+/// can tell whether they are still running.
+#[derive(Debug, Default)]
+pub struct BackgroundJobs {
+    jobs: Mutex<Vec<(&'static str, JoinHandle<()>)>>,
+}
+
+impl BackgroundJobs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn track(&self, name: &'static str, handle: JoinHandle<()>) {
+        self.jobs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push((name, handle));
+    }
+
+    /// The name of every tracked job and whether it is still running. A job that
+    /// panicked or returned is not.
+    pub fn statuses(&self) -> Vec<(&'static str, bool)> {
+        self.jobs
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .map(|(name, handle)| (*name, !handle.is_finished()))
+            .collect()
+    }
+}
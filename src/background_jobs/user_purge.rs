@@ -0,0 +1,31 @@
+use crate::users::IUserService;
+use std::sync::Arc;
+use tokio::time::{Duration, interval};
+
+/// Spawns a background task that permanently deletes accounts whose retention period
+/// after deletion is over, checking once an hour.
+/// The task will be cancelled when the returned `JoinHandle` is dropped or the tokio runtime shuts down.
+pub fn spawn_user_purge_task(user_service: Arc<dyn IUserService>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = interval(Duration::from_hours(1));
+
+        loop {
+            ticker.tick().await;
+
+            tracing::debug!("Running deleted user purge task");
+
+            match user_service.purge_deleted().await {
+                Ok(count) => {
+                    if count > 0 {
+                        tracing::info!("Purged {} deleted user account(s)", count);
+                    } else {
+                        tracing::debug!("No deleted user accounts to purge");
+                    }
+                }
+                Err(err) => {
+                    tracing::error!("Failed to purge deleted user accounts: {:?}", err);
+                }
+            }
+        }
+    })
+}
@@ -1,7 +1,18 @@
 // src/bin/hmac_key_gen.rs
 use argon2::password_hash::rand_core::{OsRng, RngCore};
+use time::OffsetDateTime;
 
 fn main() {
+    // Use the key id given on the command line, or today's date
+    let key_id = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| OffsetDateTime::now_utc().date().to_string());
+
+    if key_id.is_empty() || key_id.contains([':', ',']) {
+        eprintln!("Error: Key id cannot be empty or contain ':' or ','");
+        std::process::exit(1);
+    }
+
     // Generate 32 bytes (256 bits) of cryptographically secure random data
     let mut key = [0u8; 32];
     OsRng.fill_bytes(&mut key);
@@ -9,9 +20,11 @@ fn main() {
     // Encode as hex for storage in .env
     let hex_key = hex::encode(key);
 
-    println!("HMAC Key (hex-encoded):");
-    println!("{hex_key}");
+    println!("Keyring entry:");
+    println!("{key_id}:{hex_key}");
     println!();
-    println!("Add this to your .env file:");
-    println!("SESSION_HMAC_KEY={hex_key}");
+    println!("Put this entry in front of the existing ones in SESSION_HMAC_KEYS, keeping older");
+    println!("entries until their sessions have been rehashed. The first entry hashes new");
+    println!("sessions unless SESSION_HMAC_ACTIVE_KEY_ID names another, for example:");
+    println!("SESSION_HMAC_KEYS={key_id}:<new key>,<old id>:<old key>");
 }
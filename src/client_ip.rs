@@ -0,0 +1,258 @@
+//! Working out which address a request really came from.
+//!
+//! Behind a reverse proxy such as Cloudflare every connection comes from the proxy,
+//! and the client's address is only known from the headers the proxy adds. Those
+//! headers are easy to forge, so they are only believed when the connection comes
+//! from one of the configured trusted proxies. `CF-Connecting-IP` additionally has to
+//! be switched on, as only Cloudflare sets it and any other proxy passes on whatever
+//! the client sent.
+
+use std::{net::IpAddr, str::FromStr};
+
+use anyhow::{anyhow, bail};
+use axum::http::HeaderMap;
+
+/// Header Cloudflare sets to the address of the visitor.
+const CF_CONNECTING_IP: &str = "cf-connecting-ip";
+
+/// Standard header listing the client and every proxy a request passed through.
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// An address range in CIDR notation, e.g. `173.245.48.0/20`. A bare address is a
+/// range of one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNetwork {
+    address: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.address, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                prefix_matches(&network.octets(), &ip.octets(), self.prefix_len)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                prefix_matches(&network.octets(), &ip.octets(), self.prefix_len)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn prefix_matches(network: &[u8], ip: &[u8], prefix_len: u8) -> bool {
+    let full_bytes = usize::from(prefix_len / 8);
+    let remaining_bits = prefix_len % 8;
+
+    if network[..full_bytes] != ip[..full_bytes] {
+        return false;
+    }
+
+    if remaining_bits == 0 {
+        return true;
+    }
+
+    let mask = 0xff_u8 << (8 - remaining_bits);
+    network[full_bytes] & mask == ip[full_bytes] & mask
+}
+
+impl FromStr for IpNetwork {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (address, prefix_len) = match value.split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len)),
+            None => (value, None),
+        };
+
+        let address = address
+            .trim()
+            .parse::<IpAddr>()
+            .map_err(|_| anyhow!("`{value}` is not an IP address or CIDR range"))?
+            .to_canonical();
+        let max_prefix_len = if address.is_ipv4() { 32 } else { 128 };
+
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len
+                .trim()
+                .parse::<u8>()
+                .map_err(|_| anyhow!("`{value}` has an invalid prefix length"))?,
+            None => max_prefix_len,
+        };
+        if prefix_len > max_prefix_len {
+            bail!("`{value}` has a prefix length over {max_prefix_len}");
+        }
+
+        Ok(Self {
+            address,
+            prefix_len,
+        })
+    }
+}
+
+/// The proxies whose forwarding headers are believed. Empty by default, in which case
+/// the address of the connection is always used.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    networks: Vec<IpNetwork>,
+    trust_cf_connecting_ip: bool,
+}
+
+impl TrustedProxies {
+    /// Parse a comma separated list of addresses and CIDR ranges.
+    pub fn parse(list: &str) -> Result<Self, anyhow::Error> {
+        let networks = list
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            networks,
+            trust_cf_connecting_ip: false,
+        })
+    }
+
+    /// Believe `CF-Connecting-IP` from trusted proxies, for when they are Cloudflare.
+    pub const fn with_cf_connecting_ip(mut self, trust: bool) -> Self {
+        self.trust_cf_connecting_ip = trust;
+        self
+    }
+
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.networks.iter().any(|network| network.contains(ip))
+    }
+
+    /// The address of the client behind `peer`, the address the connection came from.
+    ///
+    /// When `peer` is a trusted proxy, `CF-Connecting-IP` is used if present and
+    /// trusted, and otherwise the last address in `X-Forwarded-For` that is not itself
+    /// a trusted proxy, since everything before it could have been sent by the client.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        let peer = peer.to_canonical();
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+
+        if let Some(ip) = headers
+            .get(CF_CONNECTING_IP)
+            .filter(|_| self.trust_cf_connecting_ip)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<IpAddr>().ok())
+        {
+            return ip.to_canonical();
+        }
+
+        let forwarded: Vec<IpAddr> = headers
+            .get_all(X_FORWARDED_FOR)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|entry| entry.trim().parse::<IpAddr>().ok())
+            .map(|ip| ip.to_canonical())
+            .collect();
+
+        forwarded
+            .iter()
+            .rev()
+            .find(|ip| !self.is_trusted(**ip))
+            .or_else(|| forwarded.first())
+            .copied()
+            .unwrap_or(peer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_network_contains() {
+        let network: IpNetwork = "173.245.48.0/20".parse().unwrap();
+        assert!(network.contains(ip("173.245.63.255")));
+        assert!(!network.contains(ip("173.245.64.0")));
+        assert!(network.contains(ip("::ffff:173.245.48.1")));
+
+        let network: IpNetwork = "2400:cb00::/32".parse().unwrap();
+        assert!(network.contains(ip("2400:cb00:1::1")));
+        assert!(!network.contains(ip("2400:cb01::1")));
+
+        assert!(
+            "10.0.0.1"
+                .parse::<IpNetwork>()
+                .unwrap()
+                .contains(ip("10.0.0.1"))
+        );
+        assert!("10.0.0.0/33".parse::<IpNetwork>().is_err());
+        assert!("proxy".parse::<IpNetwork>().is_err());
+    }
+
+    #[test]
+    fn test_headers_ignored_from_untrusted_peer() {
+        let proxies = TrustedProxies::parse("10.0.0.0/8").unwrap();
+        let forged = headers(&[
+            ("cf-connecting-ip", "1.2.3.4"),
+            ("x-forwarded-for", "1.2.3.4"),
+        ]);
+
+        assert_eq!(
+            proxies.client_ip(ip("203.0.113.9"), &forged),
+            ip("203.0.113.9")
+        );
+        assert_eq!(
+            TrustedProxies::default().client_ip(ip("10.0.0.1"), &forged),
+            ip("10.0.0.1")
+        );
+    }
+
+    #[test]
+    fn test_client_ip_from_trusted_proxy() {
+        let proxies = TrustedProxies::parse("10.0.0.0/8, 172.16.0.1").unwrap();
+
+        let cloudflare = headers(&[("cf-connecting-ip", "198.51.100.7")]);
+        assert_eq!(
+            proxies.client_ip(ip("10.1.2.3"), &cloudflare),
+            ip("10.1.2.3")
+        );
+        assert_eq!(
+            proxies
+                .clone()
+                .with_cf_connecting_ip(true)
+                .client_ip(ip("10.1.2.3"), &cloudflare),
+            ip("198.51.100.7")
+        );
+
+        let forged = headers(&[
+            ("cf-connecting-ip", "6.6.6.6"),
+            ("x-forwarded-for", "198.51.100.7"),
+        ]);
+        assert_eq!(
+            proxies.client_ip(ip("10.1.2.3"), &forged),
+            ip("198.51.100.7")
+        );
+
+        let forwarded = headers(&[("x-forwarded-for", "6.6.6.6, 198.51.100.7, 172.16.0.1")]);
+        assert_eq!(
+            proxies.client_ip(ip("10.1.2.3"), &forwarded),
+            ip("198.51.100.7")
+        );
+
+        assert_eq!(
+            proxies.client_ip(ip("10.1.2.3"), &headers(&[])),
+            ip("10.1.2.3")
+        );
+    }
+}
@@ -0,0 +1,917 @@
+//! Typed application configuration.
+//!
+//! All settings are loaded once at startup into a [`Config`], which is shared with
+//! the rest of the application through the `ServiceContainer`. Nothing else should
+//! read configuration from the environment directly.
+//!
+//! # Sources
+//!
+//! Settings are read from an optional TOML file and then from environment variables
+//! (including a `.env` file), with environment variables taking precedence. The TOML
+//! file is the path in `CONFIG_FILE`, or `mainframe.toml` in the working directory
+//! when that exists. Its keys are the lowercase names of the environment variables:
+//!
+//! ```toml
+//! database_url = "sqlite://mainframe.db"
+//! bind_address = "0.0.0.0:8080"
+//! session_hmac_keys = "2026-01-01:<hex key>"
+//! login_lockout_threshold = 5
+//! api_title = "Mainframe API"
+//! ```
+//!
+//! Numeric and boolean settings may be written bare or quoted. Empty values are
+//! treated as unset.
+//!
+//! # Settings
+//!
+//! - `DATABASE_URL` - `SQLite` connection string (required)
+//! - `BIND_ADDRESS` - Socket address to listen on (default: "127.0.0.1:3030")
+//! - `METRICS_BIND_ADDRESS` - Extra socket address that serves only `/metrics`,
+//!   without authentication, for a Prometheus server on a private network. The
+//!   `/metrics` route on `BIND_ADDRESS` always needs the `metrics:read` permission
+//!   (default: none)
+//! - `SESSION_HMAC_KEYS` - Keyring of `id:hex_key` entries used to hash session tokens
+//! - `SESSION_HMAC_ACTIVE_KEY_ID` - Key id used for new tokens (default: first entry)
+//! - `SESSION_HMAC_KEY` - Legacy single key, used when `SESSION_HMAC_KEYS` is not set.
+//!   Unlike keyring entries it may be shorter than 32 bytes, which is logged as a
+//!   warning at startup. To replace a short key, generate one with `hmac_key_gen` and
+//!   set `SESSION_HMAC_KEYS=<new id>:<new key>,default:<old key>`; the old key then
+//!   only verifies existing sessions until they are rehashed, and can be dropped after
+//!   the session lifetime has passed
+//! - `PASSWORD_RESET_CODE_TTL_MINUTES` - How long an admin-issued password reset code
+//!   can be redeemed (default: 60)
+//! - `PASSWORD_HISTORY_SIZE` - Number of previous passwords a user may not reuse when
+//!   changing their own password (default: 5)
+//! - `LOGIN_LOCKOUT_THRESHOLD` - Consecutive failed logins that lock an account (default: 5)
+//! - `LOGIN_LOCKOUT_BASE_MINUTES` - Length of the first lockout, doubled for every
+//!   further failure (default: 1)
+//! - `LOGIN_LOCKOUT_MAX_MINUTES` - Upper bound for a single lockout (default: 60)
+//! - `USER_DELETION_RETENTION_DAYS` - How long a deleted account can be restored
+//!   before it is purged for good (default: 30)
+//! - `LOGIN_RATE_LIMIT_PER_IP` - Login attempts allowed per minute from one client
+//!   address (default: 10)
+//! - `LOGIN_RATE_LIMIT_PER_USERNAME` - Login attempts allowed per minute for one
+//!   username (default: 5)
+//! - `API_RATE_LIMIT_PER_USER` - API requests allowed per minute for one signed in
+//!   user (default: 300)
+//! - `API_RATE_LIMIT_PER_IP` - Anonymous API requests allowed per minute from one
+//!   client address (default: 60)
+//! - `API_RATE_LIMIT_GROUPS` - Separate budgets for route groups, as comma separated
+//!   `prefix=per_user/per_ip` entries such as `/api/recipes=600/120` (default: none)
+//! - `TRUSTED_PROXIES` - Comma separated addresses or CIDR ranges of reverse proxies
+//!   whose `X-Forwarded-For` headers are believed (default: none)
+//! - `TRUST_CF_CONNECTING_IP` - `true` to also believe `CF-Connecting-IP` from the
+//!   trusted proxies, which must then be Cloudflare's (default: false)
+//! - `CONTENT_SECURITY_POLICY` - `Content-Security-Policy` sent with the frontend and
+//!   API responses (default: allows only this origin, suited to the built frontend)
+//! - `DOCS_CONTENT_SECURITY_POLICY` - `Content-Security-Policy` sent with `/docs`,
+//!   which loads the Scalar viewer from a CDN
+//! - `HSTS_MAX_AGE_SECONDS` - `max-age` of the `Strict-Transport-Security` header, or
+//!   0 to leave it out when not serving over HTTPS (default: 31536000)
+//! - `PUBLIC_ORIGIN` - The origin users reach Mainframe at, such as
+//!   `https://mainframe.example`, which requests that change state must come from
+//!   unless they carry the CSRF token (default: none, which trusts the `Host` header
+//!   instead and logs a warning)
+//! - `CORS_ALLOWED_ORIGINS` - Comma separated origins allowed to make credentialed
+//!   cross-origin requests, such as `http://localhost:5173` for a Vite dev server on
+//!   another origin (default: none)
+//! - `LOG_FORMAT` - `pretty` for human-readable logs or `json` for one JSON object per
+//!   line, for log collectors (default: pretty). Verbosity is set with `RUST_LOG`.
+//! - `API_TITLE`, `API_VERSION`, `API_DESCRIPTION`, `API_CONTACT_NAME`,
+//!   `API_CONTACT_EMAIL` - `OpenAPI` metadata, see [`crate::docs`]
+//!
+//! Loading fails if any setting is missing or malformed, and the error lists every
+//! problem found rather than stopping at the first one.
+
+use axum::http::{HeaderValue, Uri};
+use serde::{Deserialize, Deserializer};
+use sqlx::sqlite::SqliteConnectOptions;
+use std::{
+    env,
+    fmt::{self, Display},
+    net::SocketAddr,
+    path::PathBuf,
+    str::FromStr,
+};
+use thiserror::Error;
+use time::Duration;
+
+use crate::{
+    authentication::LockoutPolicy,
+    client_ip::TrustedProxies,
+    rate_limit::{RateLimit, RouteBudget},
+    token::HmacKeyring,
+};
+
+const DEFAULT_CONFIG_FILE: &str = "mainframe.toml";
+const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1:3030";
+const DEFAULT_PASSWORD_RESET_CODE_TTL_MINUTES: i64 = 60;
+const DEFAULT_PASSWORD_HISTORY_SIZE: i64 = 5;
+const DEFAULT_LOGIN_LOCKOUT_THRESHOLD: i64 = 5;
+const DEFAULT_LOGIN_LOCKOUT_BASE_MINUTES: i64 = 1;
+const DEFAULT_LOGIN_LOCKOUT_MAX_MINUTES: i64 = 60;
+const DEFAULT_USER_DELETION_RETENTION_DAYS: i64 = 30;
+const DEFAULT_LOGIN_RATE_LIMIT_PER_IP: u32 = 10;
+const DEFAULT_LOGIN_RATE_LIMIT_PER_USERNAME: u32 = 5;
+const DEFAULT_API_RATE_LIMIT_PER_USER: u32 = 300;
+const DEFAULT_API_RATE_LIMIT_PER_IP: u32 = 60;
+const DEFAULT_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; script-src 'self'; \
+     style-src 'self' 'unsafe-inline'; img-src 'self' data:; font-src 'self' data:; \
+     connect-src 'self'; object-src 'none'; base-uri 'self'; form-action 'self'; \
+     frame-ancestors 'none'";
+const DEFAULT_DOCS_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; \
+     script-src 'self' https://cdn.jsdelivr.net; \
+     style-src 'self' 'unsafe-inline' https://cdn.jsdelivr.net https://fonts.scalar.com; \
+     img-src 'self' data: https:; font-src 'self' data: https://fonts.scalar.com; \
+     connect-src 'self'; object-src 'none'; base-uri 'self'; frame-ancestors 'none'";
+const DEFAULT_HSTS_MAX_AGE_SECONDS: u64 = 31_536_000;
+
+/// The validated configuration for the whole application.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database_url: String,
+    pub bind_address: SocketAddr,
+    pub metrics_bind_address: Option<SocketAddr>,
+    pub session_keyring: HmacKeyring,
+    pub password_reset_code_ttl: Duration,
+    pub password_history_size: i64,
+    pub lockout: LockoutPolicy,
+    pub user_deletion_retention: Duration,
+    pub rate_limits: RateLimitConfig,
+    pub trusted_proxies: TrustedProxies,
+    pub security: SecurityConfig,
+    pub log_format: LogFormat,
+    pub api_docs: ApiDocsConfig,
+}
+
+/// Metadata shown in the generated `OpenAPI` documentation.
+#[derive(Debug, Clone)]
+pub struct ApiDocsConfig {
+    pub title: String,
+    pub version: String,
+    pub description: String,
+    pub contact_name: String,
+    pub contact_email: Option<String>,
+}
+
+/// How log lines are written to standard output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Pretty,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(Self::Pretty),
+            "json" => Ok(Self::Json),
+            _ => Err("expected `pretty` or `json`".to_string()),
+        }
+    }
+}
+
+/// Request budgets enforced by the in-process rate limiters.
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub login_per_ip: RateLimit,
+    pub login_per_username: RateLimit,
+    pub api_per_user: RateLimit,
+    pub api_per_ip: RateLimit,
+    pub api_groups: Vec<RouteBudget>,
+}
+
+/// Browser security headers and the cross-origin policy.
+#[derive(Debug, Clone)]
+pub struct SecurityConfig {
+    pub content_security_policy: HeaderValue,
+    pub docs_content_security_policy: HeaderValue,
+    /// `None` when `Strict-Transport-Security` is disabled.
+    pub hsts_max_age_seconds: Option<u64>,
+    /// `None` when not configured, in which case the `Host` header stands in for it.
+    pub public_origin: Option<HeaderValue>,
+    pub cors_allowed_origins: Vec<HeaderValue>,
+}
+
+/// Every problem found while loading the configuration.
+#[derive(Error)]
+#[error("invalid configuration:\n  - {}", .problems.join("\n  - "))]
+pub struct ConfigError {
+    pub problems: Vec<String>,
+}
+
+// Returning this from `main` prints the `Debug` output, so keep it readable.
+impl fmt::Debug for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Settings as read from the TOML file and environment, before validation.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    database_url: Option<String>,
+    bind_address: Option<String>,
+    metrics_bind_address: Option<String>,
+    session_hmac_keys: Option<String>,
+    session_hmac_active_key_id: Option<String>,
+    session_hmac_key: Option<String>,
+    #[serde(default, deserialize_with = "scalar")]
+    password_reset_code_ttl_minutes: Option<String>,
+    #[serde(default, deserialize_with = "scalar")]
+    password_history_size: Option<String>,
+    #[serde(default, deserialize_with = "scalar")]
+    login_lockout_threshold: Option<String>,
+    #[serde(default, deserialize_with = "scalar")]
+    login_lockout_base_minutes: Option<String>,
+    #[serde(default, deserialize_with = "scalar")]
+    login_lockout_max_minutes: Option<String>,
+    #[serde(default, deserialize_with = "scalar")]
+    user_deletion_retention_days: Option<String>,
+    #[serde(default, deserialize_with = "scalar")]
+    login_rate_limit_per_ip: Option<String>,
+    #[serde(default, deserialize_with = "scalar")]
+    login_rate_limit_per_username: Option<String>,
+    #[serde(default, deserialize_with = "scalar")]
+    api_rate_limit_per_user: Option<String>,
+    #[serde(default, deserialize_with = "scalar")]
+    api_rate_limit_per_ip: Option<String>,
+    api_rate_limit_groups: Option<String>,
+    trusted_proxies: Option<String>,
+    #[serde(default, deserialize_with = "scalar")]
+    trust_cf_connecting_ip: Option<String>,
+    content_security_policy: Option<String>,
+    docs_content_security_policy: Option<String>,
+    #[serde(default, deserialize_with = "scalar")]
+    hsts_max_age_seconds: Option<String>,
+    public_origin: Option<String>,
+    cors_allowed_origins: Option<String>,
+    log_format: Option<String>,
+    api_title: Option<String>,
+    api_version: Option<String>,
+    api_description: Option<String>,
+    api_contact_name: Option<String>,
+    api_contact_email: Option<String>,
+}
+
+/// A number or boolean in the TOML file, written either bare or quoted like the
+/// environment variable it stands for.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Scalar {
+    Number(i64),
+    Boolean(bool),
+    String(String),
+}
+
+fn scalar<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(Some(match Scalar::deserialize(deserializer)? {
+        Scalar::Number(number) => number.to_string(),
+        Scalar::Boolean(boolean) => boolean.to_string(),
+        Scalar::String(string) => string,
+    }))
+}
+
+impl RawConfig {
+    fn from_file(problems: &mut Vec<String>) -> Self {
+        let (path, required) = env::var("CONFIG_FILE").map_or_else(
+            |_| (PathBuf::from(DEFAULT_CONFIG_FILE), false),
+            |path| (PathBuf::from(path), true),
+        );
+
+        if !required && !path.exists() {
+            return Self::default();
+        }
+
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                problems.push(format!(
+                    "CONFIG_FILE: cannot read {}: {err}",
+                    path.display()
+                ));
+                return Self::default();
+            }
+        };
+
+        toml::from_str(&contents).unwrap_or_else(|err| {
+            problems.push(format!(
+                "CONFIG_FILE: {} is malformed: {err}",
+                path.display()
+            ));
+            Self::default()
+        })
+    }
+
+    fn apply_env(&mut self) {
+        let settings = [
+            ("DATABASE_URL", &mut self.database_url),
+            ("BIND_ADDRESS", &mut self.bind_address),
+            ("METRICS_BIND_ADDRESS", &mut self.metrics_bind_address),
+            ("SESSION_HMAC_KEYS", &mut self.session_hmac_keys),
+            (
+                "SESSION_HMAC_ACTIVE_KEY_ID",
+                &mut self.session_hmac_active_key_id,
+            ),
+            ("SESSION_HMAC_KEY", &mut self.session_hmac_key),
+            (
+                "PASSWORD_RESET_CODE_TTL_MINUTES",
+                &mut self.password_reset_code_ttl_minutes,
+            ),
+            ("PASSWORD_HISTORY_SIZE", &mut self.password_history_size),
+            ("LOGIN_LOCKOUT_THRESHOLD", &mut self.login_lockout_threshold),
+            (
+                "LOGIN_LOCKOUT_BASE_MINUTES",
+                &mut self.login_lockout_base_minutes,
+            ),
+            (
+                "LOGIN_LOCKOUT_MAX_MINUTES",
+                &mut self.login_lockout_max_minutes,
+            ),
+            (
+                "USER_DELETION_RETENTION_DAYS",
+                &mut self.user_deletion_retention_days,
+            ),
+            ("LOGIN_RATE_LIMIT_PER_IP", &mut self.login_rate_limit_per_ip),
+            (
+                "LOGIN_RATE_LIMIT_PER_USERNAME",
+                &mut self.login_rate_limit_per_username,
+            ),
+            ("API_RATE_LIMIT_PER_USER", &mut self.api_rate_limit_per_user),
+            ("API_RATE_LIMIT_PER_IP", &mut self.api_rate_limit_per_ip),
+            ("API_RATE_LIMIT_GROUPS", &mut self.api_rate_limit_groups),
+            ("TRUSTED_PROXIES", &mut self.trusted_proxies),
+            ("TRUST_CF_CONNECTING_IP", &mut self.trust_cf_connecting_ip),
+            ("CONTENT_SECURITY_POLICY", &mut self.content_security_policy),
+            (
+                "DOCS_CONTENT_SECURITY_POLICY",
+                &mut self.docs_content_security_policy,
+            ),
+            ("HSTS_MAX_AGE_SECONDS", &mut self.hsts_max_age_seconds),
+            ("PUBLIC_ORIGIN", &mut self.public_origin),
+            ("CORS_ALLOWED_ORIGINS", &mut self.cors_allowed_origins),
+            ("LOG_FORMAT", &mut self.log_format),
+            ("API_TITLE", &mut self.api_title),
+            ("API_VERSION", &mut self.api_version),
+            ("API_DESCRIPTION", &mut self.api_description),
+            ("API_CONTACT_NAME", &mut self.api_contact_name),
+            ("API_CONTACT_EMAIL", &mut self.api_contact_email),
+        ];
+
+        for (name, value) in settings {
+            if let Ok(env_value) = env::var(name) {
+                *value = Some(env_value);
+            }
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration from the optional TOML file and the environment.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut problems = Vec::new();
+        let mut raw = RawConfig::from_file(&mut problems);
+        raw.apply_env();
+
+        match Self::from_raw(raw) {
+            Ok(config) if problems.is_empty() => Ok(config),
+            Ok(_) => Err(ConfigError { problems }),
+            Err(err) => {
+                problems.extend(err.problems);
+                Err(ConfigError { problems })
+            }
+        }
+    }
+
+    /// The defaults, with an in-memory database and a fixed session key.
+    #[cfg(test)]
+    pub fn for_tests() -> Self {
+        Self::from_raw(RawConfig {
+            database_url: Some("sqlite::memory:".to_string()),
+            session_hmac_keys: Some(format!("test:{}", "ab".repeat(32))),
+            ..RawConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn from_raw(raw: RawConfig) -> Result<Self, ConfigError> {
+        let mut problems = Vec::new();
+        let api_docs = ApiDocsConfig::from_raw(&raw);
+        let rate_limits = RateLimitConfig::from_raw(&raw, &mut problems);
+        let security = SecurityConfig::from_raw(&raw, &mut problems);
+        let trusted_proxies = parse_trusted_proxies(&raw, &mut problems);
+
+        let database_url = non_empty(raw.database_url).unwrap_or_default();
+        if database_url.is_empty() {
+            problems.push("DATABASE_URL: must be set".into());
+        } else if let Err(err) = SqliteConnectOptions::from_str(&database_url) {
+            problems.push(format!("DATABASE_URL: {err}"));
+        }
+
+        let bind_address = non_empty(raw.bind_address)
+            .unwrap_or_else(|| DEFAULT_BIND_ADDRESS.to_string())
+            .parse::<SocketAddr>()
+            .map_err(|err| problems.push(format!("BIND_ADDRESS: {err}")))
+            .ok();
+        let metrics_bind_address = non_empty(raw.metrics_bind_address).and_then(|address| {
+            address
+                .parse::<SocketAddr>()
+                .map_err(|err| problems.push(format!("METRICS_BIND_ADDRESS: {err}")))
+                .ok()
+        });
+
+        let session_keyring = parse_session_keyring(
+            raw.session_hmac_keys,
+            raw.session_hmac_active_key_id,
+            raw.session_hmac_key,
+            &mut problems,
+        );
+
+        let password_reset_code_ttl = parse_minutes(
+            "PASSWORD_RESET_CODE_TTL_MINUTES",
+            raw.password_reset_code_ttl_minutes,
+            DEFAULT_PASSWORD_RESET_CODE_TTL_MINUTES,
+            &mut problems,
+        );
+
+        let password_history_size = parse_or(
+            "PASSWORD_HISTORY_SIZE",
+            raw.password_history_size,
+            DEFAULT_PASSWORD_HISTORY_SIZE,
+            &mut problems,
+        );
+        if password_history_size < 0 {
+            problems.push("PASSWORD_HISTORY_SIZE: must not be negative".into());
+        }
+
+        let lockout = LockoutPolicy {
+            threshold: parse_positive(
+                "LOGIN_LOCKOUT_THRESHOLD",
+                raw.login_lockout_threshold,
+                DEFAULT_LOGIN_LOCKOUT_THRESHOLD,
+                &mut problems,
+            ),
+            base_duration: parse_minutes(
+                "LOGIN_LOCKOUT_BASE_MINUTES",
+                raw.login_lockout_base_minutes,
+                DEFAULT_LOGIN_LOCKOUT_BASE_MINUTES,
+                &mut problems,
+            ),
+            max_duration: parse_minutes(
+                "LOGIN_LOCKOUT_MAX_MINUTES",
+                raw.login_lockout_max_minutes,
+                DEFAULT_LOGIN_LOCKOUT_MAX_MINUTES,
+                &mut problems,
+            ),
+        };
+        if lockout.max_duration < lockout.base_duration {
+            problems.push(
+                "LOGIN_LOCKOUT_MAX_MINUTES: must not be less than LOGIN_LOCKOUT_BASE_MINUTES"
+                    .into(),
+            );
+        }
+
+        let user_deletion_retention = Duration::days(parse_positive(
+            "USER_DELETION_RETENTION_DAYS",
+            raw.user_deletion_retention_days,
+            DEFAULT_USER_DELETION_RETENTION_DAYS,
+            &mut problems,
+        ));
+
+        let log_format = parse_or(
+            "LOG_FORMAT",
+            raw.log_format,
+            LogFormat::Pretty,
+            &mut problems,
+        );
+
+        match (bind_address, session_keyring) {
+            (Some(bind_address), Some(session_keyring)) if problems.is_empty() => Ok(Self {
+                database_url,
+                bind_address,
+                metrics_bind_address,
+                session_keyring,
+                password_reset_code_ttl,
+                password_history_size,
+                lockout,
+                user_deletion_retention,
+                rate_limits,
+                trusted_proxies,
+                security,
+                log_format,
+                api_docs,
+            }),
+            _ => Err(ConfigError { problems }),
+        }
+    }
+}
+
+impl ApiDocsConfig {
+    fn from_raw(raw: &RawConfig) -> Self {
+        Self {
+            title: non_empty(raw.api_title.clone()).unwrap_or_else(|| "Mainframe API".to_string()),
+            version: non_empty(raw.api_version.clone()).unwrap_or_else(|| "1.0.0".to_string()),
+            description: non_empty(raw.api_description.clone())
+                .unwrap_or_else(|| "Self-hosted personal productivity platform API".to_string()),
+            contact_name: non_empty(raw.api_contact_name.clone())
+                .unwrap_or_else(|| "Administrator".to_string()),
+            contact_email: non_empty(raw.api_contact_email.clone()),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    fn from_raw(raw: &RawConfig, problems: &mut Vec<String>) -> Self {
+        let api_groups = non_empty(raw.api_rate_limit_groups.clone())
+            .and_then(|list| {
+                RouteBudget::parse_list(&list)
+                    .map_err(|err| problems.push(format!("API_RATE_LIMIT_GROUPS: {err}")))
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        Self {
+            login_per_ip: parse_rate_limit(
+                "LOGIN_RATE_LIMIT_PER_IP",
+                raw.login_rate_limit_per_ip.clone(),
+                DEFAULT_LOGIN_RATE_LIMIT_PER_IP,
+                problems,
+            ),
+            login_per_username: parse_rate_limit(
+                "LOGIN_RATE_LIMIT_PER_USERNAME",
+                raw.login_rate_limit_per_username.clone(),
+                DEFAULT_LOGIN_RATE_LIMIT_PER_USERNAME,
+                problems,
+            ),
+            api_per_user: parse_rate_limit(
+                "API_RATE_LIMIT_PER_USER",
+                raw.api_rate_limit_per_user.clone(),
+                DEFAULT_API_RATE_LIMIT_PER_USER,
+                problems,
+            ),
+            api_per_ip: parse_rate_limit(
+                "API_RATE_LIMIT_PER_IP",
+                raw.api_rate_limit_per_ip.clone(),
+                DEFAULT_API_RATE_LIMIT_PER_IP,
+                problems,
+            ),
+            api_groups,
+        }
+    }
+}
+
+impl SecurityConfig {
+    fn from_raw(raw: &RawConfig, problems: &mut Vec<String>) -> Self {
+        let mut header_value = |name: &str, value: Option<String>, default: &'static str| {
+            let Some(value) = non_empty(value) else {
+                return HeaderValue::from_static(default);
+            };
+
+            HeaderValue::try_from(value.trim()).unwrap_or_else(|err| {
+                problems.push(format!("{name}: {err}"));
+                HeaderValue::from_static(default)
+            })
+        };
+
+        let content_security_policy = header_value(
+            "CONTENT_SECURITY_POLICY",
+            raw.content_security_policy.clone(),
+            DEFAULT_CONTENT_SECURITY_POLICY,
+        );
+        let docs_content_security_policy = header_value(
+            "DOCS_CONTENT_SECURITY_POLICY",
+            raw.docs_content_security_policy.clone(),
+            DEFAULT_DOCS_CONTENT_SECURITY_POLICY,
+        );
+
+        let hsts_max_age_seconds = Some(parse_or(
+            "HSTS_MAX_AGE_SECONDS",
+            raw.hsts_max_age_seconds.clone(),
+            DEFAULT_HSTS_MAX_AGE_SECONDS,
+            problems,
+        ))
+        .filter(|seconds| *seconds > 0);
+
+        let public_origin = non_empty(raw.public_origin.clone()).and_then(|origin| {
+            parse_origin(origin.trim())
+                .map_err(|err| problems.push(format!("PUBLIC_ORIGIN: {err}")))
+                .ok()
+        });
+
+        let cors_allowed_origins = non_empty(raw.cors_allowed_origins.clone())
+            .map(|list| {
+                list.split(',')
+                    .map(str::trim)
+                    .filter(|origin| !origin.is_empty())
+                    .filter_map(|origin| {
+                        parse_origin(origin)
+                            .map_err(|err| problems.push(format!("CORS_ALLOWED_ORIGINS: {err}")))
+                            .ok()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            content_security_policy,
+            docs_content_security_policy,
+            hsts_max_age_seconds,
+            public_origin,
+            cors_allowed_origins,
+        }
+    }
+}
+
+/// Parse an origin such as `http://localhost:5173`. Wildcards are refused because
+/// they cannot be combined with credentialed requests.
+fn parse_origin(origin: &str) -> Result<HeaderValue, String> {
+    let uri = origin
+        .parse::<Uri>()
+        .map_err(|_| format!("`{origin}` is not an origin"))?;
+
+    let is_origin = matches!(uri.scheme_str(), Some("http" | "https"))
+        && uri.authority().is_some()
+        && uri.path_and_query().is_none_or(|path| path.as_str() == "/")
+        && !origin.ends_with('/');
+    if !is_origin {
+        return Err(format!(
+            "`{origin}` is not an origin like https://example.com"
+        ));
+    }
+
+    HeaderValue::try_from(origin).map_err(|err| format!("`{origin}`: {err}"))
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.trim().is_empty())
+}
+
+/// Build the session keyring from `SESSION_HMAC_KEYS`, falling back to the legacy
+/// single `SESSION_HMAC_KEY`.
+fn parse_session_keyring(
+    keys: Option<String>,
+    active_key_id: Option<String>,
+    legacy_key: Option<String>,
+    problems: &mut Vec<String>,
+) -> Option<HmacKeyring> {
+    let active_key_id = non_empty(active_key_id);
+    match (non_empty(keys), non_empty(legacy_key)) {
+        (Some(keys), _) => HmacKeyring::parse(&keys, active_key_id.as_deref())
+            .map_err(|err| problems.push(format!("SESSION_HMAC_KEYS: {err}")))
+            .ok(),
+        (None, Some(legacy_key)) => HmacKeyring::legacy(&legacy_key)
+            .map_err(|err| problems.push(format!("SESSION_HMAC_KEY: {err}")))
+            .ok(),
+        (None, None) => {
+            problems.push("SESSION_HMAC_KEYS: must be set (or the legacy SESSION_HMAC_KEY)".into());
+            None
+        }
+    }
+}
+
+/// Parse an optional setting, using `default` when it is unset and recording a
+/// problem when it is malformed.
+fn parse_or<T>(name: &str, value: Option<String>, default: T, problems: &mut Vec<String>) -> T
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    let Some(value) = non_empty(value) else {
+        return default;
+    };
+
+    value.trim().parse().unwrap_or_else(|err| {
+        problems.push(format!("{name}: `{value}` is invalid: {err}"));
+        default
+    })
+}
+
+/// Parse an optional, strictly positive number.
+fn parse_positive(
+    name: &str,
+    value: Option<String>,
+    default: i64,
+    problems: &mut Vec<String>,
+) -> i64 {
+    let number = parse_or(name, value, default, problems);
+    if number <= 0 {
+        problems.push(format!("{name}: must be greater than zero"));
+    }
+
+    number
+}
+
+/// Parse an optional, strictly positive number of minutes.
+fn parse_minutes(
+    name: &str,
+    value: Option<String>,
+    default_minutes: i64,
+    problems: &mut Vec<String>,
+) -> Duration {
+    Duration::minutes(parse_positive(name, value, default_minutes, problems))
+}
+
+/// Parse an optional list of trusted proxies, trusting none when it is unset, and
+/// whether their `CF-Connecting-IP` header is believed.
+fn parse_trusted_proxies(raw: &RawConfig, problems: &mut Vec<String>) -> TrustedProxies {
+    let trust_cf_connecting_ip = parse_or(
+        "TRUST_CF_CONNECTING_IP",
+        raw.trust_cf_connecting_ip.clone(),
+        false,
+        problems,
+    );
+    let Some(list) = non_empty(raw.trusted_proxies.clone()) else {
+        return TrustedProxies::default();
+    };
+
+    TrustedProxies::parse(&list)
+        .unwrap_or_else(|err| {
+            problems.push(format!("TRUSTED_PROXIES: {err}"));
+            TrustedProxies::default()
+        })
+        .with_cf_connecting_ip(trust_cf_connecting_ip)
+}
+
+/// Parse an optional, strictly positive number of requests per minute.
+fn parse_rate_limit(
+    name: &str,
+    value: Option<String>,
+    default_per_minute: u32,
+    problems: &mut Vec<String>,
+) -> RateLimit {
+    let per_minute = parse_or(name, value, default_per_minute, problems);
+    if per_minute == 0 {
+        problems.push(format!("{name}: must be greater than zero"));
+    }
+
+    RateLimit { per_minute }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::LEGACY_HMAC_KEY_ID;
+
+    const KEY: &str = "00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff";
+
+    fn raw_from_toml(contents: &str) -> RawConfig {
+        toml::from_str(contents).unwrap()
+    }
+
+    #[test]
+    fn test_loads_settings_with_defaults() {
+        let raw = raw_from_toml(&format!(
+            "database_url = \"sqlite://test.db\"\nsession_hmac_keys = \"k1:{KEY}\""
+        ));
+
+        let config = Config::from_raw(raw).unwrap();
+        assert_eq!(config.bind_address.to_string(), DEFAULT_BIND_ADDRESS);
+        assert_eq!(config.session_keyring.active_key_id(), "k1");
+        assert_eq!(config.api_docs.title, "Mainframe API");
+        assert!(config.api_docs.contact_email.is_none());
+        assert_eq!(config.lockout.threshold, DEFAULT_LOGIN_LOCKOUT_THRESHOLD);
+        assert_eq!(
+            config.user_deletion_retention,
+            Duration::days(DEFAULT_USER_DELETION_RETENTION_DAYS)
+        );
+        assert_eq!(
+            config.rate_limits.login_per_ip.per_minute,
+            DEFAULT_LOGIN_RATE_LIMIT_PER_IP
+        );
+        assert!(config.rate_limits.api_groups.is_empty());
+        assert_eq!(config.log_format, LogFormat::Pretty);
+        assert!(
+            !config
+                .trusted_proxies
+                .is_trusted("127.0.0.1".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_parses_security_settings() {
+        let raw = raw_from_toml(&format!(
+            "database_url = \"sqlite://test.db\"\nsession_hmac_keys = \"k1:{KEY}\"\n\
+             hsts_max_age_seconds = \"0\"\n\
+             public_origin = \"https://mainframe.example\"\n\
+             cors_allowed_origins = \"http://localhost:5173, https://app.example\""
+        ));
+
+        let config = Config::from_raw(raw).unwrap();
+        assert!(config.security.hsts_max_age_seconds.is_none());
+        assert_eq!(
+            config.security.public_origin.unwrap(),
+            "https://mainframe.example"
+        );
+        assert_eq!(config.security.cors_allowed_origins.len(), 2);
+
+        for origin in ["*", "localhost:5173", "http://localhost:5173/app"] {
+            let raw = raw_from_toml(&format!(
+                "database_url = \"sqlite://test.db\"\nsession_hmac_keys = \"k1:{KEY}\"\n\
+                 cors_allowed_origins = \"{origin}\""
+            ));
+            let err = Config::from_raw(raw).unwrap_err();
+            assert!(
+                err.problems[0].starts_with("CORS_ALLOWED_ORIGINS"),
+                "{origin}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_malformed_trusted_proxies() {
+        let raw = raw_from_toml(&format!(
+            "database_url = \"sqlite://test.db\"\nsession_hmac_keys = \"k1:{KEY}\"\n\
+             trusted_proxies = \"10.0.0.0/8, cloudflare\""
+        ));
+
+        let err = Config::from_raw(raw).unwrap_err();
+        assert_eq!(err.problems.len(), 1);
+        assert!(err.problems[0].starts_with("TRUSTED_PROXIES"));
+    }
+
+    #[test]
+    fn test_accepts_bare_numbers() {
+        let raw = raw_from_toml(&format!(
+            "database_url = \"sqlite://test.db\"\nsession_hmac_keys = \"k1:{KEY}\"\n\
+             login_lockout_threshold = 3\n\
+             password_history_size = \"4\""
+        ));
+
+        let config = Config::from_raw(raw).unwrap();
+        assert_eq!(config.lockout.threshold, 3);
+        assert_eq!(config.password_history_size, 4);
+    }
+
+    #[test]
+    fn test_trusts_cf_connecting_ip_only_when_enabled() {
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("cf-connecting-ip", "198.51.100.7".parse().unwrap());
+        let peer = "10.0.0.1".parse().unwrap();
+
+        for (setting, expected) in [
+            ("", "10.0.0.1"),
+            ("trust_cf_connecting_ip = true", "198.51.100.7"),
+        ] {
+            let raw = raw_from_toml(&format!(
+                "database_url = \"sqlite://test.db\"\nsession_hmac_keys = \"k1:{KEY}\"\n\
+                 trusted_proxies = \"10.0.0.0/8\"\n{setting}"
+            ));
+
+            let config = Config::from_raw(raw).unwrap();
+            assert_eq!(
+                config.trusted_proxies.client_ip(peer, &headers),
+                expected.parse::<std::net::IpAddr>().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_rejects_lockout_max_below_base() {
+        let raw = raw_from_toml(&format!(
+            "database_url = \"sqlite://test.db\"\nsession_hmac_keys = \"k1:{KEY}\"\n\
+             login_lockout_base_minutes = \"30\"\nlogin_lockout_max_minutes = \"10\""
+        ));
+
+        let err = Config::from_raw(raw).unwrap_err();
+        assert_eq!(err.problems.len(), 1);
+        assert!(err.problems[0].starts_with("LOGIN_LOCKOUT_MAX_MINUTES"));
+    }
+
+    #[test]
+    fn test_falls_back_to_legacy_hmac_key() {
+        let raw = raw_from_toml(&format!(
+            "database_url = \"sqlite://test.db\"\nsession_hmac_key = \"{KEY}\""
+        ));
+
+        let config = Config::from_raw(raw).unwrap();
+        assert_eq!(config.session_keyring.active_key_id(), LEGACY_HMAC_KEY_ID);
+
+        let raw = raw_from_toml("database_url = \"sqlite://test.db\"\nsession_hmac_key = \"abcd\"");
+        let config = Config::from_raw(raw).unwrap();
+        assert!(config.session_keyring.has_short_active_key());
+    }
+
+    #[test]
+    fn test_reports_every_problem() {
+        let raw = raw_from_toml(
+            "bind_address = \"not an address\"\n\
+             password_reset_code_ttl_minutes = \"0\"\n\
+             api_contact_email = \"\"",
+        );
+
+        let err = Config::from_raw(raw).unwrap_err();
+        assert_eq!(err.problems.len(), 4);
+        assert!(err.problems[0].starts_with("DATABASE_URL"));
+        assert!(err.problems[1].starts_with("BIND_ADDRESS"));
+        assert!(err.problems[2].starts_with("SESSION_HMAC_KEYS"));
+        assert!(err.problems[3].starts_with("PASSWORD_RESET_CODE_TTL_MINUTES"));
+    }
+
+    #[test]
+    fn test_rejects_unknown_settings() {
+        assert!(toml::from_str::<RawConfig>("databse_url = \"typo\"").is_err());
+    }
+}
@@ -1,6 +1,15 @@
 use axum_extra::extract::cookie::{Cookie, SameSite};
 use time::OffsetDateTime;
 
+use crate::token::get_token_bytes;
+
+/// Cookie holding the CSRF token for the session. Unlike the session cookie it can be
+/// read by scripts on the page, which echo it back in the [`CSRF_HEADER`] header.
+pub const CSRF_COOKIE: &str = "csrf_token";
+
+/// Header that cookie-authenticated requests which change state must carry.
+pub const CSRF_HEADER: &str = "x-csrf-token";
+
 pub fn build_session_cookie(token: String, expires_at: OffsetDateTime) -> Cookie<'static> {
     Cookie::build(("session_id", token))
         .path("/")
@@ -20,3 +29,29 @@ pub fn build_expired_session_cookie() -> Cookie<'static> {
         .expires(OffsetDateTime::UNIX_EPOCH)
         .build()
 }
+
+/// A fresh random CSRF token.
+pub fn generate_csrf_token() -> String {
+    get_token_bytes()
+}
+
+/// The CSRF cookie issued alongside the session cookie, living as long as the session.
+pub fn build_csrf_cookie(token: String, expires_at: OffsetDateTime) -> Cookie<'static> {
+    Cookie::build((CSRF_COOKIE, token))
+        .path("/")
+        .http_only(false)
+        .secure(true)
+        .same_site(SameSite::Strict)
+        .expires(expires_at)
+        .build()
+}
+
+pub fn build_expired_csrf_cookie() -> Cookie<'static> {
+    Cookie::build((CSRF_COOKIE, ""))
+        .path("/")
+        .http_only(false)
+        .secure(true)
+        .same_site(SameSite::Strict)
+        .expires(OffsetDateTime::UNIX_EPOCH)
+        .build()
+}
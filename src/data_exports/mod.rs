@@ -0,0 +1,5 @@
+pub mod models;
+pub use models::*;
+
+pub mod service;
+pub use service::*;
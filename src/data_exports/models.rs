@@ -0,0 +1,221 @@
+//! The personal data export format. Every field a user can see about themselves is
+//! included, while secrets such as the password hash and session tokens are not.
+//!
+//! The format is versioned with [`EXPORT_FORMAT_VERSION`]. Fields may be added without
+//! a new version, but removing or changing the meaning of one requires bumping it so
+//! that an import can tell which layout it is reading. Ids are kept so an import can
+//! restore the same records, and timestamps are RFC 3339.
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    applications::Application,
+    recipes::{Ingredient, Instruction, Recipe},
+    roles::RoleResponse,
+    sessions::Session,
+    users::UserBase,
+};
+
+/// Version of the export layout written by this build.
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+/// Everything Mainframe holds about a user.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDataExport {
+    /// Layout version, see [`EXPORT_FORMAT_VERSION`].
+    pub format_version: u32,
+    #[serde(with = "time::serde::rfc3339")]
+    pub exported_at: OffsetDateTime,
+    pub profile: ExportedProfile,
+    /// The roles held by the user and the permissions each one grants.
+    pub roles: Vec<RoleResponse>,
+    /// The applications enabled for the user.
+    pub applications: Vec<Application>,
+    /// Sessions that had not expired when the export was made.
+    pub sessions: Vec<ExportedSession>,
+    /// Every recipe owned by the user, public or not.
+    pub recipes: Vec<ExportedRecipe>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedProfile {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub first_name: String,
+    pub last_name: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    pub updated_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub last_login: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub password_expiration: Option<OffsetDateTime>,
+    pub is_disabled: bool,
+    pub disabled_reason: Option<String>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub disabled_at: Option<OffsetDateTime>,
+}
+
+impl From<UserBase> for ExportedProfile {
+    fn from(user: UserBase) -> Self {
+        Self {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+            first_name: user.first_name,
+            last_name: user.last_name,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            last_login: user.last_login,
+            password_expiration: user.password_expiration,
+            is_disabled: user.is_disabled,
+            disabled_reason: user.disabled_reason,
+            disabled_at: user.disabled_at,
+        }
+    }
+}
+
+/// A session without its token hashes.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedSession {
+    pub id: Uuid,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+    /// When the session token was last rotated or first issued.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub rotated_at: Option<OffsetDateTime>,
+    pub password_change_required: bool,
+}
+
+impl From<Session> for ExportedSession {
+    fn from(session: Session) -> Self {
+        Self {
+            id: session.id,
+            expires_at: session.expires_at,
+            rotated_at: session.rotated_at,
+            password_change_required: session.password_change_required,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedRecipe {
+    pub id: Uuid,
+    pub name: String,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub difficulty: Option<String>,
+    pub estimated_duration: Option<String>,
+    pub is_public: bool,
+    /// Ordered by position.
+    pub ingredients: Vec<ExportedStep>,
+    /// Ordered by position.
+    pub instructions: Vec<ExportedStep>,
+}
+
+/// An ingredient or instruction of a recipe.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedStep {
+    pub id: Uuid,
+    pub position: i64,
+    pub description: String,
+}
+
+impl From<Ingredient> for ExportedStep {
+    fn from(ingredient: Ingredient) -> Self {
+        Self {
+            id: ingredient.id,
+            position: ingredient.position,
+            description: ingredient.description,
+        }
+    }
+}
+
+impl From<Instruction> for ExportedStep {
+    fn from(instruction: Instruction) -> Self {
+        Self {
+            id: instruction.id,
+            position: instruction.position,
+            description: instruction.description,
+        }
+    }
+}
+
+impl From<Recipe> for ExportedRecipe {
+    fn from(recipe: Recipe) -> Self {
+        let mut ingredients: Vec<ExportedStep> =
+            recipe.ingredients.into_iter().map(Into::into).collect();
+        ingredients.sort_by_key(|step| step.position);
+
+        let mut instructions: Vec<ExportedStep> =
+            recipe.instructions.into_iter().map(Into::into).collect();
+        instructions.sort_by_key(|step| step.position);
+
+        Self {
+            id: recipe.id,
+            name: recipe.name,
+            author: recipe.author,
+            description: recipe.description,
+            difficulty: recipe.difficulty,
+            estimated_duration: recipe.estimated_duration,
+            is_public: recipe.is_public,
+            ingredients,
+            instructions,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recipe_steps_are_ordered_by_position() {
+        let recipe_id = Uuid::now_v7();
+        let step = |position| Ingredient {
+            id: Uuid::now_v7(),
+            recipe_id,
+            position,
+            description: format!("step {position}"),
+        };
+        let recipe = Recipe {
+            id: recipe_id,
+            user_id: Some(Uuid::now_v7()),
+            name: "Bread".into(),
+            author: None,
+            description: None,
+            difficulty: None,
+            estimated_duration: None,
+            is_public: false,
+            ingredients: vec![step(2), step(0), step(1)],
+            instructions: vec![],
+        };
+
+        let exported = ExportedRecipe::from(recipe);
+        let positions: Vec<i64> = exported.ingredients.iter().map(|s| s.position).collect();
+        assert_eq!(positions, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_session_export_leaves_out_tokens() {
+        let session = Session::new(
+            Uuid::now_v7(),
+            Uuid::now_v7(),
+            "secret-hash".into(),
+            "key".into(),
+        );
+
+        let json = serde_json::to_string(&ExportedSession::from(session)).unwrap();
+        assert!(!json.contains("secret-hash"));
+    }
+}
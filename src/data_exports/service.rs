@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    applications::IApplicationRepository,
+    audit::{AuditAction, AuditEvent, IAuditRepository},
+    data_exports::{EXPORT_FORMAT_VERSION, UserDataExport},
+    errors::ServiceError,
+    recipes::IRecipeService,
+    roles::{IRoleRepository, RoleResponse},
+    sessions::ISessionRepository,
+    users::IUserRepository,
+};
+
+#[async_trait]
+pub trait IDataExportService: Send + Sync {
+    /// Collect everything held about a user into a single export.
+    async fn export_user(&self, user_id: Uuid) -> Result<UserDataExport, ServiceError>;
+}
+
+pub struct DataExportService {
+    users: Arc<dyn IUserRepository>,
+    roles: Arc<dyn IRoleRepository>,
+    sessions: Arc<dyn ISessionRepository>,
+    applications: Arc<dyn IApplicationRepository>,
+    recipes: Arc<dyn IRecipeService>,
+    audit: Arc<dyn IAuditRepository>,
+}
+
+impl DataExportService {
+    pub fn new(
+        user_repo: Arc<dyn IUserRepository>,
+        role_repo: Arc<dyn IRoleRepository>,
+        session_repo: Arc<dyn ISessionRepository>,
+        application_repo: Arc<dyn IApplicationRepository>,
+        recipe_service: Arc<dyn IRecipeService>,
+        audit_repo: Arc<dyn IAuditRepository>,
+    ) -> Self {
+        Self {
+            users: user_repo,
+            roles: role_repo,
+            sessions: session_repo,
+            applications: application_repo,
+            recipes: recipe_service,
+            audit: audit_repo,
+        }
+    }
+
+    async fn roles_with_permissions(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<RoleResponse>, ServiceError> {
+        let roles = self.roles.get_by_user_id(user_id).await?;
+
+        let mut responses = Vec::with_capacity(roles.len());
+        for role in roles {
+            let permissions = self.roles.get_permissions(role.id).await?;
+            responses.push(RoleResponse {
+                id: role.id,
+                name: role.name,
+                is_system: role.is_system,
+                permissions,
+            });
+        }
+
+        Ok(responses)
+    }
+}
+
+#[async_trait]
+impl IDataExportService for DataExportService {
+    async fn export_user(&self, user_id: Uuid) -> Result<UserDataExport, ServiceError> {
+        let user = self.users.get_by_id(user_id).await?;
+        let (roles, applications, sessions, recipes) = tokio::try_join!(
+            self.roles_with_permissions(user_id),
+            async {
+                let applications = self.applications.get_for_user(user_id).await?;
+                Ok::<_, ServiceError>(applications)
+            },
+            async {
+                let sessions = self.sessions.get_active_for_user(user_id).await?;
+                Ok::<_, ServiceError>(sessions)
+            },
+            self.recipes.get_owned_by(user_id),
+        )?;
+
+        let export = UserDataExport {
+            format_version: EXPORT_FORMAT_VERSION,
+            exported_at: OffsetDateTime::now_utc(),
+            profile: user.into(),
+            roles,
+            applications,
+            sessions: sessions.into_iter().map(Into::into).collect(),
+            recipes: recipes.into_iter().map(Into::into).collect(),
+        };
+
+        self.audit
+            .record(
+                &AuditEvent::success(AuditAction::UserDataExported)
+                    .target("user", user_id)
+                    .details(json!({ "recipes": export.recipes.len() })),
+            )
+            .await;
+
+        tracing::info!(user_id = %user_id, "Personal data exported");
+
+        Ok(export)
+    }
+}
@@ -1,20 +1,20 @@
-use anyhow::anyhow;
 use sqlx::{
     SqlitePool,
+    migrate::Migrator,
     sqlite::{SqliteConnectOptions, SqliteJournalMode},
 };
-use std::{env, str::FromStr, time::Duration};
+use std::{str::FromStr, time::Duration};
+
+/// The migrations embedded in the binary, applied at startup.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
 pub struct Database {
     pub pool: SqlitePool,
 }
 
 impl Database {
-    pub async fn new() -> Result<Self, anyhow::Error> {
-        let database_url =
-            env::var("DATABASE_URL").map_err(|_| anyhow!("DATABASE_URL must be set"))?;
-
-        let options = SqliteConnectOptions::from_str(&database_url)?
+    pub async fn new(database_url: &str) -> Result<Self, anyhow::Error> {
+        let options = SqliteConnectOptions::from_str(database_url)?
             .create_if_missing(true)
             .foreign_keys(true)
             .journal_mode(SqliteJournalMode::Wal)
@@ -24,8 +24,27 @@ impl Database {
         let pool = SqlitePool::connect_with(options).await?;
 
         // Run migrations
-        sqlx::migrate!("./migrations").run(&pool).await?;
+        MIGRATOR.run(&pool).await?;
 
         Ok(Self { pool })
     }
 }
+
+#[cfg(test)]
+impl Database {
+    /// A migrated in-memory database. One connection, since every connection to
+    /// `sqlite::memory:` opens a separate database.
+    pub async fn in_memory() -> Self {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")
+            .unwrap()
+            .foreign_keys(true);
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await
+            .unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+
+        Self { pool }
+    }
+}
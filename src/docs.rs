@@ -7,9 +7,9 @@
 //!
 //! # Configuration
 //!
-//! API metadata (title, version, description, contact info) is loaded from environment
-//! variables at runtime. To customize the API documentation, add the following variables
-//! to your `.env` file:
+//! API metadata (title, version, description, contact info) comes from the application
+//! [`Config`](crate::config::Config). To customize the API documentation, add the following
+//! variables to your `.env` file (or the matching keys to the TOML config file):
 //!
 //! - `API_TITLE` - The name of your API (default: "Mainframe API")
 //! - `API_VERSION` - The API version string (default: "1.0.0")
@@ -36,51 +36,59 @@
 //! documentation into a single spec.
 
 use crate::{
-    authentication::AuthApiDoc, roles::RolesApiDoc, sessions::SessionApiDoc, users::UsersApiDoc,
+    api_tokens::ApiTokensApiDoc,
+    applications::ApplicationsApiDoc,
+    audit::AuditApiDoc,
+    authentication::AuthApiDoc,
+    config::ApiDocsConfig,
+    errors::{FieldViolation, ProblemDetails},
+    health::HealthApiDoc,
+    metrics::MetricsApiDoc,
+    password_resets::PasswordResetApiDoc,
+    roles::RolesApiDoc,
+    sessions::SessionApiDoc,
+    users::UsersApiDoc,
 };
-use std::env;
 use utoipa::OpenApi;
 
 // Clippy lint triggered by utoipa macro expansion, not our code
 #[allow(clippy::needless_for_each)]
 #[derive(utoipa::OpenApi)]
-#[openapi(paths(), components(), tags())]
+#[openapi(paths(), components(schemas(ProblemDetails, FieldViolation)), tags())]
 pub struct ApiDoc;
 
 impl ApiDoc {
-    pub fn merge_modules() -> utoipa::openapi::OpenApi {
+    pub fn merge_modules(config: &ApiDocsConfig) -> utoipa::openapi::OpenApi {
         let mut api_docs = Self::openapi();
 
-        // Update info from environment variables
-        api_docs.info.title = env::var("API_TITLE").unwrap_or_else(|_| "Mainframe API".to_string());
-        api_docs.info.version = env::var("API_VERSION").unwrap_or_else(|_| "1.0.0".to_string());
-        api_docs.info.description = Some(
-            env::var("API_DESCRIPTION")
-                .unwrap_or_else(|_| "Self-hosted personal productivity platform API".to_string()),
-        );
+        // Update info from configuration
+        api_docs.info.title.clone_from(&config.title);
+        api_docs.info.version.clone_from(&config.version);
+        api_docs.info.description = Some(config.description.clone());
 
         if let Some(contact) = &mut api_docs.info.contact {
-            contact.name =
-                Some(env::var("API_CONTACT_NAME").unwrap_or_else(|_| "Administrator".to_string()));
-            contact.email = env::var("API_CONTACT_EMAIL").ok();
+            contact.name = Some(config.contact_name.clone());
+            contact.email.clone_from(&config.contact_email);
         } else {
             use utoipa::openapi::ContactBuilder;
 
-            let mut contact = ContactBuilder::new();
-            contact = contact.name(Some(
-                env::var("API_CONTACT_NAME").unwrap_or_else(|_| "Administrator".to_string()),
-            ));
-            if let Ok(email) = env::var("API_CONTACT_EMAIL") {
-                contact = contact.email(Some(email));
-            }
+            let contact = ContactBuilder::new()
+                .name(Some(config.contact_name.clone()))
+                .email(config.contact_email.clone());
             api_docs.info.contact = Some(contact.build());
         }
 
         // Merge module docs
         api_docs.merge(UsersApiDoc::openapi());
         api_docs.merge(AuthApiDoc::openapi());
+        api_docs.merge(ApiTokensApiDoc::openapi());
         api_docs.merge(SessionApiDoc::openapi());
         api_docs.merge(RolesApiDoc::openapi());
+        api_docs.merge(PasswordResetApiDoc::openapi());
+        api_docs.merge(ApplicationsApiDoc::openapi());
+        api_docs.merge(AuditApiDoc::openapi());
+        api_docs.merge(MetricsApiDoc::openapi());
+        api_docs.merge(HealthApiDoc::openapi());
 
         api_docs
     }
@@ -10,20 +10,62 @@
 
 use axum::{
     Json,
-    http::StatusCode,
+    extract::rejection::JsonRejection,
+    http::{StatusCode, header},
     response::{IntoResponse, Response},
 };
 use serde::Serialize;
-use std::fmt::Debug;
+use std::{borrow::Cow, fmt::Debug, time::Duration};
 use thiserror::Error;
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+use validator::{ValidationErrors, ValidationErrorsKind};
 
-/// The structured, public-facing error response sent to API clients.
+use crate::request_context::RequestContext;
+
+/// Media type of [`ProblemDetails`] responses.
+const PROBLEM_JSON: &str = "application/problem+json";
+
+/// The structured, public-facing error response sent to API clients, following
+/// RFC 9457 (Problem Details for HTTP APIs).
 ///
-/// This struct is serialized to JSON and provides a consistent error format
-/// for all client-facing errors, without leaking internal implementation details.
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
+/// Every error is described by a stable machine-readable `code`, so clients never
+/// have to parse `detail`, which is meant for people and may change. No internal
+/// implementation details are included.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemDetails {
+    /// Always `about:blank`, as problems are told apart by `code`.
+    #[serde(rename = "type")]
+    pub problem_type: &'static str,
+    /// The reason phrase of the status code.
+    pub title: &'static str,
+    pub status: u16,
+    pub detail: String,
+    #[schema(example = "validation_failed")]
+    pub code: &'static str,
+    /// Identifies the request in the server logs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// For `validation_failed` and `conflict`, what is wrong with each field of the
+    /// request body.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub violations: Vec<FieldViolation>,
+    /// For `rate_limited` and `account_locked`, the seconds to wait before trying
+    /// again, as in the `Retry-After` header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+}
+
+/// A validation rule broken by one field of a request body.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldViolation {
+    /// Path to the field as named in the JSON body, e.g. `email` or `steps[2].description`.
+    pub field: String,
+    /// The rule that was broken, e.g. `length`, `email` or `password`.
+    pub code: String,
+    pub message: String,
 }
 
 /// The primary error type for the Axum HTTP boundary.
@@ -41,16 +83,48 @@ pub enum ApiError {
     },
     #[error("Bad Request: {0}")]
     BadRequest(String),
+    /// The request would create a duplicate of something that must be unique.
+    #[error("A {entity} with the same {} already exists", .fields.join(" and "))]
+    Conflict {
+        entity: &'static str,
+        fields: Vec<String>,
+    },
     #[error("Unauthorized")]
     Unauthorized {
         /// The internal reason for the failure, used for logging.
         reason: String,
     },
+    /// A login with an unknown username or the wrong password.
+    #[error("Invalid username or password")]
+    InvalidCredentials,
+    /// A login on an account locked by failed logins, which may be retried once the
+    /// lock runs out.
+    #[error("Account locked after repeated failed logins, try again later")]
+    AccountLocked { retry_after: Duration },
+    #[error("Account disabled by an administrator")]
+    AccountDisabled,
     #[error("Forbidden")]
     Forbidden {
         /// The internal reason for the failure, used for logging.
         reason: String,
     },
+    /// A state-changing request failed the CSRF check. The reason is safe to show,
+    /// and helps client authors fix their requests.
+    #[error("CSRF check failed: {0}")]
+    CsrfRejected(&'static str),
+    /// The client has hit a rate limit and may retry after the given delay.
+    #[error("Too many requests, try again later")]
+    TooManyRequests {
+        /// The internal reason for the failure, used for logging.
+        reason: String,
+        retry_after: Duration,
+    },
+    /// The request body could not be read as JSON of the expected shape.
+    #[error("Invalid JSON: {}", .0.body_text())]
+    InvalidJson(#[from] JsonRejection),
+    /// The request body was well-formed but broke the endpoint's validation rules.
+    #[error("Validation failed")]
+    Validation(#[from] ValidationErrors),
     #[error("An internal server error occurred")]
     Internal(#[from] anyhow::Error),
 }
@@ -64,13 +138,16 @@ impl From<ServiceError> for ApiError {
     fn from(err: ServiceError) -> Self {
         match err {
             ServiceError::Unauthorized(reason) => Self::Unauthorized { reason },
-            ServiceError::AccountLocked | ServiceError::InvalidUsernameOrPassword => {
-                Self::Unauthorized {
-                    reason: err.to_string(),
-                }
-            }
+            ServiceError::InvalidUsernameOrPassword => Self::InvalidCredentials,
+            ServiceError::AccountLocked { locked_until } => Self::AccountLocked {
+                retry_after: (locked_until - OffsetDateTime::now_utc())
+                    .try_into()
+                    .unwrap_or_default(),
+            },
+            ServiceError::AccountDisabled => Self::AccountDisabled,
             ServiceError::Forbidden(reason) => Self::Forbidden { reason },
             ServiceError::BadRequest(msg) => Self::BadRequest(msg),
+            ServiceError::Conflict { entity, fields } => Self::Conflict { entity, fields },
             ServiceError::Repository(repo_err) => match repo_err {
                 RepositoryError::NotFound {
                     entity,
@@ -82,6 +159,7 @@ impl From<ServiceError> for ApiError {
                     value,
                 },
                 RepositoryError::Database(e) => Self::Internal(e.into()),
+                RepositoryError::Conflict { entity, fields } => Self::Conflict { entity, fields },
                 RepositoryError::ArgumentOutOfRange { .. } => {
                     Self::BadRequest(format!("bad request: {repo_err}"))
                 }
@@ -100,6 +178,82 @@ impl From<ServiceError> for ApiError {
     }
 }
 
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::NotFound { .. } => StatusCode::NOT_FOUND,
+            Self::Conflict { .. } => StatusCode::CONFLICT,
+            Self::BadRequest(_) | Self::Validation(_) => StatusCode::BAD_REQUEST,
+            Self::InvalidJson(rejection) => rejection.status(),
+            Self::Unauthorized { .. }
+            | Self::InvalidCredentials
+            | Self::AccountLocked { .. }
+            | Self::AccountDisabled => StatusCode::UNAUTHORIZED,
+            Self::Forbidden { .. } | Self::CsrfRejected(_) => StatusCode::FORBIDDEN,
+            Self::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+
+    /// The stable, machine-readable code sent to clients. Never change one that has
+    /// been released, clients match on them.
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound { .. } => "not_found",
+            Self::BadRequest(_) => "bad_request",
+            Self::Conflict { .. } => "conflict",
+            Self::Unauthorized { .. } => "unauthorized",
+            Self::InvalidCredentials => "invalid_credentials",
+            Self::AccountLocked { .. } => "account_locked",
+            Self::AccountDisabled => "account_disabled",
+            Self::Forbidden { .. } => "forbidden",
+            Self::CsrfRejected(_) => "csrf_rejected",
+            Self::TooManyRequests { .. } => "rate_limited",
+            Self::InvalidJson(JsonRejection::MissingJsonContentType(_)) => "unsupported_media_type",
+            Self::InvalidJson(JsonRejection::JsonSyntaxError(_)) => "malformed_json",
+            Self::InvalidJson(_) => "invalid_body",
+            Self::Validation(_) => "validation_failed",
+            Self::Internal(_) => "internal_error",
+        }
+    }
+
+    fn log(&self) {
+        match self {
+            // For 5xx errors, log the full `Debug` representation, including the source chain.
+            Self::Internal(_) => {
+                tracing::error!(error = ?self, "Request failed with a server error");
+            }
+            // For 4xx errors, log the specific reason for auth failures, or the
+            // general error for other client issues.
+            Self::Unauthorized { reason } => {
+                tracing::warn!(reason = %reason, "Unauthorized access attempt");
+            }
+            Self::InvalidCredentials | Self::AccountLocked { .. } | Self::AccountDisabled => {
+                tracing::warn!(error = %self, "Login refused");
+            }
+            Self::Forbidden { reason } => {
+                tracing::warn!(reason = %reason, "Forbidden access attempt");
+            }
+            Self::CsrfRejected(reason) => {
+                tracing::warn!(reason = %reason, "Request rejected by CSRF protection");
+            }
+            Self::TooManyRequests {
+                reason,
+                retry_after,
+            } => {
+                tracing::warn!(
+                    reason = %reason,
+                    retry_after = ?retry_after,
+                    "Request rejected by rate limit"
+                );
+            }
+            _ => {
+                tracing::info!(error = %self, "Request failed with a client error");
+            }
+        }
+    }
+}
+
 /// Converts an `ApiError` into a user-facing HTTP `Response`.
 ///
 /// This is the single point of truth for all error handling at the application
@@ -107,52 +261,142 @@ impl From<ServiceError> for ApiError {
 /// 1.  Mapping the `ApiError` variant to the correct `StatusCode`.
 /// 2.  Performing structured logging with `tracing`.
 /// 3.  Ensuring sensitive details are logged but not sent to the client.
-/// 4.  Serializing a public-facing `ErrorResponse` as the JSON response body.
+/// 4.  Serializing a public-facing `ProblemDetails` as the `application/problem+json`
+///     response body.
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let (status, log_as_error) = match &self {
-            Self::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, true),
-            Self::NotFound { .. } => (StatusCode::NOT_FOUND, false),
-            Self::BadRequest(_) => (StatusCode::BAD_REQUEST, false),
-            Self::Unauthorized { .. } => (StatusCode::UNAUTHORIZED, false),
-            Self::Forbidden { .. } => (StatusCode::FORBIDDEN, false),
-        };
+        let status = self.status();
+        self.log();
 
-        if log_as_error {
-            // For 5xx errors, log the full `Debug` representation, including the source chain.
-            tracing::error!(error = ?self, "Request failed with a server error");
-        } else {
-            // For 4xx errors, log the specific reason for auth failures, or the
-            // general error for other client issues.
-            match &self {
-                Self::Unauthorized { reason } => {
-                    tracing::warn!(reason = %reason, "Unauthorized access attempt");
-                }
-                Self::Forbidden { reason } => {
-                    tracing::warn!(reason = %reason, "Forbidden access attempt");
-                }
-                _ => {
-                    tracing::info!(error = %self, "Request failed with a client error");
-                }
+        // Whole seconds, rounded up so that retrying on time never hits the limit again.
+        let retry_after = match &self {
+            Self::TooManyRequests { retry_after, .. } | Self::AccountLocked { retry_after } => {
+                Some((retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0)).max(1))
             }
-        }
+            _ => None,
+        };
 
-        // Create the public-facing JSON response, ensuring no sensitive reasons are included.
-        let public_error_message = match self {
+        let violations = match &self {
+            Self::Validation(errors) => field_violations(errors),
+            Self::Conflict { fields, .. } => fields
+                .iter()
+                .map(|field| FieldViolation {
+                    field: field.clone(),
+                    code: "unique".to_string(),
+                    message: "is already in use".to_string(),
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        // Create the public-facing response, ensuring no sensitive reasons are included.
+        let detail = match &self {
             Self::Internal(_) => "An internal server error occurred".to_string(),
             Self::Unauthorized { .. } => "Unauthorized".to_string(),
             Self::Forbidden { .. } => "Forbidden".to_string(),
             _ => self.to_string(),
         };
 
-        let body = Json(ErrorResponse {
-            error: public_error_message,
-        });
+        let problem = ProblemDetails {
+            problem_type: "about:blank",
+            title: status.canonical_reason().unwrap_or("Error"),
+            status: status.as_u16(),
+            detail,
+            code: self.code(),
+            request_id: RequestContext::current().request_id,
+            violations,
+            retry_after,
+        };
 
-        (status, body).into_response()
+        let mut response = (
+            status,
+            [(header::CONTENT_TYPE, PROBLEM_JSON)],
+            Json(problem),
+        )
+            .into_response();
+        if let Some(seconds) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, seconds.into());
+        }
+
+        response
     }
 }
 
+/// Flatten nested validation errors into one violation per broken rule, naming
+/// fields as they appear in the camelCase JSON body.
+fn field_violations(errors: &ValidationErrors) -> Vec<FieldViolation> {
+    fn collect(errors: &ValidationErrors, prefix: &str, violations: &mut Vec<FieldViolation>) {
+        let mut fields: Vec<_> = errors.errors().iter().collect();
+        fields.sort_unstable_by_key(|(field, _)| *field);
+
+        for (field, kind) in fields {
+            let path = if prefix.is_empty() {
+                to_camel_case(field)
+            } else {
+                format!("{prefix}.{}", to_camel_case(field))
+            };
+
+            match kind {
+                ValidationErrorsKind::Field(field_errors) => {
+                    violations.extend(field_errors.iter().map(|error| {
+                        FieldViolation {
+                            field: path.clone(),
+                            code: error.code.to_string(),
+                            message: error
+                                .message
+                                .as_ref()
+                                .map_or_else(|| default_violation_message(error), Cow::to_string),
+                        }
+                    }));
+                }
+                ValidationErrorsKind::Struct(nested) => collect(nested, &path, violations),
+                ValidationErrorsKind::List(items) => {
+                    for (index, nested) in items {
+                        collect(nested, &format!("{path}[{index}]"), violations);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut violations = Vec::new();
+    collect(errors, "", &mut violations);
+    violations
+}
+
+fn default_violation_message(error: &validator::ValidationError) -> String {
+    let param = |name: &str| error.params.get(name).map(ToString::to_string);
+
+    match (error.code.as_ref(), param("min"), param("max")) {
+        ("length", Some(min), Some(max)) => format!("must be {min} to {max} characters long"),
+        ("length", Some(min), None) => format!("must be at least {min} long"),
+        ("length", None, Some(max)) => format!("must be at most {max} long"),
+        ("range", Some(min), Some(max)) => format!("must be between {min} and {max}"),
+        ("email", ..) => "must be a valid email address".to_string(),
+        ("url", ..) => "must be a valid URL".to_string(),
+        ("required", ..) => "is required".to_string(),
+        _ => "is invalid".to_string(),
+    }
+}
+
+fn to_camel_case(field: &str) -> String {
+    let mut camel = String::with_capacity(field.len());
+    let mut upper_next = false;
+    for c in field.chars() {
+        if c == '_' {
+            upper_next = true;
+        } else if upper_next {
+            camel.extend(c.to_uppercase());
+            upper_next = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
 /// Represents failures within the application's service (business logic) layer.
 ///
 /// This error enum should have no knowledge of HTTP-specific concepts. It is
@@ -168,7 +412,10 @@ pub enum ServiceError {
     InvalidUsernameOrPassword,
 
     #[error("account locked due to repeated, failed login attempts")]
-    AccountLocked,
+    AccountLocked { locked_until: OffsetDateTime },
+
+    #[error("account disabled by an administrator")]
+    AccountDisabled,
 
     #[error("forbidden: {0}")]
     Forbidden(String),
@@ -176,6 +423,12 @@ pub enum ServiceError {
     #[error("bad request: {0}")]
     BadRequest(String),
 
+    #[error("{entity} with the same {} already exists", .fields.join(" and "))]
+    Conflict {
+        entity: &'static str,
+        fields: Vec<String>,
+    },
+
     #[error(transparent)]
     Repository(#[from] RepositoryError),
 
@@ -198,7 +451,15 @@ pub enum ServiceError {
 pub enum RepositoryError {
     /// Low‑level `SQLx` errors (connection failures, constraint violations, …).
     #[error("database error: {0}")]
-    Database(#[from] sqlx::Error),
+    Database(sqlx::Error),
+
+    /// A write would break a `UNIQUE` constraint, e.g. a username that is taken.
+    #[error("{entity} with the same {} already exists", .fields.join(" and "))]
+    Conflict {
+        entity: &'static str,
+        /// The conflicting columns, named as in request and response bodies.
+        fields: Vec<String>,
+    },
 
     /// A record was not found in the database.
     #[error("{entity} with {property} {value} not found")]
@@ -213,3 +474,152 @@ pub enum RepositoryError {
     #[error("argument out of range: {field} with value `{value}` is not allowed")]
     ArgumentOutOfRange { field: &'static str, value: String },
 }
+
+impl From<sqlx::Error> for RepositoryError {
+    fn from(err: sqlx::Error) -> Self {
+        let conflict = err
+            .as_database_error()
+            .filter(|db_err| db_err.is_unique_violation())
+            .map(|db_err| unique_violation_conflict(db_err.message()));
+
+        conflict.unwrap_or(Self::Database(err))
+    }
+}
+
+/// Build a [`RepositoryError::Conflict`] from an `SQLite` message such as
+/// `UNIQUE constraint failed: users.username` or, for a composite key,
+/// `UNIQUE constraint failed: user_roles.user_id, user_roles.role_id`.
+fn unique_violation_conflict(message: &str) -> RepositoryError {
+    let columns: Vec<(&str, &str)> = message
+        .strip_prefix("UNIQUE constraint failed: ")
+        .unwrap_or_default()
+        .split(", ")
+        .filter_map(|column| column.split_once('.'))
+        .collect();
+
+    let table = columns.first().map(|(table, _)| *table).unwrap_or_default();
+    let entity = match table {
+        "users" => "user",
+        "roles" => "role",
+        "user_roles" => "role assignment",
+        "role_permissions" => "role permission",
+        "user_applications" => "application grant",
+        "api_tokens" => "token",
+        "api_token_scopes" => "token scope",
+        _ => "record",
+    };
+
+    RepositoryError::Conflict {
+        entity,
+        fields: columns
+            .iter()
+            .map(|(_, column)| to_camel_case(column))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use validator::Validate;
+
+    #[derive(Validate)]
+    struct Step {
+        #[validate(length(min = 1))]
+        description: String,
+    }
+
+    #[derive(Validate)]
+    struct Form {
+        #[validate(length(min = 1, max = 5))]
+        display_name: String,
+        #[validate(email)]
+        email: String,
+        #[validate(nested)]
+        steps: Vec<Step>,
+    }
+
+    #[test]
+    fn test_field_violations_use_json_names() {
+        let form = Form {
+            display_name: String::new(),
+            email: "nope".into(),
+            steps: vec![
+                Step {
+                    description: "ok".into(),
+                },
+                Step {
+                    description: String::new(),
+                },
+            ],
+        };
+
+        let violations = field_violations(&form.validate().unwrap_err());
+        let fields: Vec<_> = violations.iter().map(|v| v.field.as_str()).collect();
+        assert_eq!(fields, ["displayName", "email", "steps[1].description"]);
+        assert_eq!(violations[0].code, "length");
+        assert_eq!(violations[0].message, "must be 1 to 5 characters long");
+    }
+
+    #[test]
+    fn test_unique_violations_name_the_fields() {
+        let RepositoryError::Conflict { entity, fields } =
+            unique_violation_conflict("UNIQUE constraint failed: users.username")
+        else {
+            panic!("expected a conflict");
+        };
+        assert_eq!(entity, "user");
+        assert_eq!(fields, ["username"]);
+
+        let RepositoryError::Conflict { entity, fields } = unique_violation_conflict(
+            "UNIQUE constraint failed: user_roles.user_id, user_roles.role_id",
+        ) else {
+            panic!("expected a conflict");
+        };
+        assert_eq!(entity, "role assignment");
+        assert_eq!(fields, ["userId", "roleId"]);
+
+        let response = ApiError::from(ServiceError::Repository(RepositoryError::Conflict {
+            entity,
+            fields,
+        }))
+        .into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn test_problem_hides_internal_reasons() {
+        let response = ApiError::Forbidden {
+            reason: "missing users:manage".into(),
+        }
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], PROBLEM_JSON);
+    }
+
+    #[test]
+    fn test_login_refusals_keep_the_status_but_differ_in_code() {
+        let locked = ApiError::from(ServiceError::AccountLocked {
+            locked_until: OffsetDateTime::now_utc() + time::Duration::minutes(5),
+        });
+        assert_eq!(locked.code(), "account_locked");
+        assert_eq!(
+            ApiError::from(ServiceError::AccountDisabled).code(),
+            "account_disabled"
+        );
+        assert_eq!(
+            ApiError::from(ServiceError::InvalidUsernameOrPassword).code(),
+            "invalid_credentials"
+        );
+
+        let response = locked.into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let retry_after: u64 = response.headers()[header::RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((299..=300).contains(&retry_after));
+    }
+}
@@ -1,11 +1,38 @@
 use axum::extract::FromRequestParts;
 
-use crate::{sessions::Session, users::UserResponse};
+use crate::{api_tokens::ApiToken, errors::ApiError, sessions::Session, users::UserResponse};
 
 #[derive(Debug, Clone)]
 pub struct AuthenticatedUser {
     pub user: UserResponse,
-    pub session: Session,
+    pub credential: Credential,
+}
+
+/// How the request was authenticated.
+#[derive(Debug, Clone)]
+pub enum Credential {
+    /// A `session_id` cookie from an interactive login.
+    Session(Session),
+    /// A personal access token sent as `Authorization: Bearer`.
+    ApiToken(ApiToken),
+}
+
+impl AuthenticatedUser {
+    /// The session behind the request, or `None` when it used an API token.
+    pub const fn session(&self) -> Option<&Session> {
+        match &self.credential {
+            Credential::Session(session) => Some(session),
+            Credential::ApiToken(_) => None,
+        }
+    }
+
+    /// The session behind the request, refusing requests made with an API token.
+    /// Used for account changes a leaked token should not be able to make.
+    pub fn require_session(&self) -> Result<&Session, ApiError> {
+        self.session().ok_or_else(|| ApiError::Forbidden {
+            reason: "this action requires a session login and cannot use an API token".into(),
+        })
+    }
 }
 
 impl<S> FromRequestParts<S> for AuthenticatedUser
@@ -0,0 +1,9 @@
+use axum::extract::FromRequest;
+
+use crate::errors::ApiError;
+
+/// A JSON request body without validation rules, like [`axum::Json`] but rejecting
+/// malformed bodies with the same problem details as every other error.
+#[derive(FromRequest)]
+#[from_request(via(axum::Json), rejection(ApiError))]
+pub struct JsonBody<T>(pub T);
@@ -1,7 +1,10 @@
 pub mod authenticated_user;
 
-pub mod admin_user;
-pub use admin_user::*;
+pub mod json_body;
+pub use json_body::*;
+
+pub mod require_permission;
+pub use require_permission::*;
 
 pub mod validated_json;
 pub use validated_json::*;
@@ -0,0 +1,49 @@
+use std::marker::PhantomData;
+
+use axum::extract::FromRequestParts;
+
+use crate::{
+    errors::ApiError,
+    extractors::authenticated_user::{AuthenticatedUser, Credential},
+    roles::RequiredPermission,
+    services::ServiceContainer,
+    users::UserResponse,
+};
+
+/// Extractor that only succeeds when the authenticated user's roles grant the
+/// permission `P`, e.g. `RequirePermission<permissions::UsersManage>`.
+#[allow(unused)]
+pub struct RequirePermission<P> {
+    pub user: UserResponse,
+    pub credential: Credential,
+    permission: PhantomData<P>,
+}
+
+impl<S, P> FromRequestParts<S> for RequirePermission<P>
+where
+    S: Send + Sync,
+    P: RequiredPermission,
+    ServiceContainer: axum::extract::FromRef<S>,
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        let AuthenticatedUser { user, credential } =
+            AuthenticatedUser::from_request_parts(parts, state).await?;
+
+        if !user.has_permission(P::PERMISSION) {
+            return Err(ApiError::Forbidden {
+                reason: format!("user lacks the `{}` permission", P::PERMISSION),
+            });
+        }
+
+        Ok(Self {
+            user,
+            credential,
+            permission: PhantomData,
+        })
+    }
+}
@@ -1,12 +1,13 @@
 use axum::{
     Json,
     extract::{FromRequest, Request, rejection::JsonRejection},
-    http::StatusCode,
     response::{IntoResponse, Response},
 };
 use serde::de::DeserializeOwned;
 use validator::Validate;
 
+use crate::errors::ApiError;
+
 pub struct ValidatedJson<T: Validate>(pub T);
 
 impl<T, S> FromRequest<S> for ValidatedJson<T>
@@ -36,20 +37,19 @@ pub enum ValidationError {
     Validation(validator::ValidationErrors),
 }
 
+impl From<ValidationError> for ApiError {
+    fn from(err: ValidationError) -> Self {
+        match err {
+            ValidationError::JsonRejection(rejection) => Self::InvalidJson(rejection),
+            ValidationError::Validation(errors) => Self::Validation(errors),
+        }
+    }
+}
+
+// Rendered like every other error, with a violation per field for the frontend.
 impl IntoResponse for ValidationError {
     fn into_response(self) -> Response {
-        match self {
-            Self::JsonRejection(rejection) => (
-                StatusCode::BAD_REQUEST,
-                format!("Invalid JSON: {rejection}"),
-            )
-                .into_response(),
-            Self::Validation(errors) => (
-                StatusCode::BAD_REQUEST,
-                format!("Validation failed: {errors}"),
-            )
-                .into_response(),
-        }
+        ApiError::from(self).into_response()
     }
 }
 
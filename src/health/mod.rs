@@ -0,0 +1,11 @@
+pub mod models;
+pub use models::*;
+
+pub mod repository;
+pub use repository::*;
+
+pub mod service;
+pub use service::*;
+
+pub mod router;
+pub use router::*;
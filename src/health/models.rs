@@ -0,0 +1,75 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Unavailable,
+}
+
+/// The result of checking one dependency.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheck {
+    pub name: &'static str,
+    pub status: HealthStatus,
+    /// Why the check failed; left out when it passed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl HealthCheck {
+    pub const fn ok(name: &'static str) -> Self {
+        Self {
+            name,
+            status: HealthStatus::Ok,
+            detail: None,
+        }
+    }
+
+    pub fn unavailable(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status: HealthStatus::Unavailable,
+            detail: Some(detail.into()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    /// `ok` only when every check passed.
+    pub status: HealthStatus,
+    pub checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    pub fn from_checks(checks: Vec<HealthCheck>) -> Self {
+        let status = if checks.iter().all(|check| check.status == HealthStatus::Ok) {
+            HealthStatus::Ok
+        } else {
+            HealthStatus::Unavailable
+        };
+
+        Self { status, checks }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_is_unavailable_when_any_check_fails() {
+        let report = HealthReport::from_checks(vec![
+            HealthCheck::ok("database"),
+            HealthCheck::unavailable("migrations", "pending: 20260101090000"),
+        ]);
+        assert_eq!(report.status, HealthStatus::Unavailable);
+
+        let report = HealthReport::from_checks(vec![HealthCheck::ok("database")]);
+        assert_eq!(report.status, HealthStatus::Ok);
+    }
+}
@@ -0,0 +1,43 @@
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+
+use crate::errors::RepositoryError;
+
+#[async_trait]
+pub trait IHealthRepository: Send + Sync {
+    /// Run a trivial query to prove the database answers.
+    async fn ping(&self) -> Result<(), RepositoryError>;
+
+    /// Versions of the migrations that were applied successfully.
+    async fn applied_migrations(&self) -> Result<Vec<i64>, RepositoryError>;
+}
+
+pub struct SqlxHealthRepository {
+    pub pool: SqlitePool,
+}
+
+impl SqlxHealthRepository {
+    pub const fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl IHealthRepository for SqlxHealthRepository {
+    async fn ping(&self) -> Result<(), RepositoryError> {
+        sqlx::query!("SELECT 1 AS ok").fetch_one(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn applied_migrations(&self) -> Result<Vec<i64>, RepositoryError> {
+        // The migrations table is created by the migrator at startup rather than by a
+        // migration, so it is not known to the compile-time checked query macros.
+        let versions = sqlx::query_scalar(
+            "SELECT version FROM _sqlx_migrations WHERE success = TRUE ORDER BY version",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(versions)
+    }
+}
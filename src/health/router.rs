@@ -0,0 +1,75 @@
+use axum::{
+    Json, Router,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+
+use crate::{
+    health::{HealthCheck, HealthReport, HealthStatus},
+    services::ServiceContainer,
+};
+
+// Clippy lint triggered by utoipa macro expansion, not our code
+#[allow(clippy::needless_for_each)]
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(crate::health::healthz, crate::health::readyz),
+    components(schemas(HealthReport, HealthCheck, HealthStatus)),
+    tags(
+        (
+            name = "Health",
+            description = "Liveness and readiness probes for container orchestrators"
+        )
+    )
+)]
+pub struct HealthApiDoc;
+
+/// The probe routes. They are merged outside the auth middleware, so probing never
+/// reads or refreshes a session.
+pub fn router() -> Router<ServiceContainer> {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+}
+
+#[utoipa::path(
+    get,
+    summary = "Liveness Probe",
+    path = "/healthz",
+    tag = "Health",
+    responses(
+        (status = 200, description = "The process is running", body = HealthReport),
+    ),
+    description = "Answers as long as the process can serve HTTP requests, without \
+                  touching the database. Restart the container when it stops answering."
+)]
+pub async fn healthz() -> Json<HealthReport> {
+    Json(HealthReport::from_checks(Vec::new()))
+}
+
+#[utoipa::path(
+    get,
+    summary = "Readiness Probe",
+    path = "/readyz",
+    tag = "Health",
+    responses(
+        (status = 200, description = "Every dependency is available", body = HealthReport),
+        (status = 503, description = "At least one check failed; see its detail", body = HealthReport),
+    ),
+    description = "Checks that the database answers a trivial query, that every \
+                  migration has been applied and that the background jobs are still \
+                  running. Send \
+                  traffic to the instance only while this answers 200 OK. No \
+                  authentication is needed and no session is read."
+)]
+pub async fn readyz(State(container): State<ServiceContainer>) -> Response {
+    let report = container.health_service().readiness().await;
+    let status = match report.status {
+        HealthStatus::Ok => StatusCode::OK,
+        HealthStatus::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (status, Json(report)).into_response()
+}
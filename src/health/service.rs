@@ -0,0 +1,107 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+
+use crate::{
+    background_jobs::BackgroundJobs,
+    database::MIGRATOR,
+    health::{HealthCheck, HealthReport, IHealthRepository},
+};
+
+/// How long the database may take to answer before it is reported unavailable, so
+/// that a stuck database fails the probe instead of timing it out.
+const DATABASE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[async_trait]
+pub trait IHealthService: Send + Sync {
+    /// Check everything the app needs to serve requests. Failures are reported in
+    /// the returned checks rather than as errors.
+    async fn readiness(&self) -> HealthReport;
+}
+
+pub struct HealthService {
+    health: Arc<dyn IHealthRepository>,
+    background_jobs: Arc<BackgroundJobs>,
+}
+
+impl HealthService {
+    pub fn new(
+        health_repo: Arc<dyn IHealthRepository>,
+        background_jobs: Arc<BackgroundJobs>,
+    ) -> Self {
+        Self {
+            health: health_repo,
+            background_jobs,
+        }
+    }
+
+    async fn check_database(&self) -> HealthCheck {
+        match tokio::time::timeout(DATABASE_TIMEOUT, self.health.ping()).await {
+            Ok(Ok(())) => HealthCheck::ok("database"),
+            Ok(Err(err)) => {
+                tracing::warn!("Readiness check could not reach the database: {:?}", err);
+                HealthCheck::unavailable("database", "query failed")
+            }
+            Err(_) => HealthCheck::unavailable(
+                "database",
+                format!("no answer within {}s", DATABASE_TIMEOUT.as_secs()),
+            ),
+        }
+    }
+
+    async fn check_migrations(&self) -> HealthCheck {
+        let applied = match self.health.applied_migrations().await {
+            Ok(applied) => applied,
+            Err(err) => {
+                tracing::warn!("Readiness check could not list migrations: {:?}", err);
+                return HealthCheck::unavailable("migrations", "cannot list applied migrations");
+            }
+        };
+
+        let pending: Vec<String> = MIGRATOR
+            .iter()
+            .filter(|migration| !migration.migration_type.is_down_migration())
+            .filter(|migration| !applied.contains(&migration.version))
+            .map(|migration| migration.version.to_string())
+            .collect();
+
+        if pending.is_empty() {
+            HealthCheck::ok("migrations")
+        } else {
+            HealthCheck::unavailable("migrations", format!("pending: {}", pending.join(", ")))
+        }
+    }
+
+    fn check_background_jobs(&self) -> HealthCheck {
+        let statuses = self.background_jobs.statuses();
+        if statuses.is_empty() {
+            return HealthCheck::unavailable("background_jobs", "not started");
+        }
+
+        let stopped: Vec<&str> = statuses
+            .into_iter()
+            .filter(|(_, running)| !running)
+            .map(|(name, _)| name)
+            .collect();
+
+        if stopped.is_empty() {
+            HealthCheck::ok("background_jobs")
+        } else {
+            HealthCheck::unavailable(
+                "background_jobs",
+                format!("stopped: {}", stopped.join(", ")),
+            )
+        }
+    }
+}
+
+#[async_trait]
+impl IHealthService for HealthService {
+    async fn readiness(&self) -> HealthReport {
+        HealthReport::from_checks(vec![
+            self.check_database().await,
+            self.check_migrations().await,
+            self.check_background_jobs(),
+        ])
+    }
+}
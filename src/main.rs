@@ -1,12 +1,23 @@
+mod api_tokens;
+mod applications;
+mod audit;
 mod authentication;
 mod background_jobs;
+mod client_ip;
+mod config;
 mod cookies;
+mod data_exports;
 mod database;
 mod docs;
 mod errors;
 mod extractors;
+mod health;
+mod metrics;
 mod middleware;
+mod password_resets;
+mod rate_limit;
 mod recipes;
+mod request_context;
 mod roles;
 mod services;
 mod sessions;
@@ -15,10 +26,17 @@ mod token;
 mod users;
 mod validation;
 
+use api_tokens::router as api_token_router;
+use applications::{Application, router as application_router};
+use audit::router as audit_router;
 use authentication::router as auth_router;
 use axum::Router;
+use config::{Config, LogFormat};
 use database::Database;
 use dotenvy::dotenv;
+use health::router as health_router;
+use metrics::{router as metrics_router, unauthenticated_router as metrics_only_router};
+use password_resets::router as password_reset_router;
 use recipes::router as recipe_router;
 use roles::router as role_router;
 use services::ServiceContainer;
@@ -30,45 +48,143 @@ use tracing_subscriber::EnvFilter;
 use users::router as user_router;
 use utoipa_scalar::{Scalar, Servable};
 
-use crate::{background_jobs::spawn_cleanup_task, docs::ApiDoc, middleware::auth_middleware};
+use crate::{
+    background_jobs::{spawn_cleanup_task, spawn_user_purge_task},
+    docs::ApiDoc,
+    middleware::{
+        auth_middleware, cors_layer, csrf_protection, metrics_middleware, rate_limit_middleware,
+        request_id_middleware, require_application, security_headers,
+    },
+};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv().ok();
 
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .pretty()
-        .init();
+    // Load and validate all configuration up front so bad settings fail fast
+    let config = Config::load()?;
+
+    let subscriber = tracing_subscriber::fmt().with_env_filter(EnvFilter::from_default_env());
+    match config.log_format {
+        LogFormat::Pretty => subscriber.pretty().init(),
+        LogFormat::Json => subscriber
+            .json()
+            .flatten_event(true)
+            .with_span_list(false)
+            .init(),
+    }
+    warn_about_config(&config);
+
+    let addr = config.bind_address;
+    let metrics_addr = config.metrics_bind_address;
 
     // Initialize DB and ServiceContainer
-    let db = Database::new().await?;
-    let container = ServiceContainer::new(db.pool.clone());
+    let db = Database::new(&config.database_url).await?;
+    let container = ServiceContainer::new(db.pool.clone(), config);
     let session_repo = container.session_repo();
+    let user_service = container.user_service();
+    let metrics = container.metrics();
+    let background_jobs = container.background_jobs();
+    let metrics_container = container.clone();
 
     let app = Router::new()
-        .merge(Scalar::with_url("/docs", ApiDoc::merge_modules()))
-        .nest("/api/recipes", recipe_router())
+        .merge(Scalar::with_url(
+            "/docs",
+            ApiDoc::merge_modules(&container.config().api_docs),
+        ))
+        .nest(
+            "/api/recipes",
+            recipe_router().route_layer(axum::middleware::from_fn_with_state(
+                Application::Recipes,
+                require_application,
+            )),
+        )
         .nest("/api/users", user_router())
         .nest("/api/auth", auth_router())
+        .nest("/api/auth/tokens", api_token_router())
         .nest("/api/sessions", session_router())
         .nest("/api/roles", role_router())
+        .nest("/api/password-resets", password_reset_router())
+        .nest("/api/applications", application_router())
+        .nest("/api/audit", audit_router())
+        .nest("/metrics", metrics_router())
+        .layer(axum::middleware::from_fn_with_state(
+            container.clone(),
+            rate_limit_middleware,
+        ))
         .layer(axum::middleware::from_fn_with_state(
             container.clone(),
             auth_middleware,
         ))
+        .layer(axum::middleware::from_fn_with_state(
+            container.clone(),
+            csrf_protection,
+        ))
+        // Probes are added after the session and CSRF layers so that they never
+        // touch the sessions table.
+        .merge(health_router())
         .fallback_service(
             ServeDir::new("static").not_found_service(ServeFile::new("static/index.html")),
         )
-        .with_state(container);
+        .layer(axum::middleware::from_fn_with_state(
+            container.clone(),
+            security_headers,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            container.clone(),
+            metrics_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            container.clone(),
+            request_id_middleware,
+        ));
+
+    // Outermost, so that preflight requests are answered before anything else runs
+    // and refusals still carry the CORS headers the browser needs to read them.
+    let app = match cors_layer(&container.config().security) {
+        Some(cors) => app.layer(cors),
+        None => app,
+    }
+    .with_state(container);
+
+    background_jobs.track("session_cleanup", spawn_cleanup_task(session_repo, metrics));
+    background_jobs.track("user_purge", spawn_user_purge_task(user_service));
 
-    let _cleanup_handle = spawn_cleanup_task(session_repo);
+    if let Some(metrics_addr) = metrics_addr {
+        let metrics_app = metrics_only_router().with_state(metrics_container);
+        let metrics_listener = TcpListener::bind(metrics_addr).await?;
+        tracing::info!("Serving metrics on http://{}/metrics", metrics_addr);
+        tokio::spawn(async move {
+            if let Err(err) = axum::serve(metrics_listener, metrics_app).await {
+                tracing::error!("Metrics listener failed: {:?}", err);
+            }
+        });
+    }
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3030));
     tracing::info!("Listening on http://{}", addr);
 
     let listener = TcpListener::bind(addr).await?;
-    axum::serve(listener, app.into_make_service()).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
+
+/// Log settings that work but should be changed.
+fn warn_about_config(config: &Config) {
+    if config.session_keyring.has_short_active_key() {
+        tracing::warn!(
+            "SESSION_HMAC_KEY is shorter than 32 bytes; generate a new key with hmac_key_gen \
+             and set SESSION_HMAC_KEYS=<new id>:<new key>,default:<old key>"
+        );
+    }
+    if config.security.public_origin.is_none() {
+        tracing::warn!(
+            "PUBLIC_ORIGIN is not set, so cross-site request checks trust the Host header; \
+             set it to the origin users reach Mainframe at"
+        );
+    }
+}
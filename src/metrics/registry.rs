@@ -0,0 +1,331 @@
+//! In-process metrics, exposed in the Prometheus text format.
+//!
+//! Counters and histograms are kept in memory and reset when the process restarts,
+//! which Prometheus handles on its own. Values that are cheap to read when scraped,
+//! such as the number of active sessions, are not tracked here but looked up by the
+//! [`router`](super::router) on every scrape.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        Mutex, PoisonError,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Upper bounds, in seconds, of the request latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Route label for requests that did not match an API route, such as frontend
+/// assets, so that scanners cannot create a series per path.
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Method label for requests with a method outside the standard ones, for the same
+/// reason.
+pub const OTHER_METHOD: &str = "other";
+
+/// How a login attempt ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginOutcome {
+    Success,
+    InvalidCredentials,
+    Locked,
+    Disabled,
+    RateLimited,
+}
+
+impl LoginOutcome {
+    const ALL: [Self; 5] = [
+        Self::Success,
+        Self::InvalidCredentials,
+        Self::Locked,
+        Self::Disabled,
+        Self::RateLimited,
+    ];
+
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::InvalidCredentials => "invalid_credentials",
+            Self::Locked => "locked",
+            Self::Disabled => "disabled",
+            Self::RateLimited => "rate_limited",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct RequestKey {
+    method: String,
+    route: String,
+    status: u16,
+}
+
+#[derive(Debug, Default)]
+struct LatencyHistogram {
+    /// Observations per bucket, not cumulative; summed up when rendered.
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum_seconds: f64,
+}
+
+impl LatencyHistogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(bucket) = LATENCY_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[bucket] += 1;
+        }
+        self.count += 1;
+        self.sum_seconds += seconds;
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Metrics {
+    requests: Mutex<BTreeMap<RequestKey, LatencyHistogram>>,
+    logins: [AtomicU64; LoginOutcome::ALL.len()],
+    cleanup_runs_succeeded: AtomicU64,
+    cleanup_runs_failed: AtomicU64,
+    cleanup_sessions_deleted: AtomicU64,
+    cleanup_last_success_seconds: AtomicU64,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_request(&self, method: &str, route: &str, status: u16, latency: Duration) {
+        let key = RequestKey {
+            method: method.to_string(),
+            route: route.to_string(),
+            status,
+        };
+
+        // The map only holds plain numbers, so it is still usable after a panic.
+        self.requests
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(key)
+            .or_default()
+            .observe(latency.as_secs_f64());
+    }
+
+    pub fn record_login(&self, outcome: LoginOutcome) {
+        self.logins[outcome as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a run of the expired session cleanup, with the number of sessions it
+    /// deleted, or `None` when it failed.
+    pub fn record_session_cleanup(&self, deleted: Option<u64>) {
+        match deleted {
+            Some(deleted) => {
+                self.cleanup_runs_succeeded.fetch_add(1, Ordering::Relaxed);
+                self.cleanup_sessions_deleted
+                    .fetch_add(deleted, Ordering::Relaxed);
+
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                self.cleanup_last_success_seconds
+                    .store(now, Ordering::Relaxed);
+            }
+            None => {
+                self.cleanup_runs_failed.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Append every tracked metric to `out` in the Prometheus text format.
+    pub fn render(&self, out: &mut MetricsWriter) {
+        self.render_requests(out);
+
+        out.header(
+            "mainframe_login_attempts_total",
+            "Login attempts by outcome.",
+            "counter",
+        );
+        for outcome in LoginOutcome::ALL {
+            out.sample(
+                "mainframe_login_attempts_total",
+                &[("outcome", outcome.as_str())],
+                self.logins[outcome as usize].load(Ordering::Relaxed),
+            );
+        }
+
+        out.header(
+            "mainframe_session_cleanup_runs_total",
+            "Runs of the expired session cleanup task by result.",
+            "counter",
+        );
+        for (result, runs) in [
+            ("success", &self.cleanup_runs_succeeded),
+            ("failure", &self.cleanup_runs_failed),
+        ] {
+            out.sample(
+                "mainframe_session_cleanup_runs_total",
+                &[("result", result)],
+                runs.load(Ordering::Relaxed),
+            );
+        }
+
+        out.header(
+            "mainframe_session_cleanup_deleted_total",
+            "Expired sessions deleted by the cleanup task.",
+            "counter",
+        );
+        out.sample(
+            "mainframe_session_cleanup_deleted_total",
+            &[],
+            self.cleanup_sessions_deleted.load(Ordering::Relaxed),
+        );
+        out.gauge(
+            "mainframe_session_cleanup_last_success_timestamp_seconds",
+            "Unix time of the last successful cleanup run, or 0 before the first.",
+            self.cleanup_last_success_seconds.load(Ordering::Relaxed),
+        );
+    }
+
+    fn render_requests(&self, out: &mut MetricsWriter) {
+        let requests = self.requests.lock().unwrap_or_else(PoisonError::into_inner);
+
+        out.header(
+            "mainframe_http_requests_total",
+            "HTTP requests by method, route and status.",
+            "counter",
+        );
+        for (key, histogram) in requests.iter() {
+            let status = key.status.to_string();
+            let labels = [
+                ("method", key.method.as_str()),
+                ("route", key.route.as_str()),
+                ("status", status.as_str()),
+            ];
+            out.sample("mainframe_http_requests_total", &labels, histogram.count);
+        }
+
+        out.header(
+            "mainframe_http_request_duration_seconds",
+            "Time to respond to HTTP requests by method, route and status.",
+            "histogram",
+        );
+        for (key, histogram) in requests.iter() {
+            let status = key.status.to_string();
+            let labels = [
+                ("method", key.method.as_str()),
+                ("route", key.route.as_str()),
+                ("status", status.as_str()),
+            ];
+
+            let mut cumulative = 0;
+            for (bound, observed) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += observed;
+                let le = bound.to_string();
+                out.sample(
+                    "mainframe_http_request_duration_seconds_bucket",
+                    &[labels[0], labels[1], labels[2], ("le", le.as_str())],
+                    cumulative,
+                );
+            }
+            out.sample(
+                "mainframe_http_request_duration_seconds_bucket",
+                &[labels[0], labels[1], labels[2], ("le", "+Inf")],
+                histogram.count,
+            );
+            out.sample(
+                "mainframe_http_request_duration_seconds_sum",
+                &labels,
+                histogram.sum_seconds,
+            );
+            out.sample(
+                "mainframe_http_request_duration_seconds_count",
+                &labels,
+                histogram.count,
+            );
+        }
+    }
+}
+
+/// Builds a response in the Prometheus text exposition format.
+#[derive(Debug, Default)]
+pub struct MetricsWriter {
+    text: String,
+}
+
+impl MetricsWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn header(&mut self, name: &str, help: &str, metric_type: &str) {
+        let _ = writeln!(self.text, "# HELP {name} {help}");
+        let _ = writeln!(self.text, "# TYPE {name} {metric_type}");
+    }
+
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: impl std::fmt::Display) {
+        self.text.push_str(name);
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(label, value)| format!("{label}=\"{}\"", escape_label(value)))
+                .collect();
+            let _ = write!(self.text, "{{{}}}", labels.join(","));
+        }
+        let _ = writeln!(self.text, " {value}");
+    }
+
+    /// A gauge with a single, unlabelled sample.
+    pub fn gauge(&mut self, name: &str, help: &str, value: impl std::fmt::Display) {
+        self.header(name, help, "gauge");
+        self.sample(name, &[], value);
+    }
+
+    pub fn finish(self) -> String {
+        self.text
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_renders_cumulative_histogram() {
+        let metrics = Metrics::new();
+        metrics.record_request("GET", "/api/users/{id}", 200, Duration::from_millis(3));
+        metrics.record_request("GET", "/api/users/{id}", 200, Duration::from_millis(40));
+        metrics.record_login(LoginOutcome::Locked);
+        metrics.record_session_cleanup(Some(4));
+
+        let mut out = MetricsWriter::new();
+        metrics.render(&mut out);
+        let text = out.finish();
+
+        let labels = r#"method="GET",route="/api/users/{id}",status="200""#;
+        assert!(text.contains(&format!("mainframe_http_requests_total{{{labels}}} 2")));
+        assert!(text.contains(&format!(
+            "mainframe_http_request_duration_seconds_bucket{{{labels},le=\"0.005\"}} 1"
+        )));
+        assert!(text.contains(&format!(
+            "mainframe_http_request_duration_seconds_bucket{{{labels},le=\"0.05\"}} 2"
+        )));
+        assert!(text.contains(r#"mainframe_login_attempts_total{outcome="locked"} 1"#));
+        assert!(text.contains("mainframe_session_cleanup_deleted_total 4"));
+    }
+
+    #[test]
+    fn test_escapes_label_values() {
+        assert_eq!(escape_label("a\"b\\c\nd"), r#"a\"b\\c\nd"#);
+    }
+}
@@ -0,0 +1,104 @@
+use axum::{
+    Router,
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+
+use crate::{
+    errors::{ApiError, ServiceError},
+    extractors::RequirePermission,
+    metrics::MetricsWriter,
+    roles::permissions::MetricsRead,
+    services::ServiceContainer,
+};
+
+/// Content type of the Prometheus text exposition format.
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+// Clippy lint triggered by utoipa macro expansion, not our code
+#[allow(clippy::needless_for_each)]
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(crate::metrics::get_metrics),
+    tags(
+        (
+            name = "Metrics",
+            description = "Operational metrics for monitoring with Prometheus"
+        )
+    )
+)]
+pub struct MetricsApiDoc;
+
+pub fn router() -> Router<ServiceContainer> {
+    Router::new().route("/", get(get_metrics))
+}
+
+/// Serves `/metrics` without authentication, for the separate `METRICS_BIND_ADDRESS`
+/// listener that only the monitoring network can reach.
+pub fn unauthenticated_router() -> Router<ServiceContainer> {
+    Router::new().route("/metrics", get(scrape))
+}
+
+#[utoipa::path(
+    get,
+    summary = "Get Metrics",
+    path = "/metrics",
+    tag = "Metrics",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", content_type = "text/plain"),
+        (status = 401, description = "Unauthorized - invalid or expired session"),
+        (status = 403, description = "Forbidden - requires the metrics:read permission"),
+    ),
+    description = "Returns request counts and latencies per route and status, the number \
+                  of active sessions, login outcomes including lockouts, database pool \
+                  usage, and the results of the expired session cleanup task, in the \
+                  Prometheus text format. Scrape it with an API token that has the \
+                  metrics:read scope, or through METRICS_BIND_ADDRESS without one."
+)]
+pub async fn get_metrics(
+    _: RequirePermission<MetricsRead>,
+    State(container): State<ServiceContainer>,
+) -> Result<Response, ApiError> {
+    scrape(State(container)).await
+}
+
+async fn scrape(State(container): State<ServiceContainer>) -> Result<Response, ApiError> {
+    let mut out = MetricsWriter::new();
+    container.metrics().render(&mut out);
+
+    let active_sessions = container
+        .session_repo()
+        .count_active()
+        .await
+        .map_err(ServiceError::from)?;
+    out.gauge(
+        "mainframe_sessions_active",
+        "Sessions that have not expired yet.",
+        active_sessions,
+    );
+
+    let pool = container.pool();
+    out.gauge(
+        "mainframe_db_pool_connections",
+        "Open database connections, idle or in use.",
+        pool.size(),
+    );
+    out.gauge(
+        "mainframe_db_pool_idle_connections",
+        "Open database connections that are not in use.",
+        pool.num_idle(),
+    );
+    out.gauge(
+        "mainframe_db_pool_max_connections",
+        "Upper bound on database connections.",
+        pool.options().get_max_connections(),
+    );
+
+    Ok((
+        [(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+        out.finish(),
+    )
+        .into_response())
+}
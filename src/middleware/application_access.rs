@@ -0,0 +1,36 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Request, Response},
+    middleware::Next,
+    response::IntoResponse,
+};
+
+use crate::{
+    applications::Application, errors::ApiError, extractors::authenticated_user::AuthenticatedUser,
+};
+
+/// Route layer that only lets requests through when the application has been
+/// enabled for the authenticated user. Use it with
+/// `from_fn_with_state(Application::Recipes, require_application)`.
+pub async fn require_application(
+    State(application): State<Application>,
+    req: Request<Body>,
+    next: Next,
+) -> Response<Body> {
+    let Some(auth_user) = req.extensions().get::<AuthenticatedUser>() else {
+        return ApiError::Unauthorized {
+            reason: "authentication required".into(),
+        }
+        .into_response();
+    };
+
+    if !auth_user.user.applications.contains(&application) {
+        return ApiError::Forbidden {
+            reason: format!("application `{application}` is not enabled for the user"),
+        }
+        .into_response();
+    }
+
+    next.run(req).await
+}
@@ -1,17 +1,26 @@
 use axum::{
     body::Body,
     extract::State,
-    http::{HeaderValue, Request, Response, header::SET_COOKIE},
+    http::{
+        HeaderValue, Method, Request, Response,
+        header::{AUTHORIZATION, SET_COOKIE},
+    },
     middleware::Next,
+    response::IntoResponse,
 };
 use axum_extra::extract::cookie::{Cookie, CookieJar};
 
 use crate::{
-    authentication::SessionCookieHandled,
-    cookies::{build_expired_session_cookie, build_session_cookie},
-    extractors::authenticated_user::AuthenticatedUser,
+    authentication::{RefreshedSession, SessionCookieHandled},
+    cookies::{
+        CSRF_COOKIE, build_csrf_cookie, build_expired_csrf_cookie, build_expired_session_cookie,
+        build_session_cookie, generate_csrf_token,
+    },
+    errors::ApiError,
+    extractors::authenticated_user::{AuthenticatedUser, Credential},
+    request_context::RequestContext,
     services::ServiceContainer,
-    token::SessionToken,
+    token::{ApiTokenSecret, SessionToken},
 };
 
 pub async fn auth_middleware(
@@ -20,13 +29,44 @@ pub async fn auth_middleware(
     mut req: Request<Body>,
     next: Next,
 ) -> Response<Body> {
-    let eval = evaluate_session(&container, &jar).await;
+    // Set up by the request id middleware; the actor is filled in below.
+    let mut context = RequestContext::current();
+
+    // A bearer token takes the place of the session cookie, which is then left alone.
+    let eval = if let Some(authorization) = req.headers().get(AUTHORIZATION) {
+        evaluate_api_token(&container, authorization).await
+    } else {
+        evaluate_session(&container, &jar).await
+    };
+
+    // API tokens have no login to flag, so they are held to the password expiry directly
+    let password_change_required = eval.auth_user.as_ref().is_some_and(|auth_user| {
+        auth_user
+            .session()
+            .map_or(auth_user.user.password_expired, |session| {
+                session.password_change_required
+            })
+    });
 
     if let Some(auth_user) = &eval.auth_user {
+        context.actor_id = Some(auth_user.user.id);
         req.extensions_mut().insert(auth_user.clone());
+
+        let span = tracing::Span::current();
+        span.record("user_id", tracing::field::display(auth_user.user.id));
+        if let Some(session) = auth_user.session() {
+            span.record("session_id", tracing::field::display(session.id));
+        }
     }
 
-    let mut res = next.run(req).await;
+    let mut res = if password_change_required && !is_allowed_during_password_change(&req) {
+        ApiError::Forbidden {
+            reason: "password change required".into(),
+        }
+        .into_response()
+    } else {
+        context.scope(next.run(req)).await
+    };
 
     let session_cookie_handled = res.extensions().get::<SessionCookieHandled>().is_some();
 
@@ -34,12 +74,8 @@ pub async fn auth_middleware(
         return res;
     }
 
-    if !session_cookie_handled {
-        if let Some(cookie) = eval.cookie {
-            append_set_cookie(&mut res, &cookie);
-        } else if eval.clear_cookie {
-            append_set_cookie(&mut res, &build_expired_session_cookie());
-        }
+    for cookie in &eval.cookies {
+        append_set_cookie(&mut res, cookie);
     }
 
     res
@@ -47,42 +83,118 @@ pub async fn auth_middleware(
 
 struct SessionEvaluation {
     auth_user: Option<AuthenticatedUser>,
-    cookie: Option<Cookie<'static>>,
-    clear_cookie: bool,
+    /// Cookies to set on the response, unless the handler managed them itself.
+    cookies: Vec<Cookie<'static>>,
+}
+
+impl SessionEvaluation {
+    const fn anonymous() -> Self {
+        Self {
+            auth_user: None,
+            cookies: Vec::new(),
+        }
+    }
+
+    /// Clear the cookies of a session that is no longer valid.
+    fn cleared() -> Self {
+        Self {
+            auth_user: None,
+            cookies: vec![build_expired_session_cookie(), build_expired_csrf_cookie()],
+        }
+    }
 }
 
 async fn evaluate_session(container: &ServiceContainer, jar: &CookieJar) -> SessionEvaluation {
     let Some(cookie) = jar.get("session_id") else {
-        return SessionEvaluation {
-            auth_user: None,
-            cookie: None,
-            clear_cookie: false,
-        };
+        return SessionEvaluation::anonymous();
     };
 
     let Ok(token) = SessionToken::parse(cookie.value()) else {
-        return SessionEvaluation {
-            auth_user: None,
-            cookie: None,
-            clear_cookie: true,
-        };
+        return SessionEvaluation::cleared();
     };
 
     match container.auth_service().refresh(token).await {
-        Ok(auth_user) => SessionEvaluation {
-            cookie: Some(build_session_cookie(
-                auth_user.session.token.clone(),
-                auth_user.session.expires_at,
-            )),
-            auth_user: Some(auth_user),
-            clear_cookie: false,
-        },
-        Err(_) => SessionEvaluation {
-            auth_user: None,
-            cookie: None,
-            clear_cookie: true,
-        },
+        Ok(RefreshedSession {
+            auth_user,
+            reissue_cookie,
+        }) => {
+            let cookies = match auth_user.session() {
+                Some(session) if reissue_cookie => {
+                    // The CSRF cookie follows the session cookie, keeping its value so
+                    // that requests already holding the token are not rejected.
+                    let csrf_token = jar
+                        .get(CSRF_COOKIE)
+                        .map(|csrf| csrf.value().to_string())
+                        .filter(|token| !token.is_empty())
+                        .unwrap_or_else(generate_csrf_token);
+
+                    vec![
+                        build_session_cookie(session.token.clone(), session.expires_at),
+                        build_csrf_cookie(csrf_token, session.expires_at),
+                    ]
+                }
+                _ => Vec::new(),
+            };
+
+            SessionEvaluation {
+                auth_user: Some(auth_user),
+                cookies,
+            }
+        }
+        Err(_) => SessionEvaluation::cleared(),
+    }
+}
+
+async fn evaluate_api_token(
+    container: &ServiceContainer,
+    authorization: &HeaderValue,
+) -> SessionEvaluation {
+    let auth_user = match parse_bearer(authorization) {
+        Some(token) => container.api_token_service().authenticate(token).await.ok(),
+        None => None,
+    };
+
+    if let Some(AuthenticatedUser {
+        user,
+        credential: Credential::ApiToken(api_token),
+    }) = &auth_user
+    {
+        tracing::debug!(
+            user_id = %user.id,
+            token_id = %api_token.id,
+            token = %api_token.name,
+            "Request authenticated with API token"
+        );
     }
+
+    SessionEvaluation {
+        auth_user,
+        cookies: Vec::new(),
+    }
+}
+
+/// The personal access token in an `Authorization: Bearer <token>` header.
+fn parse_bearer(authorization: &HeaderValue) -> Option<ApiTokenSecret> {
+    let (scheme, token) = authorization.to_str().ok()?.split_once(' ')?;
+    if !scheme.eq_ignore_ascii_case("bearer") {
+        return None;
+    }
+
+    ApiTokenSecret::parse(token.trim()).ok()
+}
+
+/// Requests a session opened with an expired password, or an API token of a user whose
+/// password has expired, may still make: changing the password, looking up the current
+/// user, and logging out. Non-API routes such as the
+/// documentation are not restricted.
+fn is_allowed_during_password_change(req: &Request<Body>) -> bool {
+    let path = req.uri().path();
+    let method = req.method();
+
+    !path.starts_with("/api/")
+        || (method == Method::PUT && path == "/api/users/self/password")
+        || (method == Method::GET && path == "/api/auth/me")
+        || (method == Method::POST && path == "/api/auth/logout")
 }
 
 fn append_set_cookie(res: &mut Response<Body>, cookie: &Cookie<'static>) {
@@ -0,0 +1,46 @@
+use axum::http::{HeaderName, Method, header};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+use crate::{
+    config::SecurityConfig,
+    cookies::CSRF_HEADER,
+    middleware::{RATELIMIT_LIMIT, RATELIMIT_REMAINING, RATELIMIT_RESET, X_REQUEST_ID},
+};
+
+/// Builds the CORS layer for the configured origins, or `None` when no origin is
+/// allowed and browsers should keep to the same-origin policy.
+///
+/// Meant for development setups where the frontend is served from another origin,
+/// such as a Vite dev server. Requests carry cookies, so the origins must be listed
+/// one by one, and the session's CSRF token is still required.
+pub fn cors_layer(config: &SecurityConfig) -> Option<CorsLayer> {
+    if config.cors_allowed_origins.is_empty() {
+        return None;
+    }
+
+    let layer = CorsLayer::new()
+        .allow_origin(AllowOrigin::list(config.cors_allowed_origins.clone()))
+        .allow_credentials(true)
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
+        .allow_headers([
+            header::CONTENT_TYPE,
+            header::AUTHORIZATION,
+            HeaderName::from_static(CSRF_HEADER),
+        ])
+        .expose_headers([
+            header::CONTENT_DISPOSITION,
+            header::RETRY_AFTER,
+            RATELIMIT_LIMIT,
+            RATELIMIT_REMAINING,
+            RATELIMIT_RESET,
+            X_REQUEST_ID,
+        ]);
+
+    Some(layer)
+}
@@ -0,0 +1,245 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{HeaderMap, HeaderValue, Method, Request, Response, Uri, header},
+    middleware::Next,
+    response::IntoResponse,
+};
+use axum_extra::extract::cookie::{Cookie, CookieJar};
+
+use crate::{
+    cookies::{CSRF_COOKIE, CSRF_HEADER},
+    errors::ApiError,
+    services::ServiceContainer,
+};
+
+/// Rejects cross-site request forgery on API requests that change state.
+///
+/// Requests authenticated with the session cookie must echo the `csrf_token` cookie in
+/// the `X-CSRF-Token` header. Browsers that cannot send the header, such as plain HTML
+/// forms, are still let through when their `Origin` (or `Referer`) is the configured
+/// public origin, or names this host when no public origin is configured.
+/// Any request whose `Origin` or `Referer` names another origin is refused, which also
+/// protects the login form, unless it is one of the allowed CORS origins; those still
+/// have to send the token. Requests with an `Authorization` header are left alone,
+/// since a browser never attaches one on its own.
+pub async fn csrf_protection(
+    State(container): State<ServiceContainer>,
+    jar: CookieJar,
+    req: Request<Body>,
+    next: Next,
+) -> Response<Body> {
+    let security = &container.config().security;
+    match check_request(
+        &jar,
+        req.method(),
+        req.uri(),
+        req.headers(),
+        security.public_origin.as_ref(),
+        &security.cors_allowed_origins,
+    ) {
+        Ok(()) => next.run(req).await,
+        Err(reason) => ApiError::CsrfRejected(reason).into_response(),
+    }
+}
+
+fn check_request(
+    jar: &CookieJar,
+    method: &Method,
+    uri: &Uri,
+    headers: &HeaderMap,
+    public_origin: Option<&HeaderValue>,
+    allowed_origins: &[HeaderValue],
+) -> Result<(), &'static str> {
+    let is_safe = matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+    if is_safe || !uri.path().starts_with("/api/") || headers.contains_key(header::AUTHORIZATION) {
+        return Ok(());
+    }
+
+    let same_origin = is_same_origin(headers, public_origin);
+    let is_allowed_origin = headers
+        .get(header::ORIGIN)
+        .is_some_and(|origin| allowed_origins.contains(origin));
+    if same_origin == Some(false) && !is_allowed_origin {
+        return Err("the request was sent from another origin");
+    }
+
+    // Without a session cookie there is no ambient authority to abuse.
+    if jar.get("session_id").is_none() {
+        return Ok(());
+    }
+
+    let Some(presented) = headers.get(CSRF_HEADER) else {
+        return if same_origin == Some(true) {
+            Ok(())
+        } else {
+            Err("the X-CSRF-Token header is missing")
+        };
+    };
+
+    let expected = jar
+        .get(CSRF_COOKIE)
+        .map(Cookie::value)
+        .filter(|token| !token.is_empty())
+        .ok_or("the csrf_token cookie is missing")?;
+
+    if constant_time_eq(presented.as_bytes(), expected.as_bytes()) {
+        Ok(())
+    } else {
+        Err("the X-CSRF-Token header does not match the csrf_token cookie")
+    }
+}
+
+/// Whether the `Origin` header, or failing that the `Referer` header, is the public
+/// origin. Without a configured public origin, only the host is compared with the
+/// `Host` header. `None` when the client sent neither header.
+fn is_same_origin(headers: &HeaderMap, public_origin: Option<&HeaderValue>) -> Option<bool> {
+    let source = headers
+        .get(header::ORIGIN)
+        .or_else(|| headers.get(header::REFERER))?;
+
+    let source = source
+        .to_str()
+        .ok()
+        .and_then(|value| value.parse::<Uri>().ok());
+    let Some((scheme, authority)) = source
+        .as_ref()
+        .and_then(|uri| Some((uri.scheme_str()?, uri.authority()?.as_str())))
+    else {
+        return Some(false);
+    };
+
+    let Some(public_origin) = public_origin else {
+        let host = headers
+            .get(header::HOST)
+            .and_then(|host| host.to_str().ok());
+        return Some(host.is_some_and(|host| authority.eq_ignore_ascii_case(host)));
+    };
+
+    Some(
+        public_origin
+            .to_str()
+            .is_ok_and(|origin| origin.eq_ignore_ascii_case(&format!("{scheme}://{authority}"))),
+    )
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session_jar() -> CookieJar {
+        CookieJar::new()
+            .add(Cookie::new("session_id", "session"))
+            .add(Cookie::new(CSRF_COOKIE, "secret"))
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    fn check(jar: &CookieJar, method: &Method, headers: &HeaderMap) -> Result<(), &'static str> {
+        check_with_origin(jar, method, headers, None)
+    }
+
+    fn check_with_origin(
+        jar: &CookieJar,
+        method: &Method,
+        headers: &HeaderMap,
+        public_origin: Option<&'static str>,
+    ) -> Result<(), &'static str> {
+        let allowed_origins = [HeaderValue::from_static("http://localhost:5173")];
+        check_request(
+            jar,
+            method,
+            &"/api/recipes".parse().unwrap(),
+            headers,
+            public_origin.map(HeaderValue::from_static).as_ref(),
+            &allowed_origins,
+        )
+    }
+
+    #[test]
+    fn test_safe_methods_and_bearer_requests_are_not_checked() {
+        let jar = session_jar();
+        assert!(check(&jar, &Method::GET, &headers(&[])).is_ok());
+        assert!(
+            check(
+                &jar,
+                &Method::POST,
+                &headers(&[("authorization", "Bearer x")])
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_session_requests_need_a_matching_token() {
+        let jar = session_jar();
+        assert!(check(&jar, &Method::POST, &headers(&[(CSRF_HEADER, "secret")])).is_ok());
+        assert!(check(&jar, &Method::POST, &headers(&[(CSRF_HEADER, "guess")])).is_err());
+        assert!(check(&jar, &Method::DELETE, &headers(&[])).is_err());
+    }
+
+    #[test]
+    fn test_origin_fallback() {
+        let jar = session_jar();
+        let same = headers(&[
+            ("host", "home.example:8080"),
+            ("origin", "https://home.example:8080"),
+        ]);
+        let referer = headers(&[
+            ("host", "home.example"),
+            ("referer", "https://home.example/recipes"),
+        ]);
+        let other = headers(&[
+            ("host", "home.example"),
+            ("origin", "https://evil.example"),
+            (CSRF_HEADER, "secret"),
+        ]);
+
+        assert!(check(&jar, &Method::PUT, &same).is_ok());
+        assert!(check(&jar, &Method::PUT, &referer).is_ok());
+        assert!(check(&jar, &Method::PUT, &other).is_err());
+        assert!(check(&CookieJar::new(), &Method::POST, &other).is_err());
+    }
+
+    #[test]
+    fn test_public_origin_replaces_the_host_header() {
+        let jar = session_jar();
+        let public = Some("https://home.example");
+        let same = headers(&[("host", "evil.example"), ("origin", "https://home.example")]);
+        let forged_host = headers(&[("host", "evil.example"), ("origin", "https://evil.example")]);
+        let other_scheme = headers(&[("host", "home.example"), ("origin", "http://home.example")]);
+
+        assert!(check_with_origin(&jar, &Method::PUT, &same, public).is_ok());
+        assert!(check_with_origin(&jar, &Method::PUT, &forged_host, public).is_err());
+        assert!(check_with_origin(&jar, &Method::PUT, &other_scheme, public).is_err());
+    }
+
+    #[test]
+    fn test_allowed_origins_still_need_the_token() {
+        let jar = session_jar();
+        let dev_server = [
+            ("host", "localhost:3030"),
+            ("origin", "http://localhost:5173"),
+        ];
+
+        assert!(check(&jar, &Method::POST, &headers(&dev_server)).is_err());
+        assert!(
+            check(
+                &jar,
+                &Method::POST,
+                &headers(&[dev_server[0], dev_server[1], (CSRF_HEADER, "secret")])
+            )
+            .is_ok()
+        );
+    }
+}
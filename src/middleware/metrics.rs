@@ -0,0 +1,69 @@
+use std::time::Instant;
+
+use axum::{
+    body::Body,
+    extract::{MatchedPath, State},
+    http::{Method, Request, Response},
+    middleware::Next,
+};
+
+use crate::{
+    metrics::{OTHER_METHOD, UNMATCHED_ROUTE},
+    services::ServiceContainer,
+};
+
+/// Counts every request and its latency by method, route template and status.
+///
+/// Requests that did not match an API route, such as frontend assets, share the
+/// [`UNMATCHED_ROUTE`] label so that arbitrary paths cannot create new series, and
+/// non-standard methods likewise share the [`OTHER_METHOD`] label.
+pub async fn metrics_middleware(
+    State(container): State<ServiceContainer>,
+    req: Request<Body>,
+    next: Next,
+) -> Response<Body> {
+    let method = method_label(req.method());
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE, MatchedPath::as_str)
+        .to_string();
+
+    let started_at = Instant::now();
+    let res = next.run(req).await;
+
+    container
+        .metrics()
+        .record_request(method, &route, res.status().as_u16(), started_at.elapsed());
+
+    res
+}
+
+const fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::HEAD => "HEAD",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::CONNECT => "CONNECT",
+        Method::OPTIONS => "OPTIONS",
+        Method::TRACE => "TRACE",
+        Method::PATCH => "PATCH",
+        _ => OTHER_METHOD,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_standard_methods_share_a_label() {
+        assert_eq!(method_label(&Method::PATCH), "PATCH");
+        assert_eq!(
+            method_label(&Method::from_bytes(b"PROPFIND").unwrap()),
+            OTHER_METHOD
+        );
+    }
+}
@@ -1,2 +1,23 @@
+pub mod application_access;
+pub use application_access::*;
+
 pub mod authentication;
 pub use authentication::*;
+
+pub mod cors;
+pub use cors::*;
+
+pub mod csrf;
+pub use csrf::*;
+
+pub mod metrics;
+pub use metrics::*;
+
+pub mod rate_limit;
+pub use rate_limit::*;
+
+pub mod request_id;
+pub use request_id::*;
+
+pub mod security_headers;
+pub use security_headers::*;
@@ -0,0 +1,76 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{HeaderMap, HeaderName, Request, Response},
+    middleware::Next,
+    response::IntoResponse,
+};
+
+use crate::{
+    errors::ApiError,
+    extractors::authenticated_user::AuthenticatedUser,
+    rate_limit::{Quota, RateLimitClient},
+    request_context::RequestContext,
+    services::ServiceContainer,
+};
+
+pub const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+pub const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+pub const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+
+/// Enforces the API request budgets, counting requests against the signed in user
+/// or, for anonymous requests, the client address. Every limited response carries
+/// `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset` headers, and
+/// requests over budget are refused with `429 Too Many Requests`.
+///
+/// Must run inside the auth middleware, which identifies the user.
+pub async fn rate_limit_middleware(
+    State(container): State<ServiceContainer>,
+    req: Request<Body>,
+    next: Next,
+) -> Response<Body> {
+    let client = req
+        .extensions()
+        .get::<AuthenticatedUser>()
+        .map(|auth| RateLimitClient::User(auth.user.id))
+        .or_else(|| {
+            RequestContext::current()
+                .ip_address
+                .map(RateLimitClient::Ip)
+        });
+
+    let Some(client) = client else {
+        return next.run(req).await;
+    };
+
+    let limiter = container.api_limiter();
+    let Some((budget, result)) = limiter.check(req.uri().path(), client) else {
+        return next.run(req).await;
+    };
+
+    match result {
+        Ok(quota) => {
+            let mut res = next.run(req).await;
+            insert_quota_headers(res.headers_mut(), quota);
+            res
+        }
+        Err(quota) => {
+            let mut res = ApiError::TooManyRequests {
+                reason: format!("{client} exceeded the {} budget", budget.prefix),
+                retry_after: quota.reset,
+            }
+            .into_response();
+            insert_quota_headers(res.headers_mut(), quota);
+            res
+        }
+    }
+}
+
+fn insert_quota_headers(headers: &mut HeaderMap, quota: Quota) {
+    // Round up, so that waiting the advertised time is always enough.
+    let reset = quota.reset.as_secs() + u64::from(quota.reset.subsec_nanos() > 0);
+
+    headers.insert(RATELIMIT_LIMIT, quota.limit.into());
+    headers.insert(RATELIMIT_REMAINING, quota.remaining.into());
+    headers.insert(RATELIMIT_RESET, reset.into());
+}
@@ -0,0 +1,108 @@
+use std::{net::SocketAddr, time::Instant};
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, MatchedPath, State},
+    http::{HeaderName, HeaderValue, Request, Response},
+    middleware::Next,
+};
+use tracing::{Instrument, field};
+use uuid::Uuid;
+
+use crate::{request_context::RequestContext, services::ServiceContainer};
+
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest incoming request id that is passed on rather than replaced.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Gives every request an id and a tracing span, and sets up its [`RequestContext`].
+///
+/// An `X-Request-Id` sent by the client or a proxy is kept when it looks sane, and a
+/// new one is made otherwise. The id is returned in the `X-Request-Id` response
+/// header and in error bodies, and is attached to every log line of the request
+/// along with the method, route, and, once the auth middleware has run, the user
+/// and session.
+pub async fn request_id_middleware(
+    State(container): State<ServiceContainer>,
+    req: Request<Body>,
+    next: Next,
+) -> Response<Body> {
+    let request_id = req
+        .headers()
+        .get(X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| is_valid_request_id(value))
+        .map_or_else(|| Uuid::now_v7().to_string(), ToString::to_string);
+
+    // Forwarding headers are only used from trusted proxies.
+    let ip_address = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| {
+            container
+                .config()
+                .trusted_proxies
+                .client_ip(addr.ip(), req.headers())
+        });
+
+    let route = req.extensions().get::<MatchedPath>().map_or_else(
+        || req.uri().path().to_string(),
+        |path| path.as_str().to_string(),
+    );
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        route = %route,
+        user_id = field::Empty,
+        session_id = field::Empty,
+    );
+
+    let context = RequestContext {
+        request_id: Some(request_id.clone()),
+        actor_id: None,
+        ip_address,
+    };
+
+    let started_at = Instant::now();
+    let mut res = context.scope(next.run(req)).instrument(span.clone()).await;
+
+    span.in_scope(|| {
+        tracing::info!(
+            status = res.status().as_u16(),
+            latency = ?started_at.elapsed(),
+            "Request completed"
+        );
+    });
+
+    if let Ok(value) = HeaderValue::try_from(request_id) {
+        res.headers_mut().insert(X_REQUEST_ID, value);
+    }
+
+    res
+}
+
+fn is_valid_request_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LEN
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_id_validation() {
+        assert!(is_valid_request_id("01a150be-acaa-750b-a531-04c11a9dce08"));
+        assert!(is_valid_request_id("8c2f1a9b3d4e5f60-AMS"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("id with spaces"));
+        assert!(!is_valid_request_id("forged\nlog line"));
+        assert!(!is_valid_request_id(&"a".repeat(MAX_REQUEST_ID_LEN + 1)));
+    }
+}
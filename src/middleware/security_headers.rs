@@ -0,0 +1,72 @@
+use axum::{
+    body::Body,
+    extract::State,
+    http::{HeaderName, HeaderValue, Request, Response, header},
+    middleware::Next,
+};
+
+use crate::services::ServiceContainer;
+
+/// Path of the Scalar API reference, which needs a looser content security policy.
+const DOCS_PATH: &str = "/docs";
+
+const PERMISSIONS_POLICY: HeaderName = HeaderName::from_static("permissions-policy");
+const CROSS_ORIGIN_OPENER_POLICY: HeaderName =
+    HeaderName::from_static("cross-origin-opener-policy");
+
+/// Adds the browser security headers to every response, API and frontend alike.
+/// Headers already set by a handler are left as they are.
+pub async fn security_headers(
+    State(container): State<ServiceContainer>,
+    req: Request<Body>,
+    next: Next,
+) -> Response<Body> {
+    let config = container.config();
+    let security = &config.security;
+
+    let is_docs = req
+        .uri()
+        .path()
+        .strip_prefix(DOCS_PATH)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'));
+
+    let mut res = next.run(req).await;
+    let headers = res.headers_mut();
+
+    let content_security_policy = if is_docs {
+        &security.docs_content_security_policy
+    } else {
+        &security.content_security_policy
+    };
+    headers
+        .entry(header::CONTENT_SECURITY_POLICY)
+        .or_insert_with(|| content_security_policy.clone());
+
+    if let Some(max_age) = security.hsts_max_age_seconds {
+        headers
+            .entry(header::STRICT_TRANSPORT_SECURITY)
+            .or_insert_with(|| {
+                HeaderValue::try_from(format!("max-age={max_age}"))
+                    .unwrap_or_else(|_| HeaderValue::from_static("max-age=0"))
+            });
+    }
+
+    let fixed_headers = [
+        (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+        (header::X_FRAME_OPTIONS, "DENY"),
+        // Keeps same-origin referers whole, which the CSRF check falls back on.
+        (header::REFERRER_POLICY, "strict-origin-when-cross-origin"),
+        (CROSS_ORIGIN_OPENER_POLICY, "same-origin"),
+        (
+            PERMISSIONS_POLICY,
+            "camera=(), microphone=(), geolocation=()",
+        ),
+    ];
+    for (name, value) in fixed_headers {
+        headers
+            .entry(name)
+            .or_insert_with(|| HeaderValue::from_static(value));
+    }
+
+    res
+}
@@ -0,0 +1,144 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::users::Password;
+
+/// Characters used in reset codes. Easily confused characters (0/O, 1/I) are left
+/// out, and there are exactly 32 so each random byte maps onto it without bias.
+const RESET_CODE_ALPHABET: &[u8; 32] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+/// Number of characters in a reset code, giving 80 bits of entropy.
+const RESET_CODE_LENGTH: usize = 16;
+
+/// A one-time code an administrator hands to a user so they can choose a new password.
+/// Only a hash of the code is stored.
+#[derive(Debug, Clone)]
+pub struct PasswordResetCode {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub code_hash: Password,
+    pub created_by: Option<Uuid>,
+    pub failed_attempts: i64,
+    pub expires_at: OffsetDateTime,
+    pub created_at: OffsetDateTime,
+}
+
+impl PasswordResetCode {
+    pub fn new(
+        user_id: Uuid,
+        created_by: Uuid,
+        code: &str,
+        ttl: Duration,
+    ) -> Result<Self, anyhow::Error> {
+        let now = OffsetDateTime::now_utc();
+
+        Ok(Self {
+            id: Uuid::now_v7(),
+            user_id,
+            code_hash: Password::new(&normalize_reset_code(code))?,
+            created_by: Some(created_by),
+            failed_attempts: 0,
+            expires_at: now.saturating_add(ttl),
+            created_at: now,
+        })
+    }
+
+    pub fn verify(&self, code: &str) -> bool {
+        self.code_hash.verify(normalize_reset_code(code).as_bytes())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatePasswordResetRequest {
+    pub user_id: Uuid,
+}
+
+/// The plaintext reset code. This is the only time the code is ever shown.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordResetCodeResponse {
+    pub code: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct RedeemPasswordResetRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub username: String,
+    #[validate(length(min = 1, max = 64))]
+    pub code: String,
+    #[validate(custom(function = crate::validation::password_complexity))]
+    pub raw_password: String,
+}
+
+/// Generate a random reset code formatted in dash separated groups of four
+/// characters, e.g. `ABCD-EFGH-JKLM-NPQR`.
+pub fn generate_reset_code() -> String {
+    let mut bytes = [0u8; RESET_CODE_LENGTH];
+    OsRng.fill_bytes(&mut bytes);
+
+    let chars = bytes
+        .iter()
+        .map(|b| char::from(RESET_CODE_ALPHABET[usize::from(b & 0x1f)]))
+        .collect::<Vec<_>>();
+
+    chars
+        .chunks(4)
+        .map(|group| group.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Normalize a user supplied code so that case, spaces and dashes do not matter.
+pub fn normalize_reset_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_code_format() {
+        let code = generate_reset_code();
+        let groups = code.split('-').collect::<Vec<_>>();
+
+        assert_eq!(groups.len(), RESET_CODE_LENGTH / 4);
+        assert!(groups.iter().all(|g| g.len() == 4));
+        assert!(
+            normalize_reset_code(&code)
+                .bytes()
+                .all(|b| RESET_CODE_ALPHABET.contains(&b))
+        );
+    }
+
+    #[test]
+    fn test_generated_codes_differ() {
+        assert_ne!(generate_reset_code(), generate_reset_code());
+    }
+
+    #[test]
+    fn test_verify_ignores_case_and_separators() {
+        let reset = PasswordResetCode::new(
+            Uuid::now_v7(),
+            Uuid::now_v7(),
+            "ABCD-EFGH-JKLM-NPQR",
+            Duration::hours(1),
+        )
+        .unwrap();
+
+        assert!(reset.verify("abcd efgh jklm npqr"));
+        assert!(reset.verify("ABCDEFGHJKLMNPQR"));
+        assert!(!reset.verify("ABCD-EFGH-JKLM-NPQS"));
+    }
+}
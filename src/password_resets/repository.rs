@@ -0,0 +1,166 @@
+use async_trait::async_trait;
+use sqlx::SqlitePool;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    errors::RepositoryError,
+    password_resets::PasswordResetCode,
+    users::{UserBase, write_password},
+};
+
+#[async_trait]
+pub trait IPasswordResetRepository: Send + Sync {
+    /// Store a new reset code, discarding any unused codes previously issued to the user.
+    async fn create(&self, reset_code: &PasswordResetCode) -> Result<(), RepositoryError>;
+
+    /// Get the most recent unused, unexpired reset code for a user.
+    async fn get_active_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<PasswordResetCode, RepositoryError>;
+
+    /// Increment the number of failed attempts to redeem a reset code.
+    async fn record_failed_attempt(&self, id: Uuid) -> Result<(), RepositoryError>;
+
+    /// Mark a reset code as used, set the user's new password and clear their failed
+    /// logins in one transaction, so a failed update leaves the code usable. Returns `false`, changing nothing,
+    /// when the code had already been used.
+    async fn redeem(
+        &self,
+        id: Uuid,
+        user: &UserBase,
+        history_size: i64,
+    ) -> Result<bool, RepositoryError>;
+}
+
+pub struct SqlxPasswordResetRepository {
+    pub pool: SqlitePool,
+}
+
+impl SqlxPasswordResetRepository {
+    pub const fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl IPasswordResetRepository for SqlxPasswordResetRepository {
+    async fn create(&self, reset_code: &PasswordResetCode) -> Result<(), RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            "DELETE FROM password_reset_codes WHERE user_id = ? AND used_at IS NULL",
+            reset_code.user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"INSERT INTO password_reset_codes (id, user_id, code_hash, created_by, expires_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+            reset_code.id,
+            reset_code.user_id,
+            reset_code.code_hash,
+            reset_code.created_by,
+            reset_code.expires_at,
+            reset_code.created_at,
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn get_active_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<PasswordResetCode, RepositoryError> {
+        let now = OffsetDateTime::now_utc();
+
+        let reset_code = sqlx::query_as!(
+            PasswordResetCode,
+            r#"
+            SELECT
+                id AS "id: uuid::Uuid",
+                user_id AS "user_id: uuid::Uuid",
+                code_hash,
+                created_by AS "created_by: uuid::Uuid",
+                failed_attempts,
+                expires_at,
+                created_at
+            FROM password_reset_codes
+            WHERE user_id = ?
+                AND used_at IS NULL
+                AND expires_at > ?
+            ORDER BY created_at DESC
+            LIMIT 1
+            "#,
+            user_id,
+            now
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        reset_code.ok_or(RepositoryError::NotFound {
+            entity: "password reset code",
+            property: "user_id",
+            value: user_id.to_string(),
+        })
+    }
+
+    async fn record_failed_attempt(&self, id: Uuid) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "UPDATE password_reset_codes SET failed_attempts = failed_attempts + 1 WHERE id = ?",
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn redeem(
+        &self,
+        id: Uuid,
+        user: &UserBase,
+        history_size: i64,
+    ) -> Result<bool, RepositoryError> {
+        let now = OffsetDateTime::now_utc();
+        let mut tx = self.pool.begin().await?;
+
+        // claim the code first so it can only ever be used once
+        let result = sqlx::query!(
+            "UPDATE password_reset_codes SET used_at = ? WHERE id = ? AND used_at IS NULL",
+            now,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() != 1 {
+            return Ok(false);
+        }
+
+        write_password(&mut tx, user, history_size).await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE users
+            SET failed_login_attempts = 0,
+            last_failed_login_attempt = NULL
+            WHERE id = ?
+            "#,
+            user.id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+}
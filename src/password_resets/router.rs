@@ -0,0 +1,104 @@
+use axum::{Json, Router, extract::State, response::IntoResponse, routing::post};
+use hyper::StatusCode;
+
+use crate::{
+    errors::ApiError,
+    extractors::{
+        JsonBody, RequirePermission, ValidatedJson, authenticated_user::AuthenticatedUser,
+    },
+    password_resets::{
+        CreatePasswordResetRequest, PasswordResetCodeResponse, RedeemPasswordResetRequest,
+    },
+    roles::permissions::UsersManage,
+    services::ServiceContainer,
+};
+
+pub fn router() -> Router<ServiceContainer> {
+    Router::new()
+        .route("/", post(create_reset_code))
+        .route("/redeem", post(redeem_reset_code))
+}
+
+// Clippy lint triggered by utoipa macro expansion, not our code
+#[allow(clippy::needless_for_each)]
+#[derive(utoipa::OpenApi)]
+#[openapi(
+    paths(
+        crate::password_resets::create_reset_code,
+        crate::password_resets::redeem_reset_code,
+    ),
+    components(
+        schemas(
+            CreatePasswordResetRequest,
+            PasswordResetCodeResponse,
+            RedeemPasswordResetRequest
+        )
+    ),
+    tags(
+        (
+            name = "Password Resets",
+            description = "Admin-issued, one-time password reset codes"
+        )
+    )
+)]
+pub struct PasswordResetApiDoc;
+
+#[utoipa::path(
+    post,
+    summary = "Create Password Reset Code",
+    path = "/api/password-resets",
+    tag = "Password Resets",
+    request_body = CreatePasswordResetRequest,
+    responses(
+        (status = 201, description = "Reset code created", body = PasswordResetCodeResponse),
+        (status = 400, description = "Invalid request body"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - requires the users:manage permission, every \
+                                      permission the user holds, and a session login rather \
+                                      than an API token"),
+        (status = 404, description = "User not found"),
+    ),
+    description = "Generates a time-limited, single-use password reset code for a user. \
+        The plaintext code is only returned in this response and must be handed to the \
+        user, who redeems it to choose their own password. Issuing a new code discards \
+        any unused code previously issued to the same user. \
+        Requires a valid session_id cookie and the users:manage permission to access this endpoint; \
+        API tokens cannot issue reset codes."
+)]
+pub async fn create_reset_code(
+    auth: AuthenticatedUser,
+    admin: RequirePermission<UsersManage>,
+    State(container): State<ServiceContainer>,
+    JsonBody(req): JsonBody<CreatePasswordResetRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    auth.require_session()?;
+    let code = container
+        .password_reset_service()
+        .create_code(req.user_id, admin.user.id, &admin.user.permissions)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(code)))
+}
+
+#[utoipa::path(
+    post,
+    summary = "Redeem Password Reset Code",
+    path = "/api/password-resets/redeem",
+    tag = "Password Resets",
+    request_body = RedeemPasswordResetRequest,
+    responses(
+        (status = 204, description = "Password changed successfully"),
+        (status = 400, description = "Invalid request body or invalid/expired reset code"),
+    ),
+    description = "Sets a new password for the user using a reset code issued by an \
+        administrator. This endpoint does not require authentication. On success the code \
+        is consumed and all of the user's existing sessions are revoked. A code stops \
+        working after repeated wrong guesses."
+)]
+pub async fn redeem_reset_code(
+    State(container): State<ServiceContainer>,
+    ValidatedJson(req): ValidatedJson<RedeemPasswordResetRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    container.password_reset_service().redeem(req).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,280 @@
+use std::sync::{Arc, LazyLock};
+
+use async_trait::async_trait;
+use serde_json::json;
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    api_tokens::IApiTokenRepository,
+    audit::{AuditAction, AuditEvent, IAuditRepository},
+    config::Config,
+    errors::{RepositoryError, ServiceError},
+    password_resets::{
+        IPasswordResetRepository, PasswordResetCode, PasswordResetCodeResponse,
+        RedeemPasswordResetRequest, generate_reset_code,
+    },
+    roles::{IRoleRepository, Permission, ensure_can_delegate},
+    sessions::ISessionRepository,
+    users::{IUserRepository, Password},
+};
+
+/// Number of wrong guesses after which a reset code can no longer be redeemed.
+const MAX_FAILED_RESET_ATTEMPTS: i64 = 5;
+
+#[async_trait]
+pub trait IPasswordResetService: Send + Sync {
+    /// Issue a new reset code for a user on behalf of an administrator. Users holding
+    /// permissions the administrator, given as `admin_permissions`, does not hold are
+    /// refused.
+    async fn create_code(
+        &self,
+        user_id: Uuid,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+    ) -> Result<PasswordResetCodeResponse, ServiceError>;
+
+    /// Redeem a reset code, setting the user's new password, clearing any lockout from
+    /// failed logins and revoking all of their sessions and API tokens.
+    async fn redeem(&self, request: RedeemPasswordResetRequest) -> Result<(), ServiceError>;
+}
+
+pub struct PasswordResetService {
+    resets: Arc<dyn IPasswordResetRepository>,
+    users: Arc<dyn IUserRepository>,
+    roles: Arc<dyn IRoleRepository>,
+    sessions: Arc<dyn ISessionRepository>,
+    api_tokens: Arc<dyn IApiTokenRepository>,
+    audit: Arc<dyn IAuditRepository>,
+    config: Arc<Config>,
+}
+
+impl PasswordResetService {
+    pub fn new(
+        reset_repo: Arc<dyn IPasswordResetRepository>,
+        user_repo: Arc<dyn IUserRepository>,
+        role_repo: Arc<dyn IRoleRepository>,
+        session_repo: Arc<dyn ISessionRepository>,
+        api_token_repo: Arc<dyn IApiTokenRepository>,
+        audit_repo: Arc<dyn IAuditRepository>,
+        config: Arc<Config>,
+    ) -> Self {
+        Self {
+            resets: reset_repo,
+            users: user_repo,
+            roles: role_repo,
+            sessions: session_repo,
+            api_tokens: api_token_repo,
+            audit: audit_repo,
+            config,
+        }
+    }
+}
+
+/// Hash that codes are checked against when there is no real code to check, so that
+/// every refused redemption costs one hash verification, whatever the reason.
+static DUMMY_CODE_HASH: LazyLock<Option<Password>> =
+    LazyLock::new(|| Password::new("fake reset code").ok());
+
+fn invalid_code() -> ServiceError {
+    ServiceError::BadRequest("invalid or expired password reset code".into())
+}
+
+/// Refuse a redemption without a code to check, taking as long as a wrong guess would
+/// so that whether the user exists or has a code cannot be told by timing.
+fn refuse_without_code(code: &str) -> ServiceError {
+    if let Some(dummy) = DUMMY_CODE_HASH.as_ref() {
+        _ = dummy.verify(code.as_bytes());
+    }
+
+    invalid_code()
+}
+
+#[async_trait]
+impl IPasswordResetService for PasswordResetService {
+    async fn create_code(
+        &self,
+        user_id: Uuid,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+    ) -> Result<PasswordResetCodeResponse, ServiceError> {
+        // make sure the user exists before issuing a code for them
+        self.users.get_by_id(user_id).await?;
+        let permissions = self.roles.get_permissions_for_user(user_id).await?;
+        ensure_can_delegate(admin_permissions, &permissions)?;
+
+        let code = generate_reset_code();
+        let reset_code = PasswordResetCode::new(
+            user_id,
+            admin_id,
+            &code,
+            self.config.password_reset_code_ttl,
+        )?;
+
+        self.resets.create(&reset_code).await?;
+        self.audit
+            .record(
+                &AuditEvent::success(AuditAction::PasswordResetCodeIssued).target("user", user_id),
+            )
+            .await;
+
+        tracing::info!(
+            user_id = %user_id,
+            admin_id = %admin_id,
+            "Password reset code issued"
+        );
+
+        Ok(PasswordResetCodeResponse {
+            code,
+            expires_at: reset_code.expires_at,
+        })
+    }
+
+    async fn redeem(&self, request: RedeemPasswordResetRequest) -> Result<(), ServiceError> {
+        let mut user_base = match self.users.get_by_username(&request.username).await {
+            Ok(user_base) => user_base,
+            Err(RepositoryError::NotFound { .. }) => {
+                return Err(refuse_without_code(&request.code));
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let reset_code = match self.resets.get_active_for_user(user_base.id).await {
+            Ok(reset_code) => reset_code,
+            Err(RepositoryError::NotFound { .. }) => {
+                return Err(refuse_without_code(&request.code));
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        if reset_code.failed_attempts >= MAX_FAILED_RESET_ATTEMPTS {
+            return Err(refuse_without_code(&request.code));
+        }
+
+        if !reset_code.verify(&request.code) {
+            self.resets.record_failed_attempt(reset_code.id).await?;
+            self.audit
+                .record(
+                    &AuditEvent::failure(AuditAction::PasswordResetRedeemed)
+                        .target("user", user_base.id)
+                        .details(json!({ "reason": "wrong code" })),
+                )
+                .await;
+            return Err(invalid_code());
+        }
+
+        let policy = self.users.get_password_policy().await?;
+        let now = OffsetDateTime::now_utc();
+        user_base.password_hash = Password::new(&request.raw_password)?;
+        user_base.password_expiration = policy.expiration_from(now);
+        user_base.updated_at = now;
+
+        let redeemed = self
+            .resets
+            .redeem(reset_code.id, &user_base, self.config.password_history_size)
+            .await?;
+        if !redeemed {
+            return Err(invalid_code());
+        }
+
+        self.sessions.delete_all_for_user(user_base.id).await?;
+        self.api_tokens.delete_all_for_user(user_base.id).await?;
+        self.audit
+            .record(
+                &AuditEvent::success(AuditAction::PasswordResetRedeemed)
+                    .actor(user_base.id)
+                    .target("user", user_base.id),
+            )
+            .await;
+
+        tracing::info!(user_id = %user_base.id, "Password reset code redeemed");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::Database, roles::ADMINISTRATOR_ROLE, services::ServiceContainer,
+        users::CreateUserRequest,
+    };
+
+    async fn create_user(container: &ServiceContainer, username: &str, role: &str) -> Uuid {
+        let roles = container.role_service().get_all().await.unwrap();
+        let role_id = roles.iter().find(|r| r.name == role).unwrap().id;
+        container
+            .user_service()
+            .create(
+                CreateUserRequest {
+                    first_name: "Test".to_string(),
+                    last_name: "User".to_string(),
+                    email: format!("{username}@example.com"),
+                    username: username.to_string(),
+                    raw_password: "Password12!!xY".to_string(),
+                    password_expiration: OffsetDateTime::now_utc() + time::Duration::days(90),
+                    roles: vec![role_id],
+                },
+                Permission::ALL,
+            )
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_create_code_refuses_users_holding_permissions_the_caller_lacks() {
+        let db = Database::in_memory().await;
+        let container = ServiceContainer::new(db.pool, Config::for_tests());
+        let admin_id = create_user(&container, "admin", ADMINISTRATOR_ROLE).await;
+        let target = create_user(&container, "target", ADMINISTRATOR_ROLE).await;
+
+        let err = container
+            .password_reset_service()
+            .create_code(target, admin_id, &[Permission::UsersManage])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::Forbidden(_)));
+    }
+
+    #[tokio::test]
+    async fn test_redeem_clears_failed_logins() {
+        let db = Database::in_memory().await;
+        let pool = db.pool.clone();
+        let container = ServiceContainer::new(db.pool, Config::for_tests());
+        let admin_id = create_user(&container, "admin", ADMINISTRATOR_ROLE).await;
+        let target = create_user(&container, "target", "RecipeUser").await;
+        sqlx::query(
+            "UPDATE users SET failed_login_attempts = 5, last_failed_login_attempt = ? WHERE id = ?",
+        )
+        .bind(OffsetDateTime::now_utc())
+        .bind(target)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let resets = container.password_reset_service();
+        let code = resets
+            .create_code(target, admin_id, Permission::ALL)
+            .await
+            .unwrap();
+        resets
+            .redeem(RedeemPasswordResetRequest {
+                username: "target".to_string(),
+                code: code.code,
+                raw_password: "Another12!!xY".to_string(),
+            })
+            .await
+            .unwrap();
+
+        let (attempts, last): (i64, Option<OffsetDateTime>) = sqlx::query_as(
+            "SELECT failed_login_attempts, last_failed_login_attempt FROM users WHERE id = ?",
+        )
+        .bind(target)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(attempts, 0);
+        assert!(last.is_none());
+    }
+}
@@ -0,0 +1,475 @@
+//! In-process token bucket rate limiting.
+//!
+//! Each key gets a bucket holding up to a limit's worth of tokens that refills at an
+//! even pace over a minute, so short bursts are allowed while a sustained rate above
+//! the limit is refused. Buckets live in memory and are not shared between processes.
+
+use std::{
+    collections::{BTreeMap, HashMap, hash_map::Entry},
+    fmt::{self, Display},
+    hash::Hash,
+    net::IpAddr,
+    str::FromStr,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail};
+use uuid::Uuid;
+
+use crate::config::RateLimitConfig;
+
+/// Most buckets kept at once. Past it, the bucket used least recently is dropped,
+/// which forgets what that key had used up.
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+/// Buckets refill completely within a minute, so one left alone this long behaves
+/// like a missing one and can be dropped.
+const IDLE_AFTER: Duration = Duration::from_mins(1);
+
+/// Path prefix of the routes covered by the default API budget.
+const API_PREFIX: &str = "/api";
+
+/// How many requests a key may make per minute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub per_minute: u32,
+}
+
+impl RateLimit {
+    fn capacity(self) -> f64 {
+        f64::from(self.per_minute)
+    }
+
+    fn tokens_per_second(self) -> f64 {
+        f64::from(self.per_minute) / 60.0
+    }
+}
+
+/// Where a key stands against its limit after a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    pub limit: u32,
+    /// Requests that can still be made right away.
+    pub remaining: u32,
+    /// Until the bucket is full again after an allowed request, or until the next
+    /// request is allowed after a refused one.
+    pub reset: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// Buckets by key, along with the order the keys were last used in, so that idle
+/// and excess buckets are found without looking at every bucket.
+#[derive(Debug)]
+struct Buckets<K> {
+    /// Each bucket with the number of its last use in `by_use`.
+    by_key: HashMap<K, (Bucket, u64)>,
+    /// Keys by their last use, least recent first.
+    by_use: BTreeMap<u64, K>,
+    uses: u64,
+}
+
+impl<K: Hash + Eq + Clone> Buckets<K> {
+    fn new() -> Self {
+        Self {
+            by_key: HashMap::new(),
+            by_use: BTreeMap::new(),
+            uses: 0,
+        }
+    }
+
+    /// Drop the buckets nobody has used for [`IDLE_AFTER`].
+    fn evict_idle(&mut self, now: Instant) {
+        while let Some(oldest) = self.by_use.first_entry() {
+            let is_idle = self.by_key.get(oldest.get()).is_none_or(|(bucket, _)| {
+                now.saturating_duration_since(bucket.updated_at) >= IDLE_AFTER
+            });
+            if !is_idle {
+                break;
+            }
+            self.by_key.remove(&oldest.remove());
+        }
+    }
+
+    /// The bucket of `key`, marked as the most recently used one. A new key gets
+    /// `fresh`, making room for it when [`MAX_TRACKED_KEYS`] buckets are kept.
+    fn touch(&mut self, key: K, fresh: Bucket) -> &mut Bucket {
+        if self.by_key.len() >= MAX_TRACKED_KEYS
+            && !self.by_key.contains_key(&key)
+            && let Some((_, least_recent)) = self.by_use.pop_first()
+        {
+            self.by_key.remove(&least_recent);
+        }
+
+        let sequence = self.uses;
+        self.uses += 1;
+        self.by_use.insert(sequence, key.clone());
+
+        match self.by_key.entry(key) {
+            Entry::Occupied(entry) => {
+                let (bucket, last_use) = entry.into_mut();
+                self.by_use.remove(last_use);
+                *last_use = sequence;
+                bucket
+            }
+            Entry::Vacant(entry) => &mut entry.insert((fresh, sequence)).0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RateLimiter<K> {
+    limit: RateLimit,
+    buckets: Mutex<Buckets<K>>,
+}
+
+impl<K: Hash + Eq + Clone> RateLimiter<K> {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            buckets: Mutex::new(Buckets::new()),
+        }
+    }
+
+    /// Take a token for `key`. Refused requests do not use up anything.
+    pub fn check(&self, key: K) -> Result<Quota, Quota> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: K, now: Instant) -> Result<Quota, Quota> {
+        let capacity = self.limit.capacity();
+        let rate = self.limit.tokens_per_second();
+        let refill = |bucket: &Bucket| {
+            let elapsed = now
+                .saturating_duration_since(bucket.updated_at)
+                .as_secs_f64();
+            elapsed.mul_add(rate, bucket.tokens).min(capacity)
+        };
+
+        // The map only holds plain numbers, so it is still usable after a panic.
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+
+        buckets.evict_idle(now);
+        let bucket = buckets.touch(
+            key,
+            Bucket {
+                tokens: capacity,
+                updated_at: now,
+            },
+        );
+        bucket.tokens = refill(bucket);
+        bucket.updated_at = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        let tokens = bucket.tokens;
+        drop(buckets);
+
+        let quota = Quota {
+            limit: self.limit.per_minute,
+            remaining: whole_tokens(tokens),
+            reset: Duration::from_secs_f64(if allowed {
+                (capacity - tokens) / rate
+            } else {
+                (1.0 - tokens) / rate
+            }),
+        };
+
+        if allowed { Ok(quota) } else { Err(quota) }
+    }
+}
+
+// A bucket never holds less than zero or more than `u32::MAX` tokens.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+const fn whole_tokens(tokens: f64) -> u32 {
+    tokens.floor() as u32
+}
+
+/// Which of the login limits an attempt ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoginLimit {
+    PerIp,
+    PerUsername,
+}
+
+impl Display for LoginLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::PerIp => "per client address",
+            Self::PerUsername => "per username",
+        })
+    }
+}
+
+/// Limits login attempts both per client address and per username, so that neither
+/// one client trying many accounts nor many clients trying one account get far.
+#[derive(Debug)]
+pub struct LoginRateLimiter {
+    by_ip: RateLimiter<IpAddr>,
+    by_username: RateLimiter<String>,
+}
+
+impl LoginRateLimiter {
+    pub fn new(per_ip: RateLimit, per_username: RateLimit) -> Self {
+        Self {
+            by_ip: RateLimiter::new(per_ip),
+            by_username: RateLimiter::new(per_username),
+        }
+    }
+
+    pub fn from_config(config: &RateLimitConfig) -> Self {
+        Self::new(config.login_per_ip, config.login_per_username)
+    }
+
+    /// Count a login attempt, or return the limit it ran into and how long the client
+    /// has to wait.
+    pub fn check(&self, ip: Option<IpAddr>, username: &str) -> Result<(), (LoginLimit, Duration)> {
+        if let Some(ip) = ip {
+            self.by_ip
+                .check(ip)
+                .map_err(|quota| (LoginLimit::PerIp, quota.reset))?;
+        }
+
+        self.by_username
+            .check(username.trim().to_lowercase())
+            .map(drop)
+            .map_err(|quota| (LoginLimit::PerUsername, quota.reset))
+    }
+}
+
+/// Budgets for one group of API routes, matched by path prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteBudget {
+    pub prefix: String,
+    /// Requests per minute for each signed in user.
+    pub per_user: RateLimit,
+    /// Requests per minute for each client address making anonymous requests.
+    pub per_ip: RateLimit,
+}
+
+impl RouteBudget {
+    /// Parse a comma separated list of `prefix=per_user/per_ip` entries, e.g.
+    /// `/api/recipes=600/120, /api/audit=30/10`.
+    pub fn parse_list(list: &str) -> Result<Vec<Self>, anyhow::Error> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(str::parse)
+            .collect()
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        path.strip_prefix(self.prefix.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+}
+
+impl FromStr for RouteBudget {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (prefix, limits) = value
+            .split_once('=')
+            .ok_or_else(|| anyhow!("`{value}` is not of the form prefix=per_user/per_ip"))?;
+        let (per_user, per_ip) = limits
+            .split_once('/')
+            .ok_or_else(|| anyhow!("`{value}` is not of the form prefix=per_user/per_ip"))?;
+
+        let prefix = prefix.trim().trim_end_matches('/');
+        if !prefix.starts_with('/') {
+            bail!("`{value}` must start with a path such as /api/recipes");
+        }
+
+        let parse_limit = |limit: &str| match limit.trim().parse::<u32>() {
+            Ok(per_minute) if per_minute > 0 => Ok(RateLimit { per_minute }),
+            _ => Err(anyhow!("`{value}` has an invalid limit `{limit}`")),
+        };
+
+        Ok(Self {
+            prefix: prefix.to_string(),
+            per_user: parse_limit(per_user)?,
+            per_ip: parse_limit(per_ip)?,
+        })
+    }
+}
+
+/// Who an API request is counted against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitClient {
+    User(Uuid),
+    Ip(IpAddr),
+}
+
+impl Display for RateLimitClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::User(id) => write!(f, "user {id}"),
+            Self::Ip(ip) => write!(f, "address {ip}"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct RouteGroup {
+    budget: RouteBudget,
+    by_user: RateLimiter<Uuid>,
+    by_ip: RateLimiter<IpAddr>,
+}
+
+/// Limits API requests per signed in user, or per client address for anonymous
+/// requests. Every route group has its own budget and buckets, and routes outside
+/// of any configured group share the default one.
+#[derive(Debug)]
+pub struct ApiRateLimiter {
+    /// Most specific prefix first, ending with the default group.
+    groups: Vec<RouteGroup>,
+}
+
+impl ApiRateLimiter {
+    pub fn new(per_user: RateLimit, per_ip: RateLimit, groups: &[RouteBudget]) -> Self {
+        let mut budgets = groups.to_vec();
+        budgets.sort_by_key(|budget| std::cmp::Reverse(budget.prefix.len()));
+        budgets.push(RouteBudget {
+            prefix: API_PREFIX.to_string(),
+            per_user,
+            per_ip,
+        });
+
+        let groups = budgets
+            .into_iter()
+            .map(|budget| RouteGroup {
+                by_user: RateLimiter::new(budget.per_user),
+                by_ip: RateLimiter::new(budget.per_ip),
+                budget,
+            })
+            .collect();
+
+        Self { groups }
+    }
+
+    pub fn from_config(config: &RateLimitConfig) -> Self {
+        Self::new(config.api_per_user, config.api_per_ip, &config.api_groups)
+    }
+
+    /// Count a request to `path`, returning the group it was counted in, or `None`
+    /// when the path is not rate limited at all.
+    pub fn check(
+        &self,
+        path: &str,
+        client: RateLimitClient,
+    ) -> Option<(&RouteBudget, Result<Quota, Quota>)> {
+        let group = self
+            .groups
+            .iter()
+            .find(|group| group.budget.matches(path))?;
+
+        let result = match client {
+            RateLimitClient::User(id) => group.by_user.check(id),
+            RateLimitClient::Ip(ip) => group.by_ip.check(ip),
+        };
+
+        Some((&group.budget, result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_allows_burst_then_refills() {
+        let limiter = RateLimiter::new(RateLimit { per_minute: 3 });
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            assert!(limiter.check_at("key", start).is_ok());
+        }
+
+        let refused = limiter.check_at("key", start).unwrap_err();
+        assert_eq!(refused.remaining, 0);
+        assert_eq!(refused.reset.as_secs(), 20);
+
+        assert!(limiter.check_at("other", start).is_ok());
+        assert!(
+            limiter
+                .check_at("key", start + Duration::from_secs(20))
+                .is_ok()
+        );
+        assert!(
+            limiter
+                .check_at("key", start + Duration::from_secs(21))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_buckets_are_capped_and_idle_ones_dropped() {
+        let limiter = RateLimiter::new(RateLimit { per_minute: 1 });
+        let start = Instant::now();
+
+        for key in 0..=MAX_TRACKED_KEYS {
+            assert!(limiter.check_at(key, start).is_ok());
+        }
+        // the first key was used least recently, so its bucket made room for the last
+        assert!(limiter.check_at(0, start).is_ok());
+        assert!(limiter.check_at(MAX_TRACKED_KEYS, start).is_err());
+        assert_eq!(
+            limiter.buckets.lock().unwrap().by_key.len(),
+            MAX_TRACKED_KEYS
+        );
+
+        let later = start + IDLE_AFTER + Duration::from_secs(1);
+        assert!(limiter.check_at(1, later).is_ok());
+        let sizes = {
+            let buckets = limiter.buckets.lock().unwrap();
+            (buckets.by_key.len(), buckets.by_use.len())
+        };
+        assert_eq!(sizes, (1, 1));
+    }
+
+    #[test]
+    fn test_login_limit_ignores_username_case() {
+        let limiter =
+            LoginRateLimiter::new(RateLimit { per_minute: 100 }, RateLimit { per_minute: 1 });
+
+        assert!(limiter.check(None, "Alice").is_ok());
+        assert_eq!(
+            limiter.check(None, "alice ").unwrap_err().0,
+            LoginLimit::PerUsername
+        );
+        assert!(limiter.check(None, "bob").is_ok());
+    }
+
+    #[test]
+    fn test_route_groups_have_separate_budgets() {
+        let groups = RouteBudget::parse_list("/api/audit/=1/1").unwrap();
+        assert_eq!(groups[0].prefix, "/api/audit");
+
+        let limiter = ApiRateLimiter::new(
+            RateLimit { per_minute: 2 },
+            RateLimit { per_minute: 1 },
+            &groups,
+        );
+        let user = RateLimitClient::User(Uuid::new_v4());
+
+        let (budget, result) = limiter.check("/api/audit", user).unwrap();
+        assert_eq!(budget.prefix, "/api/audit");
+        assert!(result.is_ok());
+        assert!(limiter.check("/api/audit/events", user).unwrap().1.is_err());
+
+        let (budget, result) = limiter.check("/api/auditing", user).unwrap();
+        assert_eq!(budget.prefix, "/api");
+        assert_eq!(result.unwrap().remaining, 1);
+
+        assert!(limiter.check("/docs", user).is_none());
+        assert!(RouteBudget::parse_list("/api/recipes=0/5").is_err());
+        assert!(RouteBudget::parse_list("recipes=10/5").is_err());
+    }
+}
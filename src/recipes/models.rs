@@ -6,7 +6,8 @@ use uuid::Uuid;
 #[serde(rename_all = "camelCase")]
 pub struct Recipe {
     pub id: Uuid,
-    pub user_id: Uuid,
+    /// The owner, or `None` for a public recipe kept after its owner's account was purged.
+    pub user_id: Option<Uuid>,
     pub name: String,
     pub author: Option<String>,
     pub description: Option<String>,
@@ -21,7 +22,7 @@ pub struct Recipe {
 #[serde(rename_all = "camelCase")]
 pub struct RecipeBase {
     pub id: Uuid,
-    pub user_id: Uuid,
+    pub user_id: Option<Uuid>,
     pub name: String,
     pub author: Option<String>,
     pub description: Option<String>,
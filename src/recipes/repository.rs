@@ -1,8 +1,7 @@
-use std::num::Wrapping;
-
 use crate::{
     errors::RepositoryError,
     recipes::{Ingredient, Instruction, Recipe, RecipeBase, RecipeRequest},
+    shared_models::page_offset,
 };
 use async_trait::async_trait;
 use sqlx::SqlitePool;
@@ -20,6 +19,9 @@ pub trait IRecipeRepository: Send + Sync {
 
     async fn get_by_id(&self, recipe_id: Uuid) -> Result<Recipe, RepositoryError>;
 
+    /// Get every recipe owned by a user, public or not.
+    async fn get_all_by_user_id(&self, user_id: Uuid) -> Result<Vec<RecipeBase>, RepositoryError>;
+
     async fn create(
         &self,
         user_id: Uuid,
@@ -67,28 +69,7 @@ impl IRecipeRepository for SqlxRecipeRepository {
         page_size: i64,
         name_query: Option<&str>,
     ) -> Result<(Vec<RecipeBase>, i64), RepositoryError> {
-        if page < 1 {
-            return Err(RepositoryError::ArgumentOutOfRange {
-                field: "page",
-                value: format!("page={page}"),
-            });
-        }
-
-        if page_size < 1 {
-            return Err(RepositoryError::ArgumentOutOfRange {
-                field: "page_size",
-                value: format!("page_size={page_size}"),
-            });
-        }
-
-        let offset = Wrapping(page - 1) * Wrapping(page_size);
-        if offset.0 < 0 {
-            return Err(RepositoryError::ArgumentOutOfRange {
-                field: "offset",
-                value: format!("page={page}; page_size={page_size}"),
-            });
-        }
-        let offset = offset.0;
+        let offset = page_offset(page, page_size)?;
 
         // Example: input -> Some("apple") -> COALESCE('%' || 'apple' || '%', '%') -> matches apple
         // Example: input -> None -> COALESCE('%' || NULL || '%', '%') -> becomes '%' and matches anything
@@ -192,6 +173,29 @@ impl IRecipeRepository for SqlxRecipeRepository {
         Ok(recipe)
     }
 
+    async fn get_all_by_user_id(&self, user_id: Uuid) -> Result<Vec<RecipeBase>, RepositoryError> {
+        let recipes = sqlx::query_as!(
+            RecipeBase,
+            r#"SELECT
+                id as "id: uuid::Uuid",
+                user_id as "user_id: uuid::Uuid",
+                author,
+                name,
+                description,
+                difficulty,
+                estimated_duration,
+                is_public
+            FROM recipes
+            WHERE user_id = ?
+            ORDER BY name ASC"#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(recipes)
+    }
+
     async fn create(
         &self,
         user_id: Uuid,
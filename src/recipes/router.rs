@@ -13,9 +13,10 @@ use serde::{self, Deserialize};
 use uuid::Uuid;
 
 use crate::errors::ApiError;
-use crate::extractors::authenticated_user::AuthenticatedUser;
+use crate::extractors::{JsonBody, RequirePermission};
 use crate::recipes::Recipe;
 use crate::recipes::RecipeRequest;
+use crate::roles::permissions::{RecipesRead, RecipesWrite};
 use crate::services::ServiceContainer;
 use crate::shared_models::PaginatedResponse;
 
@@ -46,7 +47,7 @@ pub fn router() -> Router<ServiceContainer> {
 }
 
 pub async fn get_all_recipes(
-    auth: AuthenticatedUser,
+    auth: RequirePermission<RecipesRead>,
     State(container): State<ServiceContainer>,
     Query(filters): Query<RecipeFilters>,
 ) -> Result<Json<PaginatedResponse<Recipe>>, ApiError> {
@@ -64,7 +65,7 @@ pub async fn get_all_recipes(
 }
 
 pub async fn get_by_id(
-    auth: AuthenticatedUser,
+    auth: RequirePermission<RecipesRead>,
     Path(id): Path<Uuid>,
     State(container): State<ServiceContainer>,
 ) -> Result<Json<Recipe>, ApiError> {
@@ -77,9 +78,9 @@ pub async fn get_by_id(
 }
 
 pub async fn create_recipe(
-    auth: AuthenticatedUser,
+    auth: RequirePermission<RecipesWrite>,
     State(container): State<ServiceContainer>,
-    Json(request): Json<RecipeRequest>,
+    JsonBody(request): JsonBody<RecipeRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     let recipe_id = container
         .recipe_service()
@@ -94,10 +95,10 @@ pub async fn create_recipe(
 }
 
 pub async fn update_recipe(
-    auth: AuthenticatedUser,
+    auth: RequirePermission<RecipesWrite>,
     State(container): State<ServiceContainer>,
     Path(id): Path<Uuid>,
-    Json(request): Json<RecipeRequest>,
+    JsonBody(request): JsonBody<RecipeRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     container
         .recipe_service()
@@ -108,7 +109,7 @@ pub async fn update_recipe(
 }
 
 pub async fn delete_recipe(
-    auth: AuthenticatedUser,
+    auth: RequirePermission<RecipesWrite>,
     State(container): State<ServiceContainer>,
     Path(id): Path<Uuid>,
 ) -> Result<impl IntoResponse, ApiError> {
@@ -1,9 +1,11 @@
+use serde_json::json;
 use uuid::Uuid;
 
+use crate::audit::{AuditAction, AuditEvent, IAuditRepository};
 use crate::errors::ServiceError;
 use crate::recipes::{
     IIngredientRepository, IInstructionRepository, IRecipeRepository, Ingredient, Instruction,
-    Recipe, RecipeRequest,
+    Recipe, RecipeBase, RecipeRequest,
 };
 use crate::shared_models::PaginatedResponse;
 use std::sync::Arc;
@@ -19,6 +21,9 @@ pub trait IRecipeService: Send + Sync {
         name_query: Option<&str>,
     ) -> Result<PaginatedResponse<Recipe>, ServiceError>;
 
+    /// Get every recipe owned by a user with its ingredients and instructions.
+    async fn get_owned_by(&self, user_id: Uuid) -> Result<Vec<Recipe>, ServiceError>;
+
     /// Get a recipe by its id.
     async fn get_by_id(&self, recipe_id: Uuid, user_id: Uuid) -> Result<Recipe, ServiceError>;
 
@@ -46,6 +51,7 @@ pub struct RecipeService {
     recipes: Arc<dyn IRecipeRepository>,
     ingredients: Arc<dyn IIngredientRepository>,
     instructions: Arc<dyn IInstructionRepository>,
+    audit: Arc<dyn IAuditRepository>,
 }
 
 impl RecipeService {
@@ -53,47 +59,20 @@ impl RecipeService {
         recipe_repo: Arc<dyn IRecipeRepository>,
         ingredient_repo: Arc<dyn IIngredientRepository>,
         instruction_repo: Arc<dyn IInstructionRepository>,
+        audit_repo: Arc<dyn IAuditRepository>,
     ) -> Self {
         Self {
             recipes: recipe_repo,
             ingredients: ingredient_repo,
             instructions: instruction_repo,
+            audit: audit_repo,
         }
     }
-}
-
-#[async_trait::async_trait]
-impl IRecipeService for RecipeService {
-    async fn get_user_and_public_recipes(
-        &self,
-        user_id: Uuid,
-        page: i64,
-        page_size: i64,
-        name_query: Option<&str>,
-    ) -> Result<PaginatedResponse<Recipe>, ServiceError> {
-        if page_size <= 0 {
-            return Err(ServiceError::BadRequest("invalid page size".into()));
-        }
-
-        // Get paginated recipe bases and total count from repository
-        let (recipe_bases, total) = self
-            .recipes
-            .get_user_and_public_recipes(user_id, page, page_size, name_query)
-            .await?;
-
-        // If no recipes, return early
-        if recipe_bases.is_empty() {
-            return Ok(PaginatedResponse {
-                data: vec![],
-                page,
-                page_size,
-                total,
-                total_pages: 0,
-            });
-        }
 
+    /// Attach the ingredients and instructions to each recipe.
+    async fn with_steps(&self, bases: Vec<RecipeBase>) -> Result<Vec<Recipe>, ServiceError> {
         // Collect recipe IDs
-        let recipe_ids: Vec<Uuid> = recipe_bases.iter().map(|r| r.id).collect();
+        let recipe_ids: Vec<Uuid> = bases.iter().map(|r| r.id).collect();
 
         // Fetch ingredients and instructions in parallel
         let (ingredients, instructions) = tokio::try_join!(
@@ -102,7 +81,7 @@ impl IRecipeService for RecipeService {
         )?;
 
         // Build full Recipe objects by combining base + ingredients + instructions
-        let recipes = recipe_bases
+        Ok(bases
             .into_iter()
             .map(|base| {
                 let recipe_ingredients: Vec<Ingredient> = ingredients
@@ -130,23 +109,48 @@ impl IRecipeService for RecipeService {
                     instructions: recipe_instructions,
                 }
             })
-            .collect();
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl IRecipeService for RecipeService {
+    async fn get_user_and_public_recipes(
+        &self,
+        user_id: Uuid,
+        page: i64,
+        page_size: i64,
+        name_query: Option<&str>,
+    ) -> Result<PaginatedResponse<Recipe>, ServiceError> {
+        if page_size <= 0 {
+            return Err(ServiceError::BadRequest("invalid page size".into()));
+        }
+
+        // Get paginated recipe bases and total count from repository
+        let (recipe_bases, total) = self
+            .recipes
+            .get_user_and_public_recipes(user_id, page, page_size, name_query)
+            .await?;
 
-        let total_pages = (total + page_size - 1) / page_size;
+        // If no recipes, return early
+        if recipe_bases.is_empty() {
+            return Ok(PaginatedResponse::new(vec![], page, page_size, total));
+        }
+
+        let recipes = self.with_steps(recipe_bases).await?;
+
+        Ok(PaginatedResponse::new(recipes, page, page_size, total))
+    }
 
-        Ok(PaginatedResponse {
-            data: recipes,
-            page,
-            page_size,
-            total,
-            total_pages,
-        })
+    async fn get_owned_by(&self, user_id: Uuid) -> Result<Vec<Recipe>, ServiceError> {
+        let bases = self.recipes.get_all_by_user_id(user_id).await?;
+        self.with_steps(bases).await
     }
 
     async fn get_by_id(&self, recipe_id: Uuid, user_id: Uuid) -> Result<Recipe, ServiceError> {
         let recipe = self.recipes.get_by_id(recipe_id).await?;
 
-        if recipe.is_public || recipe.user_id == user_id {
+        if recipe.is_public || recipe.user_id == Some(user_id) {
             Ok(recipe)
         } else {
             // If a recipe exists but a user doesn't own it or it isn't public, don't give away that information.
@@ -165,7 +169,15 @@ impl IRecipeService for RecipeService {
         request: RecipeRequest,
     ) -> Result<Uuid, ServiceError> {
         let recipe_id = Uuid::now_v7();
+        let name = request.name.clone();
         self.recipes.create(user_id, recipe_id, request).await?;
+        self.audit
+            .record(
+                &AuditEvent::success(AuditAction::RecipeCreated)
+                    .target("recipe", recipe_id)
+                    .details(json!({ "name": name })),
+            )
+            .await;
         Ok(recipe_id)
     }
 
@@ -176,7 +188,7 @@ impl IRecipeService for RecipeService {
         request: RecipeRequest,
     ) -> Result<(), ServiceError> {
         let recipe = self.recipes.get_by_id(recipe_id).await?;
-        if recipe.user_id != user_id {
+        if recipe.user_id != Some(user_id) {
             return Err(ServiceError::NotFound {
                 entity: "recipe",
                 property: "id",
@@ -184,7 +196,15 @@ impl IRecipeService for RecipeService {
             });
         }
 
+        let name = request.name.clone();
         self.recipes.update(recipe_id, request).await?;
+        self.audit
+            .record(
+                &AuditEvent::success(AuditAction::RecipeUpdated)
+                    .target("recipe", recipe_id)
+                    .details(json!({ "name": name })),
+            )
+            .await;
 
         Ok(())
     }
@@ -192,7 +212,7 @@ impl IRecipeService for RecipeService {
     async fn delete_recipe(&self, recipe_id: Uuid, user_id: Uuid) -> Result<(), ServiceError> {
         let recipe = self.recipes.get_by_id(recipe_id).await?;
 
-        if recipe.user_id != user_id {
+        if recipe.user_id != Some(user_id) {
             // If a recipe exists but a user doesn't own it, don't give away that information.
             // Just tell the user it wasn't found to prevent traversal attacks.
             return Err(ServiceError::NotFound {
@@ -203,6 +223,13 @@ impl IRecipeService for RecipeService {
         }
 
         self.recipes.delete(recipe_id).await?;
+        self.audit
+            .record(
+                &AuditEvent::success(AuditAction::RecipeDeleted)
+                    .target("recipe", recipe_id)
+                    .details(json!({ "name": recipe.name })),
+            )
+            .await;
         Ok(())
     }
 }
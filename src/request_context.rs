@@ -0,0 +1,32 @@
+use std::net::IpAddr;
+
+use uuid::Uuid;
+
+tokio::task_local! {
+    static CURRENT: RequestContext;
+}
+
+/// Who made the request being handled and where it came from. The middleware sets it
+/// for the duration of each request so that services can attribute what they do
+/// without every method taking the caller as an argument.
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    /// Identifies the request in logs and error responses, see
+    /// [`request_id_middleware`](crate::middleware::request_id_middleware).
+    pub request_id: Option<String>,
+    pub actor_id: Option<Uuid>,
+    pub ip_address: Option<IpAddr>,
+}
+
+impl RequestContext {
+    /// Run `future` with this context as the current one.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CURRENT.scope(self, future).await
+    }
+
+    /// The context of the request being handled, or an empty one outside of a request
+    /// such as in background jobs.
+    pub fn current() -> Self {
+        CURRENT.try_with(Clone::clone).unwrap_or_default()
+    }
+}
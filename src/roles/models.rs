@@ -1,48 +1,136 @@
-use std::fmt::Display;
+use std::{fmt::Display, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
 use utoipa::ToSchema;
 use uuid::Uuid;
+use validator::Validate;
+
+/// Name of the built-in role that holds every permission. The last enabled user
+/// with this role cannot lose it.
+pub const ADMINISTRATOR_ROLE: &str = "Administrator";
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, FromRow)]
+#[serde(rename_all = "camelCase")]
 pub struct Role {
     pub id: Uuid,
-    pub name: RoleName,
+    pub name: String,
+    /// Built-in roles cannot be renamed, changed or deleted.
+    pub is_system: bool,
 }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, sqlx::Type, Serialize, Deserialize, ToSchema)]
-#[serde(into = "String", from = "String")]
-pub enum RoleName {
-    Administrator,
-    RecipeUser,
-    Unknown,
+/// A role together with the permissions it grants.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub is_system: bool,
+    pub permissions: Vec<Permission>,
 }
 
-impl Display for RoleName {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let name = match self {
-            Self::Administrator => "Administrator",
-            Self::RecipeUser => "Recipe User",
-            Self::Unknown => "Unknown",
-        };
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub name: String,
+    pub permissions: Vec<Permission>,
+}
 
-        f.write_str(name)
-    }
+/// Implemented by the marker types in [`permissions`] so a permission can be
+/// required at the type level, e.g. `RequirePermission<permissions::UsersManage>`.
+pub trait RequiredPermission: Send + Sync + 'static {
+    const PERMISSION: Permission;
 }
 
-impl From<String> for RoleName {
-    fn from(value: String) -> Self {
-        match value.as_str() {
-            "Administrator" => Self::Administrator,
-            "RecipeUser" => Self::RecipeUser,
-            _ => Self::Unknown,
+macro_rules! define_permissions {
+    ($($(#[$meta:meta])* $variant:ident => $name:literal),* $(,)?) => {
+        /// An action a role can allow. Permissions are checked in code, so the set is
+        /// fixed here while the assignment of permissions to roles lives in the database.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+        pub enum Permission {
+            $(
+                $(#[$meta])*
+                #[serde(rename = $name)]
+                $variant,
+            )*
+        }
+
+        impl Permission {
+            pub const ALL: &[Self] = &[$(Self::$variant),*];
+
+            pub const fn as_str(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)*
+                }
+            }
+        }
+
+        impl FromStr for Permission {
+            type Err = anyhow::Error;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                match value {
+                    $($name => Ok(Self::$variant),)*
+                    _ => Err(anyhow::anyhow!("unknown permission `{value}`")),
+                }
+            }
         }
+
+        /// Marker types for requiring a permission with an extractor.
+        pub mod permissions {
+            $(
+                $(#[$meta])*
+                pub struct $variant;
+
+                impl super::RequiredPermission for $variant {
+                    const PERMISSION: super::Permission = super::Permission::$variant;
+                }
+            )*
+        }
+    };
+}
+
+define_permissions! {
+    /// View recipes.
+    RecipesRead => "recipes:read",
+    /// Create, edit and delete own recipes.
+    RecipesWrite => "recipes:write",
+    /// Manage user accounts, passwords and role assignments.
+    UsersManage => "users:manage",
+    /// Define roles and the permissions they grant.
+    RolesManage => "roles:manage",
+    /// View active sessions across all users.
+    SessionsManage => "sessions:manage",
+    /// View the audit log.
+    AuditRead => "audit:read",
+    /// Scrape the Prometheus metrics.
+    MetricsRead => "metrics:read",
+}
+
+impl Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
     }
 }
 
-impl From<RoleName> for String {
-    fn from(value: RoleName) -> Self {
-        value.to_string()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_permission_names_round_trip() {
+        for permission in Permission::ALL {
+            assert_eq!(
+                permission.as_str().parse::<Permission>().unwrap(),
+                *permission
+            );
+            assert_eq!(
+                serde_json::to_string(permission).unwrap(),
+                format!("\"{permission}\"")
+            );
+        }
+
+        assert!("recipes:admin".parse::<Permission>().is_err());
     }
 }
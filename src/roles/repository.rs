@@ -1,14 +1,45 @@
 use sqlx::SqlitePool;
 use uuid::Uuid;
 
-use crate::{errors::RepositoryError, roles::Role};
+use crate::{
+    errors::RepositoryError,
+    roles::{ADMINISTRATOR_ROLE, Permission, Role},
+};
 
 #[async_trait::async_trait]
 pub trait IRoleRepository: Send + Sync {
     async fn get_by_id(&self, id: Uuid) -> Result<Role, RepositoryError>;
+    async fn get_by_name(&self, name: &str) -> Result<Option<Role>, RepositoryError>;
     async fn get_all(&self) -> Result<Vec<Role>, RepositoryError>;
     async fn get_by_user_id(&self, id: Uuid) -> Result<Vec<Role>, RepositoryError>;
     async fn get_by_role_ids(&self, roles: Vec<Uuid>) -> Result<Vec<Role>, RepositoryError>;
+
+    /// Create a custom role with the given permissions.
+    async fn create(&self, role: &Role, permissions: &[Permission]) -> Result<(), RepositoryError>;
+
+    /// Rename a role and replace its permissions.
+    async fn update(&self, role: &Role, permissions: &[Permission]) -> Result<(), RepositoryError>;
+
+    /// Delete a role, removing it from every user that had it.
+    async fn delete(&self, id: Uuid) -> Result<(), RepositoryError>;
+
+    /// Get the permissions granted by a role.
+    async fn get_permissions(&self, role_id: Uuid) -> Result<Vec<Permission>, RepositoryError>;
+
+    /// Get every permission a user has through any of their roles.
+    async fn get_permissions_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<Permission>, RepositoryError>;
+
+    /// Give a user a role. Granting a role the user already has does nothing.
+    async fn grant_to_user(&self, user_id: Uuid, role_id: Uuid) -> Result<(), RepositoryError>;
+
+    /// Take a role away from a user. The Administrator role is only removed while
+    /// another enabled user still holds it, so `false` means either the user did
+    /// not have the role or they are the last administrator.
+    async fn revoke_from_user(&self, user_id: Uuid, role_id: Uuid)
+    -> Result<bool, RepositoryError>;
 }
 
 pub struct SqlxRoleRepository {
@@ -21,6 +52,18 @@ impl SqlxRoleRepository {
     }
 }
 
+/// Parse stored permission names, skipping any this version no longer knows about.
+pub fn parse_permissions(names: Vec<String>) -> Vec<Permission> {
+    names
+        .into_iter()
+        .filter_map(|name| {
+            name.parse()
+                .map_err(|err| tracing::warn!(error = %err, "Ignoring stored permission"))
+                .ok()
+        })
+        .collect()
+}
+
 #[async_trait::async_trait]
 impl IRoleRepository for SqlxRoleRepository {
     async fn get_by_id(&self, id: Uuid) -> Result<Role, RepositoryError> {
@@ -29,7 +72,8 @@ impl IRoleRepository for SqlxRoleRepository {
             r#"
             SELECT
                 id AS "id: uuid::Uuid",
-                name
+                name,
+                is_system
             FROM roles
             WHERE id = ?
             "#,
@@ -45,13 +89,33 @@ impl IRoleRepository for SqlxRoleRepository {
         })
     }
 
+    async fn get_by_name(&self, name: &str) -> Result<Option<Role>, RepositoryError> {
+        let role = sqlx::query_as!(
+            Role,
+            r#"
+            SELECT
+                id AS "id: uuid::Uuid",
+                name,
+                is_system
+            FROM roles
+            WHERE LOWER(name) = LOWER(?)
+            "#,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(role)
+    }
+
     async fn get_all(&self) -> Result<Vec<Role>, RepositoryError> {
         let roles = sqlx::query_as!(
             Role,
             r#"
             SELECT
                 id AS "id: uuid::Uuid",
-                name
+                name,
+                is_system
             FROM roles"#,
         )
         .fetch_all(&self.pool)
@@ -66,7 +130,8 @@ impl IRoleRepository for SqlxRoleRepository {
             r#"
             SELECT
                 r.id AS "id: uuid::Uuid",
-                r.name
+                r.name,
+                r.is_system
             FROM roles r
             INNER JOIN user_roles ur
                 ON r.id = ur.role_id
@@ -81,7 +146,8 @@ impl IRoleRepository for SqlxRoleRepository {
     }
 
     async fn get_by_role_ids(&self, roles: Vec<Uuid>) -> Result<Vec<Role>, RepositoryError> {
-        let mut builder = sqlx::QueryBuilder::new("SELECT id, name FROM roles WHERE id IN (");
+        let mut builder =
+            sqlx::QueryBuilder::new("SELECT id, name, is_system FROM roles WHERE id IN (");
 
         for (idx, id) in roles.iter().enumerate() {
             builder.push_bind(id);
@@ -96,4 +162,163 @@ impl IRoleRepository for SqlxRoleRepository {
         let roles = query.fetch_all(&self.pool).await?;
         Ok(roles)
     }
+
+    async fn create(&self, role: &Role, permissions: &[Permission]) -> Result<(), RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            "INSERT INTO roles (id, name, is_system) VALUES (?, ?, ?)",
+            role.id,
+            role.name,
+            role.is_system
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for permission in permissions {
+            let permission = permission.as_str();
+            sqlx::query!(
+                "INSERT OR IGNORE INTO role_permissions (role_id, permission) VALUES (?, ?)",
+                role.id,
+                permission
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn update(&self, role: &Role, permissions: &[Permission]) -> Result<(), RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query!("UPDATE roles SET name = ? WHERE id = ?", role.name, role.id)
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound {
+                entity: "role",
+                property: "id",
+                value: role.id.to_string(),
+            });
+        }
+
+        sqlx::query!("DELETE FROM role_permissions WHERE role_id = ?", role.id)
+            .execute(&mut *tx)
+            .await?;
+
+        for permission in permissions {
+            let permission = permission.as_str();
+            sqlx::query!(
+                "INSERT OR IGNORE INTO role_permissions (role_id, permission) VALUES (?, ?)",
+                role.id,
+                permission
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let result = sqlx::query!("DELETE FROM roles WHERE id = ?", id)
+            .execute(&self.pool)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound {
+                entity: "role",
+                property: "id",
+                value: id.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn get_permissions(&self, role_id: Uuid) -> Result<Vec<Permission>, RepositoryError> {
+        let names = sqlx::query_scalar!(
+            "SELECT permission FROM role_permissions WHERE role_id = ? ORDER BY permission",
+            role_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(parse_permissions(names))
+    }
+
+    async fn get_permissions_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> Result<Vec<Permission>, RepositoryError> {
+        let names = sqlx::query_scalar!(
+            r#"
+            SELECT DISTINCT rp.permission
+            FROM role_permissions rp
+            INNER JOIN user_roles ur
+                ON rp.role_id = ur.role_id
+            WHERE ur.user_id = ?
+            ORDER BY rp.permission
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(parse_permissions(names))
+    }
+
+    async fn grant_to_user(&self, user_id: Uuid, role_id: Uuid) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "INSERT OR IGNORE INTO user_roles (user_id, role_id) VALUES (?, ?)",
+            user_id,
+            role_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn revoke_from_user(
+        &self,
+        user_id: Uuid,
+        role_id: Uuid,
+    ) -> Result<bool, RepositoryError> {
+        // checked in the same statement so two concurrent revocations cannot both
+        // see another administrator and leave none behind
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM user_roles
+            WHERE user_id = ?
+                AND role_id = ?
+                AND (
+                    role_id NOT IN (SELECT id FROM roles WHERE name = ?)
+                    OR EXISTS (
+                        SELECT 1
+                        FROM user_roles other
+                        INNER JOIN users u
+                            ON u.id = other.user_id
+                        WHERE other.role_id = user_roles.role_id
+                            AND other.user_id != user_roles.user_id
+                            AND u.is_disabled = false
+                            AND u.deleted_at IS NULL
+                    )
+                )
+            "#,
+            user_id,
+            role_id,
+            ADMINISTRATOR_ROLE
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
@@ -1,11 +1,19 @@
 use axum::{
     Json, Router,
     extract::{Path, State},
+    http::HeaderValue,
+    response::IntoResponse,
     routing::get,
 };
+use hyper::{HeaderMap, StatusCode, header};
 use uuid::Uuid;
 
-use crate::{errors::ApiError, extractors::AdminUser, roles::Role, services::ServiceContainer};
+use crate::{
+    errors::ApiError,
+    extractors::{RequirePermission, ValidatedJson},
+    roles::{Permission, RoleRequest, RoleResponse, permissions::RolesManage},
+    services::ServiceContainer,
+};
 
 // Clippy lint triggered by utoipa macro expansion, not our code
 #[allow(clippy::needless_for_each)]
@@ -14,14 +22,18 @@ use crate::{errors::ApiError, extractors::AdminUser, roles::Role, services::Serv
     paths(
         crate::roles::get_all,
         crate::roles::get_by_id,
+        crate::roles::get_permissions,
+        crate::roles::create_role,
+        crate::roles::update_role,
+        crate::roles::delete_role,
     ),
     components(
-        schemas(Role)
+        schemas(RoleResponse, RoleRequest, Permission)
     ),
     tags(
         (
-            name = "Sessions", 
-            description = "Session management"
+            name = "Roles",
+            description = "Roles and the permissions they grant"
         )
     )
 )]
@@ -29,8 +41,9 @@ pub struct RolesApiDoc;
 
 pub fn router() -> Router<ServiceContainer> {
     Router::new()
-        .route("/", get(get_all))
-        .route("/{id}", get(get_by_id))
+        .route("/", get(get_all).post(create_role))
+        .route("/permissions", get(get_permissions))
+        .route("/{id}", get(get_by_id).put(update_role).delete(delete_role))
 }
 
 #[utoipa::path(
@@ -39,16 +52,16 @@ pub fn router() -> Router<ServiceContainer> {
     path = "/api/roles",
     tag = "Roles",
     responses(
-        (status = 200, description = "A list of roles", body = Vec<Role>),
+        (status = 200, description = "A list of roles", body = Vec<RoleResponse>),
         (status = 401, description = "Unauthorized - invalid or expired session"),
-        (status = 403, description = "Forbidden - requires an administrator role"),
+        (status = 403, description = "Forbidden - requires the roles:manage permission"),
     ),
-    description = "Retrieves a list of roles."
+    description = "Retrieves a list of roles and the permissions each one grants."
 )]
 pub async fn get_all(
-    _: AdminUser,
+    _: RequirePermission<RolesManage>,
     State(container): State<ServiceContainer>,
-) -> Result<Json<Vec<Role>>, ApiError> {
+) -> Result<Json<Vec<RoleResponse>>, ApiError> {
     let roles = container.role_service().get_all().await?;
     Ok(Json(roles))
 }
@@ -58,18 +71,137 @@ pub async fn get_all(
     summary = "Get Role By ID",
     path = "/api/roles/{id}",
     tag = "Roles",
+    params(
+        ("id" = Uuid, Path, description = "Unique identifier of the role")
+    ),
     responses(
-        (status = 200, description = "The role with the given ID.", body = Vec<Role>),
+        (status = 200, description = "The role with the given ID.", body = RoleResponse),
         (status = 401, description = "Unauthorized - invalid or expired session"),
-        (status = 403, description = "Forbidden - requires an administrator role"),
+        (status = 403, description = "Forbidden - requires the roles:manage permission"),
+        (status = 404, description = "Role not found"),
     ),
-    description = "Retrieves a role by its id."
+    description = "Retrieves a role and its permissions by its id."
 )]
 pub async fn get_by_id(
-    _: AdminUser,
+    _: RequirePermission<RolesManage>,
     State(container): State<ServiceContainer>,
     Path(id): Path<Uuid>,
-) -> Result<Json<Role>, ApiError> {
+) -> Result<Json<RoleResponse>, ApiError> {
     let role = container.role_service().get_by_id(id).await?;
     Ok(Json(role))
 }
+
+#[utoipa::path(
+    get,
+    summary = "Get All Permissions",
+    path = "/api/roles/permissions",
+    tag = "Roles",
+    responses(
+        (status = 200, description = "Every permission a role can grant", body = Vec<Permission>),
+        (status = 401, description = "Unauthorized - invalid or expired session"),
+        (status = 403, description = "Forbidden - requires the roles:manage permission"),
+    ),
+    description = "Lists every permission that can be granted to a custom role."
+)]
+pub async fn get_permissions(_: RequirePermission<RolesManage>) -> Json<&'static [Permission]> {
+    Json(Permission::ALL)
+}
+
+#[utoipa::path(
+    post,
+    summary = "Create Role",
+    path = "/api/roles",
+    tag = "Roles",
+    request_body = RoleRequest,
+    responses(
+        (status = 201, description = "Role created successfully", headers(
+            ("Location" = String, description = "URI of the newly created role")
+        )),
+        (status = 400, description = "Invalid request body"),
+        (status = 409, description = "A role with the same name already exists"),
+        (status = 401, description = "Unauthorized - invalid or expired session"),
+        (status = 403, description = "Forbidden - requires the roles:manage permission, and \
+                                      every permission given to the role"),
+    ),
+    description = "Creates a custom role granting the given permissions, which must all be \
+        permissions the caller holds. \
+        Returns a 201 status code with a Location header pointing to the new role."
+)]
+pub async fn create_role(
+    admin: RequirePermission<RolesManage>,
+    State(container): State<ServiceContainer>,
+    ValidatedJson(req): ValidatedJson<RoleRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let role_id = container
+        .role_service()
+        .create(req, &admin.user.permissions)
+        .await?;
+    let location_str = format!("/roles/{role_id}");
+    let location = HeaderValue::from_str(&location_str).map_err(|err| anyhow::anyhow!(err))?;
+    let mut headers = HeaderMap::new();
+    headers.insert(header::LOCATION, location);
+    Ok((StatusCode::CREATED, headers))
+}
+
+#[utoipa::path(
+    put,
+    summary = "Update Role",
+    path = "/api/roles/{id}",
+    tag = "Roles",
+    params(
+        ("id" = Uuid, Path, description = "Unique identifier of the role to update")
+    ),
+    request_body = RoleRequest,
+    responses(
+        (status = 204, description = "Role updated successfully"),
+        (status = 400, description = "Invalid request body or built-in role"),
+        (status = 409, description = "A role with the same name already exists"),
+        (status = 401, description = "Unauthorized - invalid or expired session"),
+        (status = 403, description = "Forbidden - requires the roles:manage permission, and \
+                                      every permission given to the role"),
+        (status = 404, description = "Role not found"),
+    ),
+    description = "Renames a custom role and replaces the permissions it grants, which must \
+        all be permissions the caller holds. \
+        Users with the role are affected on their next request. \
+        Built-in roles cannot be changed."
+)]
+pub async fn update_role(
+    admin: RequirePermission<RolesManage>,
+    Path(id): Path<Uuid>,
+    State(container): State<ServiceContainer>,
+    ValidatedJson(req): ValidatedJson<RoleRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    container
+        .role_service()
+        .update(id, req, &admin.user.permissions)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    summary = "Delete Role",
+    path = "/api/roles/{id}",
+    tag = "Roles",
+    params(
+        ("id" = Uuid, Path, description = "Unique identifier of the role to delete")
+    ),
+    responses(
+        (status = 204, description = "Role deleted successfully"),
+        (status = 400, description = "Built-in roles cannot be deleted"),
+        (status = 401, description = "Unauthorized - invalid or expired session"),
+        (status = 403, description = "Forbidden - requires the roles:manage permission"),
+        (status = 404, description = "Role not found"),
+    ),
+    description = "Deletes a custom role and removes it from every user that had it. \
+        Built-in roles cannot be deleted."
+)]
+pub async fn delete_role(
+    _: RequirePermission<RolesManage>,
+    Path(id): Path<Uuid>,
+    State(container): State<ServiceContainer>,
+) -> Result<impl IntoResponse, ApiError> {
+    container.role_service().delete(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
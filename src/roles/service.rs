@@ -1,40 +1,282 @@
 use std::sync::Arc;
 
+use serde_json::json;
+
 use crate::{
+    audit::{AuditAction, AuditEvent, IAuditRepository},
     errors::ServiceError,
-    roles::{IRoleRepository, Role},
+    roles::{IRoleRepository, Permission, Role, RoleRequest, RoleResponse},
 };
 use uuid::Uuid;
 
 #[async_trait::async_trait]
 pub trait IRoleService: Send + Sync {
     /// Get all roles
-    async fn get_all(&self) -> Result<Vec<Role>, ServiceError>;
+    async fn get_all(&self) -> Result<Vec<RoleResponse>, ServiceError>;
 
     /// Get a role by its id.
-    async fn get_by_id(&self, id: Uuid) -> Result<Role, ServiceError>;
+    async fn get_by_id(&self, id: Uuid) -> Result<RoleResponse, ServiceError>;
+
+    /// Create a custom role. The role may only carry permissions held by the
+    /// administrator, given as `admin_permissions`.
+    async fn create(
+        &self,
+        request: RoleRequest,
+        admin_permissions: &[Permission],
+    ) -> Result<Uuid, ServiceError>;
+
+    /// Rename a custom role and replace its permissions, which may only be ones held
+    /// by the administrator.
+    async fn update(
+        &self,
+        id: Uuid,
+        request: RoleRequest,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError>;
+
+    /// Delete a custom role.
+    async fn delete(&self, id: Uuid) -> Result<(), ServiceError>;
 }
 
 #[derive(Clone)]
 pub struct RoleService {
     roles: Arc<dyn IRoleRepository>,
+    audit: Arc<dyn IAuditRepository>,
 }
 
 impl RoleService {
-    pub fn new(role_repo: Arc<dyn IRoleRepository>) -> Self {
-        Self { roles: role_repo }
+    pub fn new(role_repo: Arc<dyn IRoleRepository>, audit_repo: Arc<dyn IAuditRepository>) -> Self {
+        Self {
+            roles: role_repo,
+            audit: audit_repo,
+        }
+    }
+
+    async fn with_permissions(&self, role: Role) -> Result<RoleResponse, ServiceError> {
+        let permissions = self.roles.get_permissions(role.id).await?;
+
+        Ok(RoleResponse {
+            id: role.id,
+            name: role.name,
+            is_system: role.is_system,
+            permissions,
+        })
     }
+
+    /// Make sure no other role already uses the name.
+    async fn ensure_name_available(
+        &self,
+        name: &str,
+        id: Option<Uuid>,
+    ) -> Result<(), ServiceError> {
+        match self.roles.get_by_name(name).await? {
+            Some(existing) if Some(existing.id) != id => Err(ServiceError::Conflict {
+                entity: "role",
+                fields: vec!["name".to_string()],
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Refuse to hand out permissions the acting administrator does not hold, or to act
+/// on an account holding them, so that managing roles, role assignments or accounts
+/// cannot be used to gain more access.
+pub fn ensure_can_delegate(
+    held: &[Permission],
+    granted: &[Permission],
+) -> Result<(), ServiceError> {
+    let missing: Vec<&str> = granted
+        .iter()
+        .filter(|permission| !held.contains(permission))
+        .map(|permission| permission.as_str())
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    Err(ServiceError::Forbidden(format!(
+        "requires permissions you do not hold: {}",
+        missing.join(", ")
+    )))
+}
+
+fn ensure_editable(role: &Role) -> Result<(), ServiceError> {
+    if role.is_system {
+        return Err(ServiceError::BadRequest(format!(
+            "the built-in role `{}` cannot be changed",
+            role.name
+        )));
+    }
+
+    Ok(())
 }
 
 #[async_trait::async_trait]
 impl IRoleService for RoleService {
-    async fn get_all(&self) -> Result<Vec<Role>, ServiceError> {
+    async fn get_all(&self) -> Result<Vec<RoleResponse>, ServiceError> {
         let roles = self.roles.get_all().await?;
-        Ok(roles)
+
+        let mut responses = Vec::with_capacity(roles.len());
+        for role in roles {
+            responses.push(self.with_permissions(role).await?);
+        }
+
+        Ok(responses)
     }
 
-    async fn get_by_id(&self, id: Uuid) -> Result<Role, ServiceError> {
+    async fn get_by_id(&self, id: Uuid) -> Result<RoleResponse, ServiceError> {
         let role = self.roles.get_by_id(id).await?;
-        Ok(role)
+        self.with_permissions(role).await
+    }
+
+    async fn create(
+        &self,
+        request: RoleRequest,
+        admin_permissions: &[Permission],
+    ) -> Result<Uuid, ServiceError> {
+        ensure_can_delegate(admin_permissions, &request.permissions)?;
+        let name = request.name.trim().to_string();
+        self.ensure_name_available(&name, None).await?;
+
+        let role = Role {
+            id: Uuid::now_v7(),
+            name,
+            is_system: false,
+        };
+        self.roles.create(&role, &request.permissions).await?;
+        self.audit
+            .record(
+                &AuditEvent::success(AuditAction::RoleCreated)
+                    .target("role", role.id)
+                    .details(json!({ "name": role.name, "permissions": request.permissions })),
+            )
+            .await;
+
+        tracing::info!(role_id = %role.id, role = %role.name, "Role created");
+
+        Ok(role.id)
+    }
+
+    async fn update(
+        &self,
+        id: Uuid,
+        request: RoleRequest,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError> {
+        let mut role = self.roles.get_by_id(id).await?;
+        ensure_editable(&role)?;
+        ensure_can_delegate(admin_permissions, &request.permissions)?;
+
+        role.name = request.name.trim().to_string();
+        self.ensure_name_available(&role.name, Some(id)).await?;
+        self.roles.update(&role, &request.permissions).await?;
+        self.audit
+            .record(
+                &AuditEvent::success(AuditAction::RoleUpdated)
+                    .target("role", role.id)
+                    .details(json!({ "name": role.name, "permissions": request.permissions })),
+            )
+            .await;
+
+        tracing::info!(role_id = %role.id, role = %role.name, "Role updated");
+
+        Ok(())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<(), ServiceError> {
+        let role = self.roles.get_by_id(id).await?;
+        ensure_editable(&role)?;
+
+        self.roles.delete(id).await?;
+        self.audit
+            .record(
+                &AuditEvent::success(AuditAction::RoleDeleted)
+                    .target("role", id)
+                    .details(json!({ "name": role.name })),
+            )
+            .await;
+
+        tracing::info!(role_id = %role.id, role = %role.name, "Role deleted");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, database::Database, services::ServiceContainer};
+
+    const HELD: &[Permission] = &[Permission::RolesManage, Permission::RecipesRead];
+
+    fn request(permissions: &[Permission]) -> RoleRequest {
+        RoleRequest {
+            name: "Reviewer".to_string(),
+            permissions: permissions.to_vec(),
+        }
+    }
+
+    async fn roles() -> Arc<dyn IRoleService> {
+        let db = Database::in_memory().await;
+        ServiceContainer::new(db.pool, Config::for_tests()).role_service()
+    }
+
+    #[test]
+    fn test_ensure_can_delegate_lists_missing_permissions() {
+        assert!(ensure_can_delegate(HELD, &[Permission::RecipesRead]).is_ok());
+        assert!(ensure_can_delegate(HELD, &[]).is_ok());
+
+        let err = ensure_can_delegate(HELD, &[Permission::RecipesRead, Permission::UsersManage])
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::Forbidden(msg) if msg.ends_with(": users:manage")));
+    }
+
+    #[tokio::test]
+    async fn test_create_refuses_permissions_the_caller_lacks() {
+        let roles = roles().await;
+
+        let err = roles
+            .create(request(&[Permission::UsersManage]), HELD)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::Forbidden(_)));
+        assert!(
+            roles
+                .get_all()
+                .await
+                .unwrap()
+                .iter()
+                .all(|r| r.name != "Reviewer")
+        );
+
+        let id = roles
+            .create(request(&[Permission::RecipesRead]), HELD)
+            .await
+            .unwrap();
+        assert_eq!(
+            roles.get_by_id(id).await.unwrap().permissions,
+            vec![Permission::RecipesRead]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_update_refuses_permissions_the_caller_lacks() {
+        let roles = roles().await;
+        let id = roles
+            .create(request(&[Permission::RecipesRead]), HELD)
+            .await
+            .unwrap();
+
+        let err = roles
+            .update(id, request(&[Permission::AuditRead]), HELD)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::Forbidden(_)));
+        assert_eq!(
+            roles.get_by_id(id).await.unwrap().permissions,
+            vec![Permission::RecipesRead]
+        );
     }
 }
@@ -1,8 +1,23 @@
 use crate::{
+    api_tokens::{ApiTokenService, IApiTokenRepository, IApiTokenService, SqlxApiTokenRepository},
+    applications::{
+        ApplicationService, IApplicationRepository, IApplicationService, SqlxApplicationRepository,
+    },
+    audit::{AuditService, IAuditRepository, IAuditService, SqlxAuditRepository},
     authentication::{
         AuthenticationService, IAuthenticationRepository, IAuthenticationService,
         SqlxAuthenticationRepository,
     },
+    background_jobs::BackgroundJobs,
+    config::Config,
+    data_exports::{DataExportService, IDataExportService},
+    health::{HealthService, IHealthRepository, IHealthService, SqlxHealthRepository},
+    metrics::Metrics,
+    password_resets::{
+        IPasswordResetRepository, IPasswordResetService, PasswordResetService,
+        SqlxPasswordResetRepository,
+    },
+    rate_limit::{ApiRateLimiter, LoginRateLimiter},
     recipes::{
         IIngredientRepository, IInstructionRepository, IRecipeRepository, IRecipeService,
         RecipeService, SqlxIngredientRepository, SqlxInstructionRepository, SqlxRecipeRepository,
@@ -17,6 +32,9 @@ use std::sync::Arc;
 /// A container holding all shared repositories and services for the app
 #[derive(Clone)]
 pub struct ServiceContainer {
+    config: Arc<Config>,
+    pool: SqlitePool,
+
     // Repositories (shared across services)
     auth_repo: Arc<dyn IAuthenticationRepository>,
     user_repo: Arc<dyn IUserRepository>,
@@ -25,6 +43,11 @@ pub struct ServiceContainer {
     recipe_repo: Arc<dyn IRecipeRepository>,
     ingredient_repo: Arc<dyn IIngredientRepository>,
     instruction_repo: Arc<dyn IInstructionRepository>,
+    password_reset_repo: Arc<dyn IPasswordResetRepository>,
+    application_repo: Arc<dyn IApplicationRepository>,
+    audit_repo: Arc<dyn IAuditRepository>,
+    api_token_repo: Arc<dyn IApiTokenRepository>,
+    health_repo: Arc<dyn IHealthRepository>,
 
     // Services
     recipes: Arc<dyn IRecipeService>,
@@ -32,10 +55,28 @@ pub struct ServiceContainer {
     sessions: Arc<dyn ISessionService>,
     roles: Arc<dyn IRoleService>,
     auth: Arc<dyn IAuthenticationService>,
+    password_resets: Arc<dyn IPasswordResetService>,
+    applications: Arc<dyn IApplicationService>,
+    audit: Arc<dyn IAuditService>,
+    data_exports: Arc<dyn IDataExportService>,
+    api_tokens: Arc<dyn IApiTokenService>,
+    health: Arc<dyn IHealthService>,
+
+    // Rate limiters (in-process state)
+    login_limiter: Arc<LoginRateLimiter>,
+    api_limiter: Arc<ApiRateLimiter>,
+
+    // Metrics and background job handles (in-process state)
+    metrics: Arc<Metrics>,
+    background_jobs: Arc<BackgroundJobs>,
 }
 
 impl ServiceContainer {
-    pub fn new(pool: SqlitePool) -> Self {
+    // Wiring every repository and service in one place reads better than splitting it up.
+    #[allow(clippy::too_many_lines)]
+    pub fn new(pool: SqlitePool, config: Config) -> Self {
+        let config = Arc::new(config);
+
         // Create all repositories once
         let auth_repo = Arc::new(SqlxAuthenticationRepository::new(pool.clone()));
         let user_repo = Arc::new(SqlxUserRepository::new(pool.clone()));
@@ -43,29 +84,94 @@ impl ServiceContainer {
         let session_repo = Arc::new(SqlxSessionRepository::new(pool.clone()));
         let recipe_repo = Arc::new(SqlxRecipeRepository::new(pool.clone()));
         let ingredient_repo = Arc::new(SqlxIngredientRepository::new(pool.clone()));
-        let instruction_repo = Arc::new(SqlxInstructionRepository::new(pool));
+        let instruction_repo = Arc::new(SqlxInstructionRepository::new(pool.clone()));
+        let password_reset_repo = Arc::new(SqlxPasswordResetRepository::new(pool.clone()));
+        let application_repo = Arc::new(SqlxApplicationRepository::new(pool.clone()));
+        let audit_repo = Arc::new(SqlxAuditRepository::new(pool.clone()));
+        let api_token_repo = Arc::new(SqlxApiTokenRepository::new(pool.clone()));
+        let health_repo = Arc::new(SqlxHealthRepository::new(pool.clone()));
 
         // Create services using shared repositories
         let recipes = Arc::new(RecipeService::new(
             recipe_repo.clone(),
             ingredient_repo.clone(),
             instruction_repo.clone(),
+            audit_repo.clone(),
         ));
 
-        let users = Arc::new(UserService::new(user_repo.clone(), role_repo.clone()));
+        let users = Arc::new(UserService::new(
+            user_repo.clone(),
+            role_repo.clone(),
+            session_repo.clone(),
+            application_repo.clone(),
+            api_token_repo.clone(),
+            audit_repo.clone(),
+            config.clone(),
+        ));
 
         let sessions = Arc::new(SessionService::new(session_repo.clone()));
 
-        let roles = Arc::new(RoleService::new(role_repo.clone()));
+        let roles = Arc::new(RoleService::new(role_repo.clone(), audit_repo.clone()));
 
         let auth = Arc::new(AuthenticationService::new(
             auth_repo.clone(),
             user_repo.clone(),
             role_repo.clone(),
             session_repo.clone(),
+            application_repo.clone(),
+            audit_repo.clone(),
+            config.clone(),
         ));
 
+        let password_resets = Arc::new(PasswordResetService::new(
+            password_reset_repo.clone(),
+            user_repo.clone(),
+            role_repo.clone(),
+            session_repo.clone(),
+            api_token_repo.clone(),
+            audit_repo.clone(),
+            config.clone(),
+        ));
+
+        let applications = Arc::new(ApplicationService::new(
+            application_repo.clone(),
+            user_repo.clone(),
+            role_repo.clone(),
+            audit_repo.clone(),
+        ));
+
+        let audit = Arc::new(AuditService::new(audit_repo.clone()));
+
+        let data_exports = Arc::new(DataExportService::new(
+            user_repo.clone(),
+            role_repo.clone(),
+            session_repo.clone(),
+            application_repo.clone(),
+            recipes.clone(),
+            audit_repo.clone(),
+        ));
+
+        let api_tokens = Arc::new(ApiTokenService::new(
+            api_token_repo.clone(),
+            user_repo.clone(),
+            role_repo.clone(),
+            application_repo.clone(),
+            audit_repo.clone(),
+            config.clone(),
+        ));
+
+        let background_jobs = Arc::new(BackgroundJobs::new());
+        let health = Arc::new(HealthService::new(
+            health_repo.clone(),
+            background_jobs.clone(),
+        ));
+
+        let login_limiter = Arc::new(LoginRateLimiter::from_config(&config.rate_limits));
+        let api_limiter = Arc::new(ApiRateLimiter::from_config(&config.rate_limits));
+
         Self {
+            config,
+            pool,
             auth_repo,
             user_repo,
             role_repo,
@@ -73,14 +179,37 @@ impl ServiceContainer {
             recipe_repo,
             ingredient_repo,
             instruction_repo,
+            password_reset_repo,
+            application_repo,
+            audit_repo,
+            api_token_repo,
+            health_repo,
             recipes,
             users,
             sessions,
             roles,
             auth,
+            password_resets,
+            applications,
+            audit,
+            data_exports,
+            api_tokens,
+            health,
+            login_limiter,
+            api_limiter,
+            metrics: Arc::new(Metrics::new()),
+            background_jobs,
         }
     }
 
+    pub fn config(&self) -> Arc<Config> {
+        self.config.clone()
+    }
+
+    pub fn pool(&self) -> SqlitePool {
+        self.pool.clone()
+    }
+
     // Repository accessors
     #[allow(unused)]
     pub fn auth_repo(&self) -> Arc<dyn IAuthenticationRepository> {
@@ -117,6 +246,31 @@ impl ServiceContainer {
         self.instruction_repo.clone()
     }
 
+    #[allow(unused)]
+    pub fn password_reset_repo(&self) -> Arc<dyn IPasswordResetRepository> {
+        self.password_reset_repo.clone()
+    }
+
+    #[allow(unused)]
+    pub fn application_repo(&self) -> Arc<dyn IApplicationRepository> {
+        self.application_repo.clone()
+    }
+
+    #[allow(unused)]
+    pub fn audit_repo(&self) -> Arc<dyn IAuditRepository> {
+        self.audit_repo.clone()
+    }
+
+    #[allow(unused)]
+    pub fn api_token_repo(&self) -> Arc<dyn IApiTokenRepository> {
+        self.api_token_repo.clone()
+    }
+
+    #[allow(unused)]
+    pub fn health_repo(&self) -> Arc<dyn IHealthRepository> {
+        self.health_repo.clone()
+    }
+
     // Service accessors
     #[allow(unused)]
     pub fn recipe_service(&self) -> Arc<dyn IRecipeService> {
@@ -142,4 +296,52 @@ impl ServiceContainer {
     pub fn auth_service(&self) -> Arc<dyn IAuthenticationService> {
         self.auth.clone()
     }
+
+    #[allow(unused)]
+    pub fn password_reset_service(&self) -> Arc<dyn IPasswordResetService> {
+        self.password_resets.clone()
+    }
+
+    #[allow(unused)]
+    pub fn application_service(&self) -> Arc<dyn IApplicationService> {
+        self.applications.clone()
+    }
+
+    #[allow(unused)]
+    pub fn audit_service(&self) -> Arc<dyn IAuditService> {
+        self.audit.clone()
+    }
+
+    #[allow(unused)]
+    pub fn data_export_service(&self) -> Arc<dyn IDataExportService> {
+        self.data_exports.clone()
+    }
+
+    #[allow(unused)]
+    pub fn api_token_service(&self) -> Arc<dyn IApiTokenService> {
+        self.api_tokens.clone()
+    }
+
+    #[allow(unused)]
+    pub fn health_service(&self) -> Arc<dyn IHealthService> {
+        self.health.clone()
+    }
+
+    // Rate limiter accessors
+    pub fn login_limiter(&self) -> Arc<LoginRateLimiter> {
+        self.login_limiter.clone()
+    }
+
+    pub fn api_limiter(&self) -> Arc<ApiRateLimiter> {
+        self.api_limiter.clone()
+    }
+
+    // Metrics and background job accessors
+    pub fn metrics(&self) -> Arc<Metrics> {
+        self.metrics.clone()
+    }
+
+    pub fn background_jobs(&self) -> Arc<BackgroundJobs> {
+        self.background_jobs.clone()
+    }
 }
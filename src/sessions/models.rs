@@ -5,27 +5,67 @@ use uuid::Uuid;
 
 use crate::users::UserBaseResponse;
 
+/// How often the raw token behind a session cookie is replaced with a new secret.
+pub const TOKEN_ROTATION_INTERVAL: Duration = Duration::minutes(15);
+
+/// How long the previous token stays valid after a rotation so that requests
+/// already in flight with the old cookie are not rejected.
+pub const ROTATED_TOKEN_GRACE_PERIOD: Duration = Duration::seconds(30);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
     pub id: Uuid,
     pub user_id: Uuid,
     pub token: String,
+    /// The id of the HMAC key the token hashes were made with.
+    pub token_key_id: String,
     pub expires_at: OffsetDateTime,
+    /// The hash of the token that was replaced by the most recent rotation.
+    pub previous_token: Option<String>,
+    /// When the token was last rotated or first issued.
+    pub rotated_at: Option<OffsetDateTime>,
+    /// Set when the user logged in with an expired password. Such a session may
+    /// only be used to change the password.
+    pub password_change_required: bool,
 }
 
 impl Session {
-    pub fn new(id: Uuid, user_id: Uuid, token: String) -> Self {
-        Self::new_with_duration(id, user_id, Duration::hours(2), token)
+    pub fn new(id: Uuid, user_id: Uuid, token: String, token_key_id: String) -> Self {
+        Self::new_with_duration(id, user_id, Duration::hours(2), token, token_key_id)
     }
 
-    pub fn new_with_duration(id: Uuid, user_id: Uuid, duration: Duration, token: String) -> Self {
+    pub fn new_with_duration(
+        id: Uuid,
+        user_id: Uuid,
+        duration: Duration,
+        token: String,
+        token_key_id: String,
+    ) -> Self {
+        let now = OffsetDateTime::now_utc();
+
         Self {
             id,
             user_id,
             token,
-            expires_at: OffsetDateTime::now_utc().saturating_add(duration),
+            token_key_id,
+            expires_at: now.saturating_add(duration),
+            previous_token: None,
+            rotated_at: Some(now),
+            password_change_required: false,
         }
     }
+
+    /// Whether the current token has been in use long enough that it should be replaced.
+    pub fn is_rotation_due(&self, now: OffsetDateTime) -> bool {
+        self.rotated_at
+            .is_none_or(|rotated_at| now - rotated_at >= TOKEN_ROTATION_INTERVAL)
+    }
+
+    /// Whether the previous token may still be presented without being treated as a replay.
+    pub fn is_within_grace_period(&self, now: OffsetDateTime) -> bool {
+        self.rotated_at
+            .is_some_and(|rotated_at| now - rotated_at <= ROTATED_TOKEN_GRACE_PERIOD)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -34,3 +74,47 @@ pub struct SessionSummary {
     pub user: UserBaseResponse,
     pub active_sessions: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session_rotated_at(rotated_at: Option<OffsetDateTime>) -> Session {
+        let mut session = Session::new(Uuid::now_v7(), Uuid::now_v7(), "hash".into(), "key".into());
+        session.rotated_at = rotated_at;
+        session
+    }
+
+    #[test]
+    fn new_session_does_not_need_rotation() {
+        let session = session_rotated_at(Some(OffsetDateTime::now_utc()));
+        assert!(!session.is_rotation_due(OffsetDateTime::now_utc()));
+    }
+
+    #[test]
+    fn session_never_rotated_needs_rotation() {
+        let session = session_rotated_at(None);
+        assert!(session.is_rotation_due(OffsetDateTime::now_utc()));
+        assert!(!session.is_within_grace_period(OffsetDateTime::now_utc()));
+    }
+
+    #[test]
+    fn session_past_rotation_interval_needs_rotation() {
+        let now = OffsetDateTime::now_utc();
+        let session = session_rotated_at(Some(now - TOKEN_ROTATION_INTERVAL));
+        assert!(session.is_rotation_due(now));
+    }
+
+    #[test]
+    fn previous_token_only_accepted_inside_grace_period() {
+        let now = OffsetDateTime::now_utc();
+
+        let recent = session_rotated_at(Some(now - Duration::seconds(5)));
+        assert!(recent.is_within_grace_period(now));
+
+        let stale = session_rotated_at(Some(
+            now - ROTATED_TOKEN_GRACE_PERIOD - Duration::seconds(1),
+        ));
+        assert!(!stale.is_within_grace_period(now));
+    }
+}
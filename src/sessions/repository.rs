@@ -13,6 +13,19 @@ pub trait ISessionRepository: Send + Sync {
     /// Update the expiration of a session.
     async fn update(&self, session: &Session) -> Result<(), RepositoryError>;
 
+    /// Replace the token of a session with a newly rotated one, keeping the old hash
+    /// as the previous token. The update only applies when the stored token still
+    /// matches `expected_token`, so `false` means a concurrent request already
+    /// rotated it.
+    async fn rotate_token(
+        &self,
+        session: &Session,
+        expected_token: &str,
+    ) -> Result<bool, RepositoryError>;
+
+    /// Lift the password change restriction from all sessions of a user.
+    async fn clear_password_change_required(&self, user_id: Uuid) -> Result<(), RepositoryError>;
+
     /// Delete a session by its id.
     async fn delete(&self, id: Uuid) -> Result<(), RepositoryError>;
 
@@ -22,12 +35,26 @@ pub trait ISessionRepository: Send + Sync {
     /// Delete all sessions for a given user.
     async fn delete_all_for_user(&self, user_id: Uuid) -> Result<(), RepositoryError>;
 
+    /// Delete all sessions for a given user except one, e.g. the session that
+    /// made the request.
+    async fn delete_all_for_user_except(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+    ) -> Result<(), RepositoryError>;
+
     /// Get a session and the associated user for auth.
     async fn get_by_id(&self, session_id: Uuid) -> Result<Session, RepositoryError>;
 
+    /// Get every session of a user that has not expired yet.
+    async fn get_active_for_user(&self, user_id: Uuid) -> Result<Vec<Session>, RepositoryError>;
+
     /// Find all active sessions and get the details for each user and a count of active
     /// sessions.
     async fn get_active_summary(&self) -> Result<Vec<SessionSummary>, RepositoryError>;
+
+    /// Count the sessions that have not expired yet, across all users.
+    async fn count_active(&self) -> Result<i64, RepositoryError>;
 }
 
 pub struct SqlxSessionRepository {
@@ -57,6 +84,45 @@ impl ISessionRepository for SqlxSessionRepository {
         Ok(())
     }
 
+    async fn rotate_token(
+        &self,
+        session: &Session,
+        expected_token: &str,
+    ) -> Result<bool, RepositoryError> {
+        let result = sqlx::query!(
+            r#"UPDATE sessions
+            SET token = ?,
+            token_key_id = ?,
+            previous_token = ?,
+            rotated_at = ?,
+            expires_at = ?
+            WHERE id = ? AND token = ?
+        "#,
+            session.token,
+            session.token_key_id,
+            session.previous_token,
+            session.rotated_at,
+            session.expires_at,
+            session.id,
+            expected_token
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn clear_password_change_required(&self, user_id: Uuid) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "UPDATE sessions SET password_change_required = false WHERE user_id = ?",
+            user_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     async fn delete(&self, id: Uuid) -> Result<(), RepositoryError> {
         sqlx::query!("DELETE FROM sessions WHERE id = ?", id)
             .execute(&self.pool)
@@ -81,6 +147,22 @@ impl ISessionRepository for SqlxSessionRepository {
         Ok(())
     }
 
+    async fn delete_all_for_user_except(
+        &self,
+        user_id: Uuid,
+        session_id: Uuid,
+    ) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "DELETE FROM sessions WHERE user_id = ? AND id != ?",
+            user_id,
+            session_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     async fn get_by_id(&self, session_id: Uuid) -> Result<Session, RepositoryError> {
         let session = sqlx::query_as!(
             Session,
@@ -89,7 +171,11 @@ impl ISessionRepository for SqlxSessionRepository {
                 id AS "id: uuid::Uuid",
                 user_id AS "user_id: uuid::Uuid",
                 token,
-                expires_at
+                token_key_id,
+                expires_at,
+                previous_token,
+                rotated_at,
+                password_change_required
             FROM sessions
             WHERE id = ?
             "#,
@@ -106,6 +192,42 @@ impl ISessionRepository for SqlxSessionRepository {
         Ok(session)
     }
 
+    async fn get_active_for_user(&self, user_id: Uuid) -> Result<Vec<Session>, RepositoryError> {
+        let sessions = sqlx::query_as!(
+            Session,
+            r#"
+            SELECT
+                id AS "id: uuid::Uuid",
+                user_id AS "user_id: uuid::Uuid",
+                token,
+                token_key_id,
+                expires_at,
+                previous_token,
+                rotated_at,
+                password_change_required
+            FROM sessions
+            WHERE user_id = ?
+                AND expires_at > CURRENT_TIMESTAMP
+            ORDER BY expires_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(sessions)
+    }
+
+    async fn count_active(&self) -> Result<i64, RepositoryError> {
+        let count: i64 = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM sessions WHERE expires_at > CURRENT_TIMESTAMP"
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
     async fn get_active_summary(&self) -> Result<Vec<SessionSummary>, RepositoryError> {
         let rows = sqlx::query!(
             r#"
@@ -117,7 +239,10 @@ impl ISessionRepository for SqlxSessionRepository {
                 u.email,
                 u.username,
                 u.last_login,
-                u.is_disabled
+                u.created_at,
+                u.is_disabled,
+                u.disabled_reason,
+                u.disabled_at
             FROM users u
             INNER JOIN sessions s
                 ON s.user_id = u.id
@@ -139,7 +264,12 @@ impl ISessionRepository for SqlxSessionRepository {
                     email: row.email,
                     username: row.username,
                     last_login: row.last_login,
+                    created_at: row.created_at,
                     is_disabled: row.is_disabled,
+                    disabled_reason: row.disabled_reason,
+                    disabled_at: row.disabled_at,
+                    locked_until: None,
+                    deleted_at: None,
                 };
 
                 SessionSummary {
@@ -1,7 +1,8 @@
 use axum::{Json, Router, extract::State, routing::get};
 
 use crate::{
-    errors::ApiError, extractors::AdminUser, services::ServiceContainer, sessions::SessionSummary,
+    errors::ApiError, extractors::RequirePermission, roles::permissions::SessionsManage,
+    services::ServiceContainer, sessions::SessionSummary,
 };
 
 pub fn router() -> Router<ServiceContainer> {
@@ -35,12 +36,12 @@ pub struct SessionApiDoc;
     responses(
         (status = 200, description = "A list of session summary details", body = Vec<SessionSummary>),
         (status = 401, description = "Unauthorized - invalid or expired session"),
-        (status = 403, description = "Forbidden - requires an administrator role"),
+        (status = 403, description = "Forbidden - requires the sessions:manage permission"),
     ),
     description = "Retrieves a list of users with active sessions and the the count."
 )]
 pub async fn session_summary(
-    _: AdminUser,
+    _: RequirePermission<SessionsManage>,
     State(container): State<ServiceContainer>,
 ) -> Result<Json<Vec<SessionSummary>>, ApiError> {
     let summaries = container.session_service().get_session_summaries().await?;
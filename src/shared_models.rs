@@ -1,4 +1,7 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::errors::{RepositoryError, ServiceError};
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -9,3 +12,67 @@ pub struct PaginatedResponse<T> {
     pub total: i64,
     pub total_pages: i64,
 }
+
+impl<T> PaginatedResponse<T> {
+    /// A page of `data` out of `total` items, which must come from a valid page size.
+    pub const fn new(data: Vec<T>, page: i64, page_size: i64, total: i64) -> Self {
+        Self {
+            data,
+            page,
+            page_size,
+            total,
+            total_pages: (total + page_size - 1) / page_size,
+        }
+    }
+}
+
+/// Refuse page sizes outside `1..=max`.
+pub fn check_page_size(page_size: i64, max: i64) -> Result<(), ServiceError> {
+    if page_size <= 0 || page_size > max {
+        return Err(ServiceError::BadRequest(format!(
+            "page size must be between 1 and {max}"
+        )));
+    }
+    Ok(())
+}
+
+/// Number of rows to skip to reach the 1-based `page`.
+pub fn page_offset(page: i64, page_size: i64) -> Result<i64, RepositoryError> {
+    if page < 1 {
+        return Err(RepositoryError::ArgumentOutOfRange {
+            field: "page",
+            value: format!("page={page}"),
+        });
+    }
+
+    if page_size < 1 {
+        return Err(RepositoryError::ArgumentOutOfRange {
+            field: "page_size",
+            value: format!("page_size={page_size}"),
+        });
+    }
+
+    (page - 1)
+        .checked_mul(page_size)
+        .ok_or_else(|| RepositoryError::ArgumentOutOfRange {
+            field: "offset",
+            value: format!("page={page}; page_size={page_size}"),
+        })
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    pub const fn as_sql(self) -> &'static str {
+        match self {
+            Self::Asc => "ASC",
+            Self::Desc => "DESC",
+        }
+    }
+}
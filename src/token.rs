@@ -1,11 +1,118 @@
 use crate::errors::ApiError;
+use anyhow::{anyhow, bail};
 use argon2::password_hash::rand_core::{OsRng, RngCore};
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 type HmacSha256 = Hmac<Sha256>;
 
+/// The key id given to the legacy single `SESSION_HMAC_KEY` setting.
+pub const LEGACY_HMAC_KEY_ID: &str = "default";
+
+/// The minimum accepted length of an HMAC key, in bytes.
+const MIN_HMAC_KEY_LEN: usize = 32;
+
+/// A set of HMAC keys used to hash session tokens, identified by key id.
+///
+/// New tokens are always hashed with the active key. Older keys stay in the
+/// keyring only so that existing sessions can still be verified until they are
+/// rehashed with the active key, after which the old key can be retired.
+#[derive(Debug, Clone)]
+pub struct HmacKeyring {
+    active_key_id: String,
+    active_key: Vec<u8>,
+    keys: HashMap<String, Vec<u8>>,
+}
+
+impl HmacKeyring {
+    /// Parse a comma separated list of `id:hex_key` entries. The active key defaults
+    /// to the first entry when `active_key_id` is not given.
+    pub fn parse(entries: &str, active_key_id: Option<&str>) -> Result<Self, anyhow::Error> {
+        let mut keys = HashMap::new();
+        let mut first_key_id = None;
+
+        for entry in entries.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((id, hex_key)) = entry.split_once(':') else {
+                bail!("HMAC keyring entry `{entry}` must be in the format id:hex_key");
+            };
+
+            let id = id.trim();
+            if id.is_empty() {
+                bail!("HMAC keyring entry is missing a key id");
+            }
+
+            let key = hex::decode(hex_key.trim())
+                .map_err(|_| anyhow!("HMAC key `{id}` is not valid hex"))?;
+
+            if keys.insert(id.to_string(), key).is_some() {
+                bail!("HMAC key id `{id}` appears more than once");
+            }
+
+            first_key_id.get_or_insert_with(|| id.to_string());
+        }
+
+        let active_key_id = active_key_id
+            .map(str::to_string)
+            .or(first_key_id)
+            .ok_or_else(|| anyhow!("HMAC keyring is empty"))?;
+
+        let active_key = keys
+            .get(&active_key_id)
+            .cloned()
+            .ok_or_else(|| anyhow!("active HMAC key id `{active_key_id}` is not in the keyring"))?;
+
+        // Short keys from before the minimum was enforced may stay in the keyring to
+        // verify existing sessions, but new tokens must be hashed with a strong key.
+        if active_key.len() < MIN_HMAC_KEY_LEN {
+            bail!("active HMAC key `{active_key_id}` must be at least {MIN_HMAC_KEY_LEN} bytes");
+        }
+
+        Ok(Self {
+            active_key_id,
+            active_key,
+            keys,
+        })
+    }
+
+    /// A keyring holding only the legacy `SESSION_HMAC_KEY`, which is accepted at any
+    /// length so that existing deployments keep working; see [`Self::has_short_active_key`].
+    pub fn legacy(hex_key: &str) -> Result<Self, anyhow::Error> {
+        let key = hex::decode(hex_key.trim()).map_err(|_| anyhow!("HMAC key is not valid hex"))?;
+        if key.is_empty() {
+            bail!("HMAC key is empty");
+        }
+
+        Ok(Self {
+            active_key_id: LEGACY_HMAC_KEY_ID.to_string(),
+            active_key: key.clone(),
+            keys: HashMap::from([(LEGACY_HMAC_KEY_ID.to_string(), key)]),
+        })
+    }
+
+    /// Whether new tokens are hashed with a key shorter than the recommended minimum,
+    /// which only a legacy `SESSION_HMAC_KEY` can be.
+    pub const fn has_short_active_key(&self) -> bool {
+        self.active_key.len() < MIN_HMAC_KEY_LEN
+    }
+
+    /// The id of the key used to hash new tokens.
+    pub fn active_key_id(&self) -> &str {
+        &self.active_key_id
+    }
+
+    /// The key used to hash new tokens.
+    pub fn active_key(&self) -> &[u8] {
+        &self.active_key
+    }
+
+    /// Look up a key by id, returning `None` once it has been retired.
+    pub fn get(&self, key_id: &str) -> Option<&[u8]> {
+        self.keys.get(key_id).map(Vec::as_slice)
+    }
+}
+
 /// Represents a parsed session token in the format "uuid:token"
 #[derive(Debug, Clone)]
 pub struct SessionToken {
@@ -58,6 +165,53 @@ impl SessionToken {
     }
 }
 
+/// Represents a parsed personal access token in the format "uuid:token", as sent in
+/// an `Authorization: Bearer` header.
+#[derive(Debug, Clone)]
+pub struct ApiTokenSecret {
+    pub token_id: Uuid,
+    pub raw_token: String,
+}
+
+impl ApiTokenSecret {
+    /// Parse a bearer token in the format "uuid:token"
+    pub fn parse(value: &str) -> Result<Self, ApiError> {
+        let SessionToken {
+            session_id,
+            raw_token,
+        } = SessionToken::parse(value).map_err(|_| ApiError::Unauthorized {
+            reason: "invalid API token format".into(),
+        })?;
+
+        Ok(Self {
+            token_id: session_id,
+            raw_token,
+        })
+    }
+
+    /// Encode as "uuid:token" for the bearer header
+    pub fn encode(&self) -> String {
+        format!("{}:{}", self.token_id, self.raw_token)
+    }
+
+    /// Hash the raw token using HMAC-SHA256 for storage in database
+    pub fn hash_token(&self, hmac_key: &[u8]) -> Result<String, ApiError> {
+        let mut mac = HmacSha256::new_from_slice(hmac_key)
+            .map_err(|_| ApiError::Internal(anyhow::anyhow!("invalid HMAC key")))?;
+
+        mac.update(self.raw_token.as_bytes());
+        Ok(hex::encode(mac.finalize().into_bytes()))
+    }
+}
+
+/// Generate a new personal access token with random bytes
+pub fn generate_api_token(token_id: Uuid) -> ApiTokenSecret {
+    ApiTokenSecret {
+        token_id,
+        raw_token: get_token_bytes(),
+    }
+}
+
 /// Generate a new session token with random bytes
 pub fn generate_session_token(session_id: Uuid) -> SessionToken {
     // Generate 32 bytes of random data for the token
@@ -93,7 +247,7 @@ mod tests {
     #[test]
     fn test_parse_valid_token() {
         let uuid = Uuid::new_v4();
-        let cookie_value = format!("{}:abc123def456", uuid);
+        let cookie_value = format!("{uuid}:abc123def456");
 
         let token = SessionToken::parse(&cookie_value).unwrap();
         assert_eq!(token.session_id, uuid);
@@ -109,7 +263,7 @@ mod tests {
         };
 
         let encoded = token.encode();
-        assert_eq!(encoded, format!("{}:abc123def456", uuid));
+        assert_eq!(encoded, format!("{uuid}:abc123def456"));
     }
 
     #[test]
@@ -128,4 +282,59 @@ mod tests {
         let is_invalid = verify_token("wrong_token", &hash, hmac_key).unwrap();
         assert!(!is_invalid);
     }
+
+    #[test]
+    fn test_api_token_round_trip() {
+        let token = generate_api_token(Uuid::now_v7());
+        let parsed = ApiTokenSecret::parse(&token.encode()).unwrap();
+
+        assert_eq!(parsed.token_id, token.token_id);
+        assert_eq!(parsed.raw_token, token.raw_token);
+        assert!(ApiTokenSecret::parse("not-a-token").is_err());
+    }
+
+    const KEY_A: &str = "00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff";
+    const KEY_B: &str = "ffeeddccbbaa99887766554433221100ffeeddccbbaa99887766554433221100";
+
+    #[test]
+    fn test_keyring_defaults_active_key_to_first_entry() {
+        let keyring = HmacKeyring::parse(&format!("new:{KEY_A}, old:{KEY_B}"), None).unwrap();
+
+        assert_eq!(keyring.active_key_id(), "new");
+        assert_eq!(keyring.active_key(), hex::decode(KEY_A).unwrap());
+        assert_eq!(
+            keyring.get("old"),
+            Some(hex::decode(KEY_B).unwrap().as_slice())
+        );
+        assert!(keyring.get("retired").is_none());
+    }
+
+    #[test]
+    fn test_keyring_uses_configured_active_key() {
+        let keyring = HmacKeyring::parse(&format!("new:{KEY_A},old:{KEY_B}"), Some("old")).unwrap();
+        assert_eq!(keyring.active_key_id(), "old");
+    }
+
+    #[test]
+    fn test_keyring_rejects_invalid_entries() {
+        assert!(HmacKeyring::parse("", None).is_err());
+        assert!(HmacKeyring::parse(KEY_A, None).is_err());
+        assert!(HmacKeyring::parse("short:abcd", None).is_err());
+        assert!(HmacKeyring::parse(&format!("a:{KEY_A},a:{KEY_B}"), None).is_err());
+        assert!(HmacKeyring::parse(&format!("a:{KEY_A}"), Some("b")).is_err());
+    }
+
+    #[test]
+    fn test_keyring_keeps_short_legacy_keys() {
+        let keyring = HmacKeyring::legacy("abcd").unwrap();
+        assert_eq!(keyring.active_key_id(), LEGACY_HMAC_KEY_ID);
+        assert!(keyring.has_short_active_key());
+
+        // A short key can stay in the keyring to verify sessions, but not hash new ones.
+        let keyring =
+            HmacKeyring::parse(&format!("new:{KEY_A},{LEGACY_HMAC_KEY_ID}:abcd"), None).unwrap();
+        assert!(!keyring.has_short_active_key());
+        assert!(keyring.get(LEGACY_HMAC_KEY_ID).is_some());
+        assert!(HmacKeyring::parse(&format!("new:{KEY_A},old:abcd"), Some("old")).is_err());
+    }
 }
@@ -5,13 +5,17 @@ use argon2::{
 };
 use serde::{Deserialize, Serialize};
 use sqlx::prelude::FromRow;
-use std::fmt::Display;
-use time::OffsetDateTime;
-use utoipa::ToSchema;
+use std::{fmt::Display, str::FromStr};
+use time::{Duration, OffsetDateTime};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
-use crate::roles::{Role, RoleName};
+use crate::{
+    applications::Application,
+    roles::{Permission, Role},
+    shared_models::SortOrder,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, sqlx::Type)]
 #[sqlx(transparent)]
@@ -66,7 +70,11 @@ pub struct User {
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
     pub is_disabled: bool,
+    pub disabled_reason: Option<String>,
+    pub disabled_at: Option<OffsetDateTime>,
     pub roles: Vec<Role>,
+    pub permissions: Vec<Permission>,
+    pub applications: Vec<Application>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -85,6 +93,28 @@ pub struct UserBase {
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
     pub is_disabled: bool,
+    pub disabled_reason: Option<String>,
+    pub disabled_at: Option<OffsetDateTime>,
+    /// Set while a deleted account waits to be purged.
+    pub deleted_at: Option<OffsetDateTime>,
+}
+
+impl UserBase {
+    /// Whether the password has passed its expiration date.
+    pub fn is_password_expired(&self, now: OffsetDateTime) -> bool {
+        is_password_expired(self.password_expiration, now)
+    }
+}
+
+impl User {
+    /// Whether the password has passed its expiration date.
+    pub fn is_password_expired(&self, now: OffsetDateTime) -> bool {
+        is_password_expired(self.password_expiration, now)
+    }
+}
+
+fn is_password_expired(expiration: Option<OffsetDateTime>, now: OffsetDateTime) -> bool {
+    expiration.is_some_and(|expiration| expiration <= now)
 }
 
 impl From<UserBase> for User {
@@ -103,7 +133,11 @@ impl From<UserBase> for User {
             created_at: value.created_at,
             updated_at: value.updated_at,
             roles: Vec::new(),
+            permissions: Vec::new(),
+            applications: Vec::new(),
             is_disabled: value.is_disabled,
+            disabled_reason: value.disabled_reason,
+            disabled_at: value.disabled_at,
         }
     }
 }
@@ -139,6 +173,17 @@ pub struct UpdateUserRequest {
     pub username: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct DisableUserRequest {
+    /// Why the account is being disabled, shown to administrators.
+    #[validate(
+        length(min = 1, max = 500),
+        custom(function = crate::validation::not_blank)
+    )]
+    pub reason: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UserResponse {
@@ -149,23 +194,26 @@ pub struct UserResponse {
     pub username: String,
     #[serde(with = "time::serde::rfc3339::option")]
     pub last_login: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub password_expiration: Option<OffsetDateTime>,
+    pub password_expired: bool,
     pub roles: Vec<Role>,
+    /// Every permission granted by the user's roles.
+    pub permissions: Vec<Permission>,
+    /// The applications enabled for the user.
+    pub applications: Vec<Application>,
 }
 
 impl UserResponse {
-    pub fn is_admin(&self) -> bool {
-        for r in &self.roles {
-            if r.name == RoleName::Administrator {
-                return true;
-            }
-        }
-
-        false
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
     }
 }
 
 impl From<User> for UserResponse {
     fn from(user: User) -> Self {
+        let password_expired = user.is_password_expired(OffsetDateTime::now_utc());
+
         Self {
             id: user.id,
             first_name: user.first_name,
@@ -173,7 +221,11 @@ impl From<User> for UserResponse {
             email: user.email,
             username: user.username,
             last_login: user.last_login,
+            password_expiration: user.password_expiration,
+            password_expired,
             roles: user.roles,
+            permissions: user.permissions,
+            applications: user.applications,
         }
     }
 }
@@ -188,7 +240,18 @@ pub struct UserBaseResponse {
     pub username: String,
     #[serde(with = "time::serde::rfc3339::option")]
     pub last_login: Option<OffsetDateTime>,
+    #[serde(with = "time::serde::rfc3339")]
+    pub created_at: OffsetDateTime,
     pub is_disabled: bool,
+    pub disabled_reason: Option<String>,
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub disabled_at: Option<OffsetDateTime>,
+    /// When a lockout caused by failed logins runs out, if the account is locked.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub locked_until: Option<OffsetDateTime>,
+    /// When the account was deleted, if it is waiting to be purged.
+    #[serde(with = "time::serde::rfc3339::option")]
+    pub deleted_at: Option<OffsetDateTime>,
 }
 
 impl From<UserBase> for UserBaseResponse {
@@ -200,7 +263,12 @@ impl From<UserBase> for UserBaseResponse {
             email: user.email,
             username: user.username,
             last_login: user.last_login,
+            created_at: user.created_at,
             is_disabled: user.is_disabled,
+            disabled_reason: user.disabled_reason,
+            disabled_at: user.disabled_at,
+            locked_until: None,
+            deleted_at: user.deleted_at,
         }
     }
 }
@@ -223,11 +291,135 @@ impl TryFrom<CreateUserRequest> for User {
             failed_login_attempts: 0,
             last_failed_login_attempt: None,
             is_disabled: false,
+            disabled_reason: None,
+            disabled_at: None,
             roles: Vec::new(),
+            permissions: Vec::new(),
+            applications: Vec::new(),
         })
     }
 }
 
+/// Which users to list and in what order. Every filter is optional.
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct UserFilters {
+    #[serde(default = "default_page")]
+    pub page: i64,
+    #[serde(default = "default_page_size")]
+    pub page_size: i64,
+    /// Only users whose username, first name, last name or email contains this text.
+    pub q: Option<String>,
+    /// Only disabled (`true`) or enabled (`false`) accounts.
+    pub disabled: Option<bool>,
+    /// Only accounts that are (`true`) or are not (`false`) locked by failed logins.
+    pub locked: Option<bool>,
+    /// Only users with this role.
+    pub role_id: Option<Uuid>,
+    /// List deleted accounts that are waiting to be purged instead of active ones.
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub sort: UserSort,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+const fn default_page() -> i64 {
+    1
+}
+
+const fn default_page_size() -> i64 {
+    20
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum UserSort {
+    /// Last name, then first name.
+    #[default]
+    Name,
+    CreatedAt,
+    /// Users who never logged in come last.
+    LastLogin,
+}
+
+/// What happens to a deleted user's public recipes when the account is purged. Private
+/// recipes are always deleted along with the account.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RecipeHandling {
+    /// Delete the recipes with the account.
+    #[default]
+    Delete,
+    /// Keep the recipes without an owner.
+    Anonymize,
+    /// Give the recipes to another user.
+    Transfer,
+}
+
+impl RecipeHandling {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Delete => "delete",
+            Self::Anonymize => "anonymize",
+            Self::Transfer => "transfer",
+        }
+    }
+}
+
+impl FromStr for RecipeHandling {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "delete" => Ok(Self::Delete),
+            "anonymize" => Ok(Self::Anonymize),
+            "transfer" => Ok(Self::Transfer),
+            _ => Err(anyhow!("unknown recipe handling `{value}`")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+pub struct DeleteUserParams {
+    /// What to do with the user's public recipes once the account is purged.
+    #[serde(default)]
+    pub recipes: RecipeHandling,
+    /// The user who receives the recipes when `recipes` is `transfer`.
+    pub transfer_to: Option<Uuid>,
+}
+
+/// The password policy applied whenever a password is changed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordPolicy {
+    /// Number of days a new password stays valid, or `null` for passwords that never expire.
+    #[validate(range(min = 1, max = 3650))]
+    pub expiration_days: Option<i64>,
+}
+
+impl PasswordPolicy {
+    /// The expiration date for a password changed at `now`.
+    pub fn expiration_from(&self, now: OffsetDateTime) -> Option<OffsetDateTime> {
+        self.expiration_days
+            .map(|days| now.saturating_add(Duration::days(days)))
+    }
+}
+
+/// A user changing their own password, which requires knowing the current one.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePasswordRequest {
+    #[validate(length(min = 1, max = 256))]
+    pub current_password: String,
+    #[validate(custom(function = crate::validation::password_complexity))]
+    pub raw_password: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, Validate)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdatePasswordRequest {
@@ -304,7 +496,7 @@ mod tests {
     #[test]
     fn test_first_name_too_short() {
         let mut request = valid_request();
-        request.first_name = "".to_string();
+        request.first_name = String::new();
         assert!(request.validate().is_err());
     }
 
@@ -318,7 +510,7 @@ mod tests {
     #[test]
     fn test_last_name_too_short() {
         let mut request = valid_request();
-        request.last_name = "".to_string();
+        request.last_name = String::new();
         assert!(request.validate().is_err());
     }
 
@@ -339,7 +531,7 @@ mod tests {
     #[test]
     fn test_username_too_short() {
         let mut request = valid_request();
-        request.username = "".to_string();
+        request.username = String::new();
         assert!(request.validate().is_err());
     }
 
@@ -406,6 +598,58 @@ mod tests {
         assert!(request.validate().is_err());
     }
 
+    #[test]
+    fn test_change_password_requires_current_password() {
+        let mut request = ChangePasswordRequest {
+            current_password: "OldPass123!@".to_string(),
+            raw_password: "MyPass123!@".to_string(),
+        };
+        assert!(request.validate().is_ok());
+
+        request.current_password = String::new();
+        assert!(request.validate().is_err());
+    }
+
+    #[test]
+    fn test_disable_reason_must_not_be_blank() {
+        let request = |reason: &str| DisableUserRequest {
+            reason: reason.to_string(),
+        };
+        assert!(request("").validate().is_err());
+        assert!(request("   \n").validate().is_err());
+        assert!(request("left the household").validate().is_ok());
+    }
+
+    #[test]
+    fn test_password_policy_without_expiration() {
+        let policy = PasswordPolicy {
+            expiration_days: None,
+        };
+        assert!(policy.expiration_from(OffsetDateTime::now_utc()).is_none());
+    }
+
+    #[test]
+    fn test_password_policy_sets_expiration() {
+        let now = OffsetDateTime::now_utc();
+        let policy = PasswordPolicy {
+            expiration_days: Some(180),
+        };
+        assert_eq!(
+            policy.expiration_from(now),
+            Some(now + time::Duration::days(180))
+        );
+    }
+
+    #[test]
+    fn test_password_policy_range() {
+        let policy = |days| PasswordPolicy {
+            expiration_days: Some(days),
+        };
+        assert!(policy(0).validate().is_err());
+        assert!(policy(3651).validate().is_err());
+        assert!(policy(180).validate().is_ok());
+    }
+
     #[test]
     fn test_password_with_minimum_requirements() {
         let mut request = valid_request();
@@ -1,20 +1,82 @@
 use crate::{
+    authentication::LockoutPolicy,
     errors::RepositoryError,
-    users::{User, UserBase},
+    roles::ADMINISTRATOR_ROLE,
+    shared_models::page_offset,
+    users::{Password, PasswordPolicy, RecipeHandling, User, UserBase, UserFilters, UserSort},
 };
 use async_trait::async_trait;
-use sqlx::SqlitePool;
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use time::OffsetDateTime;
 use uuid::Uuid;
 
 #[async_trait::async_trait]
 pub trait IUserRepository: Send + Sync {
     async fn get_by_id(&self, id: Uuid) -> Result<UserBase, RepositoryError>;
     async fn get_by_username(&self, username: &str) -> Result<UserBase, RepositoryError>;
-    async fn get_all(&self) -> Result<Vec<UserBase>, RepositoryError>;
+    /// Get a page of the users matching the filters, along with the total number of
+    /// matching users. Whether an account counts as locked depends on `lockout` and `now`.
+    async fn search(
+        &self,
+        filters: &UserFilters,
+        lockout: &LockoutPolicy,
+        now: OffsetDateTime,
+    ) -> Result<(Vec<UserBase>, i64), RepositoryError>;
     async fn create(&self, user: &User) -> Result<(), RepositoryError>;
     async fn update_base(&self, user: &UserBase) -> Result<(), RepositoryError>;
-    async fn update_password(&self, user: &UserBase) -> Result<(), RepositoryError>;
-    async fn delete(&self, id: Uuid) -> Result<(), RepositoryError>;
+    /// Set a new password, moving the old hash into the password history and keeping
+    /// only the `history_size` most recent entries there.
+    async fn update_password(
+        &self,
+        user: &UserBase,
+        history_size: i64,
+    ) -> Result<(), RepositoryError>;
+    /// Get up to `limit` previous password hashes of a user, newest first.
+    async fn get_password_history(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Password>, RepositoryError>;
+    /// Count a failed login attempt, returning the number of consecutive failures.
+    async fn record_failed_login(
+        &self,
+        id: Uuid,
+        attempted_at: OffsetDateTime,
+    ) -> Result<i64, RepositoryError>;
+    /// Clear the failed login counter, lifting any lockout.
+    async fn reset_failed_logins(&self, id: Uuid) -> Result<(), RepositoryError>;
+    /// Disable an account, recording why and when. Returns `false`, changing nothing,
+    /// when the account is the last enabled administrator.
+    async fn disable(
+        &self,
+        id: Uuid,
+        reason: &str,
+        disabled_at: OffsetDateTime,
+    ) -> Result<bool, RepositoryError>;
+    /// Re-enable a disabled account, clearing the recorded reason.
+    async fn enable(&self, id: Uuid) -> Result<(), RepositoryError>;
+    /// Mark an account as deleted, remembering what to do with its recipes. The account
+    /// is kept until it is purged, so it can be restored until then. Returns `false`,
+    /// changing nothing, when the account is the last enabled administrator.
+    async fn mark_deleted(
+        &self,
+        id: Uuid,
+        deleted_at: OffsetDateTime,
+        recipes: RecipeHandling,
+        transfer_to: Option<Uuid>,
+    ) -> Result<bool, RepositoryError>;
+    /// Bring back a deleted account that has not been purged yet.
+    async fn restore(&self, id: Uuid) -> Result<(), RepositoryError>;
+    /// Permanently delete the accounts deleted before `deleted_before`, handling their
+    /// public recipes as chosen when they were deleted. Deleted administrators are kept
+    /// while no enabled administrator is left, so one can still be restored. Returns the
+    /// ids of the purged accounts.
+    async fn purge_deleted(
+        &self,
+        deleted_before: OffsetDateTime,
+    ) -> Result<Vec<Uuid>, RepositoryError>;
+    async fn get_password_policy(&self) -> Result<PasswordPolicy, RepositoryError>;
+    async fn update_password_policy(&self, policy: &PasswordPolicy) -> Result<(), RepositoryError>;
 }
 
 pub struct SqlxUserRepository {
@@ -25,6 +87,162 @@ impl SqlxUserRepository {
     pub const fn new(pool: SqlitePool) -> Self {
         Self { pool }
     }
+
+    /// Fail with `NotFound` unless the account exists and has not been deleted.
+    async fn ensure_active(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let exists = sqlx::query_scalar!(
+            r#"SELECT EXISTS(SELECT 1 FROM users WHERE id = ? AND deleted_at IS NULL) AS "exists: bool""#,
+            id
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        if !exists {
+            return Err(RepositoryError::NotFound {
+                entity: "user",
+                property: "id",
+                value: id.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Set a user's new password on `conn`, moving the old hash into the password history
+/// and keeping only the `history_size` most recent entries there. Shared with other
+/// repositories that change the password as part of a larger transaction.
+pub async fn write_password(
+    conn: &mut SqliteConnection,
+    user: &UserBase,
+    history_size: i64,
+) -> Result<(), RepositoryError> {
+    let history_id = Uuid::now_v7();
+    let now = OffsetDateTime::now_utc();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO password_history (id, user_id, password_hash, created_at)
+        SELECT ?, id, password_hash, ?
+        FROM users
+        WHERE id = ?
+        "#,
+        history_id,
+        now,
+        user.id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE users SET password_hash = ?, password_expiration = ? WHERE id = ?",
+        user.password_hash,
+        user.password_expiration,
+        user.id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM password_history
+        WHERE user_id = ?
+            AND id NOT IN (
+                SELECT id
+                FROM password_history
+                WHERE user_id = ?
+                ORDER BY created_at DESC
+                LIMIT ?
+            )
+        "#,
+        user.id,
+        user.id,
+        history_size
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Add the WHERE clause matching `filters` to a query over the users table.
+fn push_user_filters(
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    filters: &UserFilters,
+    lockout: &LockoutPolicy,
+    now: OffsetDateTime,
+) {
+    builder.push(if filters.deleted {
+        " WHERE deleted_at IS NOT NULL"
+    } else {
+        " WHERE deleted_at IS NULL"
+    });
+
+    if let Some(q) = filters
+        .q
+        .as_deref()
+        .map(str::trim)
+        .filter(|q| !q.is_empty())
+    {
+        let escaped = q
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        let pattern = format!("%{escaped}%");
+
+        builder.push(" AND (");
+        for (idx, column) in ["username", "first_name", "last_name", "email"]
+            .iter()
+            .enumerate()
+        {
+            if idx > 0 {
+                builder.push(" OR ");
+            }
+            builder.push(format!("{column} LIKE "));
+            builder.push_bind(pattern.clone());
+            builder.push(" ESCAPE '\\'");
+        }
+        builder.push(")");
+    }
+
+    if let Some(disabled) = filters.disabled {
+        builder.push(" AND is_disabled = ");
+        builder.push_bind(disabled);
+    }
+
+    if let Some(locked) = filters.locked {
+        // one range of failure counts per lockout window, each locked while the last
+        // failure is more recent than the window
+        builder.push(if locked { " AND (" } else { " AND NOT (" });
+        builder.push("last_failed_login_attempt IS NOT NULL AND (");
+        let windows = lockout.windows();
+        if windows.is_empty() {
+            builder.push("FALSE");
+        }
+        for (idx, &(from, window)) in windows.iter().enumerate() {
+            if idx > 0 {
+                builder.push(" OR ");
+            }
+            builder.push("(failed_login_attempts >= ");
+            builder.push_bind(from);
+            if let Some(&(next, _)) = windows.get(idx + 1) {
+                builder.push(" AND failed_login_attempts < ");
+                builder.push_bind(next);
+            }
+            builder.push(" AND CAST(strftime('%s', last_failed_login_attempt) AS INTEGER) > ");
+            builder.push_bind(now.saturating_sub(window).unix_timestamp());
+            builder.push(")");
+        }
+        builder.push("))");
+    }
+
+    if let Some(role_id) = filters.role_id {
+        builder.push(
+            " AND EXISTS (SELECT 1 FROM user_roles ur WHERE ur.user_id = users.id AND ur.role_id = ",
+        );
+        builder.push_bind(role_id);
+        builder.push(")");
+    }
 }
 
 #[async_trait]
@@ -45,10 +263,13 @@ impl IUserRepository for SqlxUserRepository {
                 failed_login_attempts,
                 last_failed_login_attempt,
                 is_disabled,
+                disabled_reason,
+                disabled_at,
                 created_at,
-                updated_at
+                updated_at,
+                deleted_at
             FROM users 
-            WHERE id = ?;"#,
+            WHERE id = ? AND deleted_at IS NULL;"#,
             id
         )
         .fetch_optional(&self.pool)
@@ -75,12 +296,15 @@ impl IUserRepository for SqlxUserRepository {
                 password_expiration,
                 last_login,
                 is_disabled,
+                disabled_reason,
+                disabled_at,
                 failed_login_attempts,
                 last_failed_login_attempt,
                 created_at,
-                updated_at
+                updated_at,
+                deleted_at
             FROM users 
-            WHERE LOWER(username) = LOWER(?)"#,
+            WHERE LOWER(username) = LOWER(?) AND deleted_at IS NULL"#,
             username
         )
         .fetch_optional(&self.pool)
@@ -93,30 +317,64 @@ impl IUserRepository for SqlxUserRepository {
         })
     }
 
-    async fn get_all(&self) -> Result<Vec<UserBase>, RepositoryError> {
-        let users = sqlx::query_as!(
-            UserBase,
-            r#"
-            SELECT 
-                id as "id: uuid::Uuid", 
-                email, 
-                first_name, 
-                last_name, 
-                username, 
+    async fn search(
+        &self,
+        filters: &UserFilters,
+        lockout: &LockoutPolicy,
+        now: OffsetDateTime,
+    ) -> Result<(Vec<UserBase>, i64), RepositoryError> {
+        let page = filters.page;
+        let page_size = filters.page_size;
+
+        let offset = page_offset(page, page_size)?;
+
+        let mut count = QueryBuilder::new("SELECT COUNT(*) FROM users");
+        push_user_filters(&mut count, filters, lockout, now);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut select = QueryBuilder::new(
+            r"
+            SELECT
+                id,
+                email,
+                first_name,
+                last_name,
+                username,
                 password_hash,
                 password_expiration,
                 last_login,
                 is_disabled,
+                disabled_reason,
+                disabled_at,
                 failed_login_attempts,
                 last_failed_login_attempt,
                 created_at,
-                updated_at
-            FROM users"#
-        )
-        .fetch_all(&self.pool)
-        .await?;
+                updated_at,
+                deleted_at
+            FROM users",
+        );
+        push_user_filters(&mut select, filters, lockout, now);
 
-        return Ok(users);
+        // the sort columns come from a fixed set, so they are safe to put in the query
+        let order = filters.order.as_sql();
+        select.push(match filters.sort {
+            UserSort::Name => format!(
+                " ORDER BY last_name COLLATE NOCASE {order}, first_name COLLATE NOCASE {order}, id"
+            ),
+            UserSort::CreatedAt => format!(" ORDER BY created_at {order}, id"),
+            UserSort::LastLogin => format!(" ORDER BY last_login IS NULL, last_login {order}, id"),
+        });
+        select.push(" LIMIT ");
+        select.push_bind(page_size);
+        select.push(" OFFSET ");
+        select.push_bind(offset);
+
+        let users = select
+            .build_query_as::<UserBase>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok((users, total))
     }
 
     async fn create(&self, user: &User) -> Result<(), RepositoryError> {
@@ -184,22 +442,162 @@ impl IUserRepository for SqlxUserRepository {
         Ok(())
     }
 
-    async fn update_password(&self, user: &UserBase) -> Result<(), RepositoryError> {
-        sqlx::query!(
-            "UPDATE users SET password_hash = ?, password_expiration = ? WHERE id = ?",
-            user.password_hash,
-            user.password_expiration,
-            user.id
+    async fn update_password(
+        &self,
+        user: &UserBase,
+        history_size: i64,
+    ) -> Result<(), RepositoryError> {
+        let mut tx = self.pool.begin().await?;
+        write_password(&mut tx, user, history_size).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn get_password_history(
+        &self,
+        user_id: Uuid,
+        limit: i64,
+    ) -> Result<Vec<Password>, RepositoryError> {
+        let history = sqlx::query_scalar!(
+            r#"
+            SELECT password_hash
+            FROM password_history
+            WHERE user_id = ?
+            ORDER BY created_at DESC
+            LIMIT ?
+            "#,
+            user_id,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(history.into_iter().map(Password::from).collect())
+    }
+
+    async fn record_failed_login(
+        &self,
+        id: Uuid,
+        attempted_at: OffsetDateTime,
+    ) -> Result<i64, RepositoryError> {
+        // increment in the database so concurrent failures are all counted
+        let failed_login_attempts = sqlx::query_scalar!(
+            r#"
+            UPDATE users
+            SET failed_login_attempts = failed_login_attempts + 1,
+            last_failed_login_attempt = ?
+            WHERE id = ?
+            RETURNING failed_login_attempts
+            "#,
+            attempted_at,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        failed_login_attempts.ok_or(RepositoryError::NotFound {
+            entity: "user",
+            property: "id",
+            value: id.to_string(),
+        })
+    }
+
+    async fn reset_failed_logins(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET failed_login_attempts = 0,
+            last_failed_login_attempt = NULL
+            WHERE id = ?
+            "#,
+            id
         )
         .execute(&self.pool)
         .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound {
+                entity: "user",
+                property: "id",
+                value: id.to_string(),
+            });
+        }
+
         Ok(())
     }
 
-    async fn delete(&self, id: Uuid) -> Result<(), RepositoryError> {
-        let result = sqlx::query!("DELETE FROM users WHERE id = ?", id)
-            .execute(&self.pool)
-            .await?;
+    async fn disable(
+        &self,
+        id: Uuid,
+        reason: &str,
+        disabled_at: OffsetDateTime,
+    ) -> Result<bool, RepositoryError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET is_disabled = true,
+            disabled_reason = ?,
+            disabled_at = ?,
+            updated_at = ?
+            WHERE id = ? AND deleted_at IS NULL
+            AND (
+                users.is_disabled = true
+                OR users.id NOT IN (
+                    SELECT ur.user_id
+                    FROM user_roles ur
+                    INNER JOIN roles r
+                        ON r.id = ur.role_id
+                    WHERE r.name = ?
+                )
+                OR EXISTS (
+                    SELECT 1
+                    FROM user_roles ur
+                    INNER JOIN roles r
+                        ON r.id = ur.role_id
+                    INNER JOIN users other
+                        ON other.id = ur.user_id
+                    WHERE r.name = ?
+                        AND other.id != users.id
+                        AND other.is_disabled = false
+                        AND other.deleted_at IS NULL
+                )
+            )
+            "#,
+            reason,
+            disabled_at,
+            disabled_at,
+            id,
+            ADMINISTRATOR_ROLE,
+            ADMINISTRATOR_ROLE
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return self.ensure_active(id).await.map(|()| false);
+        }
+
+        Ok(true)
+    }
+
+    async fn enable(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let now = OffsetDateTime::now_utc();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET is_disabled = false,
+            disabled_reason = NULL,
+            disabled_at = NULL,
+            updated_at = ?
+            WHERE id = ? AND deleted_at IS NULL
+            "#,
+            now,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
 
         if result.rows_affected() == 0 {
             return Err(RepositoryError::NotFound {
@@ -211,4 +609,207 @@ impl IUserRepository for SqlxUserRepository {
 
         Ok(())
     }
+
+    async fn mark_deleted(
+        &self,
+        id: Uuid,
+        deleted_at: OffsetDateTime,
+        recipes: RecipeHandling,
+        transfer_to: Option<Uuid>,
+    ) -> Result<bool, RepositoryError> {
+        let recipes = recipes.as_str();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET deleted_at = ?,
+            recipe_handling = ?,
+            recipe_transfer_to = ?,
+            updated_at = ?
+            WHERE id = ? AND deleted_at IS NULL
+            AND (
+                users.is_disabled = true
+                OR users.id NOT IN (
+                    SELECT ur.user_id
+                    FROM user_roles ur
+                    INNER JOIN roles r
+                        ON r.id = ur.role_id
+                    WHERE r.name = ?
+                )
+                OR EXISTS (
+                    SELECT 1
+                    FROM user_roles ur
+                    INNER JOIN roles r
+                        ON r.id = ur.role_id
+                    INNER JOIN users other
+                        ON other.id = ur.user_id
+                    WHERE r.name = ?
+                        AND other.id != users.id
+                        AND other.is_disabled = false
+                        AND other.deleted_at IS NULL
+                )
+            )
+            "#,
+            deleted_at,
+            recipes,
+            transfer_to,
+            deleted_at,
+            id,
+            ADMINISTRATOR_ROLE,
+            ADMINISTRATOR_ROLE
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return self.ensure_active(id).await.map(|()| false);
+        }
+
+        Ok(true)
+    }
+
+    async fn restore(&self, id: Uuid) -> Result<(), RepositoryError> {
+        let now = OffsetDateTime::now_utc();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE users
+            SET deleted_at = NULL,
+            recipe_handling = NULL,
+            recipe_transfer_to = NULL,
+            updated_at = ?
+            WHERE id = ? AND deleted_at IS NOT NULL
+            "#,
+            now,
+            id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound {
+                entity: "deleted user",
+                property: "id",
+                value: id.to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn purge_deleted(
+        &self,
+        deleted_before: OffsetDateTime,
+    ) -> Result<Vec<Uuid>, RepositoryError> {
+        let candidates = sqlx::query!(
+            r#"
+            SELECT
+                id AS "id: uuid::Uuid",
+                recipe_handling,
+                recipe_transfer_to AS "recipe_transfer_to: uuid::Uuid"
+            FROM users
+            WHERE deleted_at IS NOT NULL AND deleted_at <= ?
+                AND (
+                    users.id NOT IN (
+                        SELECT ur.user_id
+                        FROM user_roles ur
+                        INNER JOIN roles r
+                            ON r.id = ur.role_id
+                        WHERE r.name = ?
+                    )
+                    OR EXISTS (
+                        SELECT 1
+                        FROM user_roles ur
+                        INNER JOIN roles r
+                            ON r.id = ur.role_id
+                        INNER JOIN users other
+                            ON other.id = ur.user_id
+                        WHERE r.name = ?
+                            AND other.is_disabled = false
+                            AND other.deleted_at IS NULL
+                    )
+                )
+            "#,
+            deleted_before,
+            ADMINISTRATOR_ROLE,
+            ADMINISTRATOR_ROLE
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut purged = Vec::with_capacity(candidates.len());
+
+        for candidate in candidates {
+            let handling: RecipeHandling = candidate
+                .recipe_handling
+                .as_deref()
+                .and_then(|handling| handling.parse().ok())
+                .unwrap_or_default();
+
+            let mut tx = self.pool.begin().await?;
+
+            if handling != RecipeHandling::Delete {
+                // a recipient that has been deleted in the meantime cannot take the
+                // recipes, so they are anonymized instead
+                let recipient = match candidate.recipe_transfer_to {
+                    Some(recipient) if handling == RecipeHandling::Transfer => {
+                        sqlx::query_scalar!(
+                            r#"SELECT id AS "id: uuid::Uuid" FROM users WHERE id = ? AND deleted_at IS NULL"#,
+                            recipient
+                        )
+                        .fetch_optional(&mut *tx)
+                        .await?
+                    }
+                    _ => None,
+                };
+
+                sqlx::query!(
+                    "UPDATE recipes SET user_id = ? WHERE user_id = ? AND is_public = true",
+                    recipient,
+                    candidate.id
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            // everything else the user owned goes with the account
+            sqlx::query!(
+                "DELETE FROM users WHERE id = ? AND deleted_at IS NOT NULL",
+                candidate.id
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            tx.commit().await?;
+            purged.push(candidate.id);
+        }
+
+        Ok(purged)
+    }
+
+    async fn get_password_policy(&self) -> Result<PasswordPolicy, RepositoryError> {
+        let policy = sqlx::query_as!(
+            PasswordPolicy,
+            "SELECT expiration_days FROM password_policy WHERE id = 1"
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        policy.ok_or(RepositoryError::NotFound {
+            entity: "password policy",
+            property: "id",
+            value: "1".into(),
+        })
+    }
+
+    async fn update_password_policy(&self, policy: &PasswordPolicy) -> Result<(), RepositoryError> {
+        sqlx::query!(
+            "UPDATE password_policy SET expiration_days = ?, updated_at = CURRENT_TIMESTAMP WHERE id = 1",
+            policy.expiration_days
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
 }
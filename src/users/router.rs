@@ -1,20 +1,28 @@
 use axum::{
     Json, Router,
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::HeaderValue,
     response::IntoResponse,
-    routing::{get, put},
+    routing::{get, post, put},
 };
 
 use hyper::{HeaderMap, StatusCode, header};
 use uuid::Uuid;
 
 use crate::{
+    applications::Application,
+    data_exports::{
+        ExportedProfile, ExportedRecipe, ExportedSession, ExportedStep, UserDataExport,
+    },
     errors::ApiError,
-    extractors::{AdminUser, ValidatedJson, authenticated_user::AuthenticatedUser},
+    extractors::{RequirePermission, ValidatedJson, authenticated_user::AuthenticatedUser},
+    roles::permissions::UsersManage,
     services::ServiceContainer,
+    shared_models::{PaginatedResponse, SortOrder},
     users::{
-        CreateUserRequest, UpdatePasswordRequest, UpdateUserRequest, UserBaseResponse, UserResponse,
+        ChangePasswordRequest, CreateUserRequest, DeleteUserParams, DisableUserRequest,
+        PasswordPolicy, RecipeHandling, UpdatePasswordRequest, UpdateUserRequest, UserBaseResponse,
+        UserFilters, UserResponse, UserSort,
     },
 };
 
@@ -23,8 +31,22 @@ pub fn router() -> Router<ServiceContainer> {
         .route("/", get(get_all_users).post(create_user))
         .route("/{id}", get(get_by_id).put(update_user).delete(delete_user))
         .route("/{id}/password", put(update_password_for_user))
+        .route("/{id}/unlock", post(unlock_user))
+        .route("/{id}/disable", post(disable_user))
+        .route("/{id}/enable", post(enable_user))
+        .route("/{id}/restore", post(restore_user))
+        .route("/{id}/roles/{role_id}", put(grant_role).delete(revoke_role))
+        .route(
+            "/{id}/applications/{application}",
+            put(enable_application).delete(disable_application),
+        )
         .route("/self", put(update_self))
         .route("/self/password", put(update_own_password))
+        .route("/self/export", get(export_self))
+        .route(
+            "/password-policy",
+            get(get_password_policy).put(update_password_policy),
+        )
 }
 
 // Clippy lint triggered by utoipa macro expansion, not our code
@@ -39,10 +61,38 @@ pub fn router() -> Router<ServiceContainer> {
         crate::users::update_own_password,
         crate::users::update_password_for_user,
         crate::users::update_self,
-        crate::users::delete_user
+        crate::users::export_self,
+        crate::users::delete_user,
+        crate::users::restore_user,
+        crate::users::unlock_user,
+        crate::users::disable_user,
+        crate::users::enable_user,
+        crate::users::grant_role,
+        crate::users::revoke_role,
+        crate::users::enable_application,
+        crate::users::disable_application,
+        crate::users::get_password_policy,
+        crate::users::update_password_policy
     ),
     components(
-        schemas(UserBaseResponse, UserResponse, CreateUserRequest, UpdateUserRequest)
+        schemas(
+            UserBaseResponse,
+            UserResponse,
+            CreateUserRequest,
+            UpdateUserRequest,
+            DisableUserRequest,
+            ChangePasswordRequest,
+            UpdatePasswordRequest,
+            PasswordPolicy,
+            UserSort,
+            SortOrder,
+            RecipeHandling,
+            UserDataExport,
+            ExportedProfile,
+            ExportedSession,
+            ExportedRecipe,
+            ExportedStep
+        )
     ),
     tags(
         (name = "Users", description = "User and account management endpoints")
@@ -55,19 +105,24 @@ pub struct UsersApiDoc;
     summary = "List Users",
     path = "/api/users",
     tag = "Users",
+    params(UserFilters),
     responses(
-        (status = 200, description = "List all users", body = Vec<UserBaseResponse>),
+        (status = 200, description = "A page of users matching the filters"),
+        (status = 400, description = "Invalid filters or page size"),
         (status = 401, description = "Unauthorized"),
-        (status = 403, description = "Forbidden - requires admin role"),
+        (status = 403, description = "Forbidden - requires the users:manage permission"),
     ),
-    description = "Retrieves a complete list of all users in the system. \
-        Requires a valid session_id cookie and administrator role to access this endpoint."
+    description = "Retrieves a page of users. Users can be searched by username, name and email, \
+        filtered by disabled or locked state and by role, and sorted by name, creation date or \
+        last login. Requires a valid session_id cookie and the users:manage permission to access \
+        this endpoint."
 )]
 pub async fn get_all_users(
-    _: AdminUser,
+    _: RequirePermission<UsersManage>,
     State(container): State<ServiceContainer>,
-) -> Result<Json<Vec<UserBaseResponse>>, ApiError> {
-    let users = container.user_service().get_all().await?;
+    Query(filters): Query<UserFilters>,
+) -> Result<Json<PaginatedResponse<UserBaseResponse>>, ApiError> {
+    let users = container.user_service().search(filters).await?;
     Ok(Json(users))
 }
 
@@ -82,14 +137,14 @@ pub async fn get_all_users(
     responses(
         (status = 200, description = "User found", body = UserResponse),
         (status = 401, description = "Unauthorized"),
-        (status = 403, description = "Forbidden - requires admin role"),
+        (status = 403, description = "Forbidden - requires the users:manage permission"),
         (status = 404, description = "User not found"),
     ),
     description = "Retrieves detailed information about a specific user by their unique ID. \
-        Requires a valid session_id cookie and administrator role to access this endpoint."
+        Requires a valid session_id cookie and the users:manage permission to access this endpoint."
 )]
 pub async fn get_by_id(
-    _: AdminUser,
+    _: RequirePermission<UsersManage>,
     Path(id): Path<Uuid>,
     State(container): State<ServiceContainer>,
 ) -> Result<Json<UserResponse>, ApiError> {
@@ -109,20 +164,25 @@ pub async fn get_by_id(
         )),
         (status = 400, description = "Invalid request body"),
         (status = 401, description = "Unauthorized"),
-        (status = 403, description = "Forbidden - requires admin role"),
-        (status = 409, description = "User already exists"),
+        (status = 403, description = "Forbidden - requires the users:manage permission, and \
+                                      every permission of the given roles"),
+        (status = 409, description = "The username or email is already in use"),
     ),
-    description = "Creates a new user in the system with the provided details. \
+    description = "Creates a new user in the system with the provided details. The caller \
+        must hold every permission of the roles given to the new user. \
         Returns a 201 status code with a Location header pointing to the newly created user resource. \
-        Requires a valid session_id cookie and administrator role to access this endpoint."
+        Requires a valid session_id cookie and the users:manage permission to access this endpoint."
 )]
 
 pub async fn create_user(
-    _: AdminUser,
+    admin: RequirePermission<UsersManage>,
     State(container): State<ServiceContainer>,
     ValidatedJson(req): ValidatedJson<CreateUserRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
-    let user_id = container.user_service().create(req).await?;
+    let user_id = container
+        .user_service()
+        .create(req, &admin.user.permissions)
+        .await?;
     let location_str = format!("/users/{user_id}");
     let location = HeaderValue::from_str(&location_str).map_err(|err| anyhow::anyhow!(err))?;
     let mut headers = HeaderMap::new();
@@ -142,22 +202,27 @@ pub async fn create_user(
     responses(
         (status = 204, description = "User updated successfully"),
         (status = 400, description = "Invalid request body"),
+        (status = 409, description = "The username or email is already in use"),
         (status = 401, description = "Unauthorized"),
-        (status = 403, description = "Forbidden - requires admin role"),
+        (status = 403, description = "Forbidden - requires the users:manage permission, and \
+                                      every permission the user holds"),
         (status = 404, description = "User not found"),
     ),
     description = "Updates an existing user's information by their unique ID. \
         Only the fields provided in the request body will be updated. \
         Returns a 204 No Content status on success. \
-        Requires a valid session_id cookie and administrator role to access this endpoint."
+        Requires a valid session_id cookie and the users:manage permission to access this endpoint."
 )]
 pub async fn update_user(
-    _: AdminUser,
+    admin: RequirePermission<UsersManage>,
     Path(id): Path<Uuid>,
     State(container): State<ServiceContainer>,
     ValidatedJson(req): ValidatedJson<UpdateUserRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
-    container.user_service().update(id, req).await?;
+    container
+        .user_service()
+        .update(id, req, &admin.user.permissions)
+        .await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
@@ -174,20 +239,27 @@ pub async fn update_user(
         (status = 204, description = "Password updated successfully for other user"),
         (status = 400, description = "Invalid request body"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - requires the users:manage permission, every \
+                                      permission the user holds, and a session login rather \
+                                      than an API token"),
     ),
     description = "Allows an administrator to update a password for another user. \
         Only the password field provided in the request body will be updated. \
-        Returns a 204 No Content status on success. Requires a valid session_id cookie."
+        The user's API tokens are revoked. \
+        Returns a 204 No Content status on success. Requires a valid session_id cookie; \
+        API tokens cannot set passwords."
 )]
 pub async fn update_password_for_user(
-    _: AdminUser,
+    auth: AuthenticatedUser,
+    admin: RequirePermission<UsersManage>,
     Path(id): Path<Uuid>,
     State(container): State<ServiceContainer>,
     ValidatedJson(req): ValidatedJson<UpdatePasswordRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
+    auth.require_session()?;
     container
         .user_service()
-        .update_password_for_user(id, req)
+        .update_password_for_user(id, req, &admin.user.permissions)
         .await?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -201,46 +273,100 @@ pub async fn update_password_for_user(
     responses(
         (status = 204, description = "User updated successfully"),
         (status = 400, description = "Invalid request body"),
+        (status = 409, description = "The username or email is already in use"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - API tokens cannot change the profile"),
     ),
     description = "Allows an authenticated user to update their own profile information. \
         The user ID is automatically determined from the authentication session. \
         Only the fields provided in the request body will be updated. \
-        Returns a 204 No Content status on success. Requires a valid session_id cookie."
+        Returns a 204 No Content status on success. Requires a valid session_id cookie; \
+        API tokens cannot change the profile."
 )]
 pub async fn update_self(
     auth: AuthenticatedUser,
     State(container): State<ServiceContainer>,
     ValidatedJson(req): ValidatedJson<UpdateUserRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
-    container.user_service().update(auth.user.id, req).await?;
+    auth.require_session()?;
+    container
+        .user_service()
+        .update(auth.user.id, req, &auth.user.permissions)
+        .await?;
     Ok(StatusCode::NO_CONTENT)
 }
 
+#[utoipa::path(
+    get,
+    summary = "Export Current User's Data",
+    path = "/api/users/self/export",
+    tag = "Users",
+    responses(
+        (status = 200, description = "Everything held about the current user, as a JSON file download",
+            body = UserDataExport,
+            headers(
+                ("Content-Disposition" = String, description = "Attachment filename for the export")
+            )
+        ),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - API tokens cannot download the export"),
+    ),
+    description = "Downloads everything Mainframe holds about the authenticated user: their profile \
+        without the password hash, their roles and permissions, enabled applications, active \
+        sessions without their tokens, and every recipe they own with its ingredients and \
+        instructions. The layout is versioned by `formatVersion` and keeps record ids so that the \
+        export can be imported again. Requires a valid session_id cookie; API tokens cannot \
+        download the export."
+)]
+pub async fn export_self(
+    auth: AuthenticatedUser,
+    State(container): State<ServiceContainer>,
+) -> Result<impl IntoResponse, ApiError> {
+    auth.require_session()?;
+    let export = container
+        .data_export_service()
+        .export_user(auth.user.id)
+        .await?;
+
+    let disposition = format!(
+        "attachment; filename=\"mainframe-export-{}.json\"",
+        auth.user.id
+    );
+    let disposition = HeaderValue::from_str(&disposition).map_err(|err| anyhow::anyhow!(err))?;
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_DISPOSITION, disposition);
+    Ok((headers, Json(export)))
+}
+
 #[utoipa::path(
     put,
     summary = "Update Current User's Password",
     path = "/api/users/self/password",
     tag = "Users",
-    request_body = UpdatePasswordRequest,
+    request_body = ChangePasswordRequest,
     responses(
         (status = 204, description = "Current User's Password updated successfully"),
-        (status = 400, description = "Invalid request body"),
-        (status = 401, description = "Unauthorized"),
+        (status = 400, description = "Invalid request body, incorrect current password, or a recently used password"),
+        (status = 401, description = "Unauthorized or account locked"),
     ),
-    description = "Allows an authenticated user to update their own password. \
+    description = "Allows an authenticated user to change their own password. \
         The user ID is automatically determined from the authentication session. \
-        Only the password field provided in the request body will be updated. \
+        The current password must be supplied, and wrong guesses count towards the login \
+        lockout. The new password may not match the current password or any of the \
+        recently used ones. It expires according to the password policy. \
+        All other sessions of the user are revoked; the session making the request stays \
+        logged in. This is the only endpoint available to a session that was opened with \
+        an expired password, and changing the password lifts that restriction. \
         Returns a 204 No Content status on success. Requires a valid session_id cookie."
 )]
 pub async fn update_own_password(
     auth: AuthenticatedUser,
     State(container): State<ServiceContainer>,
-    ValidatedJson(req): ValidatedJson<UpdatePasswordRequest>,
+    ValidatedJson(req): ValidatedJson<ChangePasswordRequest>,
 ) -> Result<impl IntoResponse, ApiError> {
     container
         .user_service()
-        .update_password_for_user(auth.user.id, req)
+        .change_own_password(auth.user.id, auth.require_session()?.id, req)
         .await?;
     Ok(StatusCode::NO_CONTENT)
 }
@@ -251,24 +377,345 @@ pub async fn update_own_password(
     path = "/api/users/{id}",
     tag = "Users",
     params(
-        ("id" = Uuid, Path, description = "Unique identifier of the user to delete")
+        ("id" = Uuid, Path, description = "Unique identifier of the user to delete"),
+        DeleteUserParams
     ),
     responses(
         (status = 204, description = "User deleted successfully"),
+        (status = 400, description = "Own account, the last enabled administrator, or missing or \
+                                      unknown transfer recipient"),
         (status = 401, description = "Unauthorized"),
-        (status = 403, description = "Forbidden - requires admin role"),
+        (status = 403, description = "Forbidden - requires the users:manage permission, and \
+                                      every permission the user holds"),
         (status = 404, description = "User not found"),
     ),
-    description = "Permanently deletes a user from the system by their unique ID. \
-        This action cannot be undone. \
+    description = "Deletes a user by their unique ID and signs them out everywhere. \
+        The account can be restored until it is purged at the end of the retention period \
+        (USER_DELETION_RETENTION_DAYS). When purged, private recipes are deleted with the account \
+        while public recipes are deleted, anonymized or transferred to another user as chosen here. \
         Returns a 204 No Content status on success. \
-        Requires a valid session_id cookie and administrator role to access this endpoint."
+        Requires a valid session_id cookie and the users:manage permission to access this endpoint."
 )]
 pub async fn delete_user(
-    _: AdminUser,
+    admin: RequirePermission<UsersManage>,
     Path(id): Path<Uuid>,
     State(container): State<ServiceContainer>,
+    Query(params): Query<DeleteUserParams>,
+) -> Result<impl IntoResponse, ApiError> {
+    container
+        .user_service()
+        .delete(id, admin.user.id, &admin.user.permissions, params)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    summary = "Restore User",
+    path = "/api/users/{id}/restore",
+    tag = "Users",
+    params(
+        ("id" = Uuid, Path, description = "Unique identifier of the deleted user to restore")
+    ),
+    responses(
+        (status = 204, description = "User restored successfully"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - requires the users:manage permission, and \
+                                      every permission the user holds"),
+        (status = 404, description = "No deleted user with this ID, or it was already purged"),
+    ),
+    description = "Restores a deleted account that has not been purged yet, along with all of its \
+        recipes. Requires a valid session_id cookie and the users:manage permission to access \
+        this endpoint."
+)]
+pub async fn restore_user(
+    admin: RequirePermission<UsersManage>,
+    Path(id): Path<Uuid>,
+    State(container): State<ServiceContainer>,
+) -> Result<impl IntoResponse, ApiError> {
+    container
+        .user_service()
+        .restore(id, &admin.user.permissions)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    summary = "Unlock User",
+    path = "/api/users/{id}/unlock",
+    tag = "Users",
+    params(
+        ("id" = Uuid, Path, description = "Unique identifier of the user to unlock")
+    ),
+    responses(
+        (status = 204, description = "User unlocked successfully"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - requires the users:manage permission, and \
+                                      every permission the user holds"),
+        (status = 404, description = "User not found"),
+    ),
+    description = "Clears a user's failed login attempts, lifting a lockout before it \
+        expires on its own. Accounts are locked temporarily after repeated failed logins, \
+        with the lockout growing longer for every further failure. \
+        Requires a valid session_id cookie and the users:manage permission to access this endpoint."
+)]
+pub async fn unlock_user(
+    admin: RequirePermission<UsersManage>,
+    Path(id): Path<Uuid>,
+    State(container): State<ServiceContainer>,
+) -> Result<impl IntoResponse, ApiError> {
+    container
+        .user_service()
+        .unlock(id, &admin.user.permissions)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    summary = "Disable User",
+    path = "/api/users/{id}/disable",
+    tag = "Users",
+    params(
+        ("id" = Uuid, Path, description = "Unique identifier of the user to disable")
+    ),
+    request_body = DisableUserRequest,
+    responses(
+        (status = 204, description = "User disabled successfully"),
+        (status = 400, description = "Invalid request body, own account, or the last enabled \
+                                      administrator"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - requires the users:manage permission, and \
+                                      every permission the user holds"),
+        (status = 404, description = "User not found"),
+    ),
+    description = "Disables a user's account until it is re-enabled by an administrator. \
+        The reason and time are recorded and shown in user listings. All of the user's \
+        sessions are revoked immediately and they can no longer log in. \
+        Administrators cannot disable their own account. \
+        Requires a valid session_id cookie and the users:manage permission to access this endpoint."
+)]
+pub async fn disable_user(
+    admin: RequirePermission<UsersManage>,
+    Path(id): Path<Uuid>,
+    State(container): State<ServiceContainer>,
+    ValidatedJson(req): ValidatedJson<DisableUserRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    container
+        .user_service()
+        .disable(id, admin.user.id, &admin.user.permissions, req)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    post,
+    summary = "Enable User",
+    path = "/api/users/{id}/enable",
+    tag = "Users",
+    params(
+        ("id" = Uuid, Path, description = "Unique identifier of the user to enable")
+    ),
+    responses(
+        (status = 204, description = "User enabled successfully"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - requires the users:manage permission, and \
+                                      every permission the user holds"),
+        (status = 404, description = "User not found"),
+    ),
+    description = "Re-enables a disabled user's account and clears the recorded reason. \
+        Requires a valid session_id cookie and the users:manage permission to access this endpoint."
+)]
+pub async fn enable_user(
+    admin: RequirePermission<UsersManage>,
+    Path(id): Path<Uuid>,
+    State(container): State<ServiceContainer>,
+) -> Result<impl IntoResponse, ApiError> {
+    container
+        .user_service()
+        .enable(id, admin.user.id, &admin.user.permissions)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    summary = "Grant Role",
+    path = "/api/users/{id}/roles/{role_id}",
+    tag = "Users",
+    params(
+        ("id" = Uuid, Path, description = "Unique identifier of the user"),
+        ("role_id" = Uuid, Path, description = "Unique identifier of the role to grant")
+    ),
+    responses(
+        (status = 204, description = "Role granted successfully"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - requires the users:manage permission, and \
+                                      every permission of the role"),
+        (status = 404, description = "User or role not found"),
+    ),
+    description = "Gives a user a role. Granting a role the user already has does nothing. \
+        The caller must hold every permission of the role. \
+        The change takes effect on the user's next request without logging in again. \
+        Requires a valid session_id cookie and the users:manage permission to access this endpoint."
+)]
+pub async fn grant_role(
+    admin: RequirePermission<UsersManage>,
+    Path((id, role_id)): Path<(Uuid, Uuid)>,
+    State(container): State<ServiceContainer>,
+) -> Result<impl IntoResponse, ApiError> {
+    container
+        .user_service()
+        .grant_role(id, role_id, admin.user.id, &admin.user.permissions)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    summary = "Revoke Role",
+    path = "/api/users/{id}/roles/{role_id}",
+    tag = "Users",
+    params(
+        ("id" = Uuid, Path, description = "Unique identifier of the user"),
+        ("role_id" = Uuid, Path, description = "Unique identifier of the role to revoke")
+    ),
+    responses(
+        (status = 204, description = "Role revoked successfully"),
+        (status = 400, description = "The user is the last enabled administrator"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - requires the users:manage permission, and \
+                                      every permission the user holds"),
+        (status = 404, description = "User or role not found"),
+    ),
+    description = "Takes a role away from a user. Revoking a role the user does not have \
+        does nothing. The Administrator role cannot be revoked from the last enabled \
+        administrator. The change takes effect on the user's next request. \
+        Requires a valid session_id cookie and the users:manage permission to access this endpoint."
+)]
+pub async fn revoke_role(
+    admin: RequirePermission<UsersManage>,
+    Path((id, role_id)): Path<(Uuid, Uuid)>,
+    State(container): State<ServiceContainer>,
+) -> Result<impl IntoResponse, ApiError> {
+    container
+        .user_service()
+        .revoke_role(id, role_id, admin.user.id, &admin.user.permissions)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    put,
+    summary = "Enable Application",
+    path = "/api/users/{id}/applications/{application}",
+    tag = "Users",
+    params(
+        ("id" = Uuid, Path, description = "Unique identifier of the user"),
+        ("application" = Application, Path, description = "The application to enable")
+    ),
+    responses(
+        (status = 204, description = "Application enabled successfully"),
+        (status = 400, description = "Unknown application"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - requires the users:manage permission, and \
+                                      every permission the user holds"),
+        (status = 404, description = "User not found"),
+    ),
+    description = "Gives a user access to an application. Requests into an application \
+        that has not been enabled for the user are rejected with 403 Forbidden, whatever \
+        their permissions. The change takes effect on the user's next request. \
+        Requires a valid session_id cookie and the users:manage permission to access this endpoint."
+)]
+pub async fn enable_application(
+    admin: RequirePermission<UsersManage>,
+    Path((id, application)): Path<(Uuid, Application)>,
+    State(container): State<ServiceContainer>,
+) -> Result<impl IntoResponse, ApiError> {
+    container
+        .application_service()
+        .grant(id, application, admin.user.id, &admin.user.permissions)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    summary = "Disable Application",
+    path = "/api/users/{id}/applications/{application}",
+    tag = "Users",
+    params(
+        ("id" = Uuid, Path, description = "Unique identifier of the user"),
+        ("application" = Application, Path, description = "The application to disable")
+    ),
+    responses(
+        (status = 204, description = "Application disabled successfully"),
+        (status = 400, description = "Unknown application"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - requires the users:manage permission, and \
+                                      every permission the user holds"),
+        (status = 404, description = "User not found"),
+    ),
+    description = "Takes away a user's access to an application. Their data in the \
+        application is kept. The change takes effect on the user's next request. \
+        Requires a valid session_id cookie and the users:manage permission to access this endpoint."
+)]
+pub async fn disable_application(
+    admin: RequirePermission<UsersManage>,
+    Path((id, application)): Path<(Uuid, Application)>,
+    State(container): State<ServiceContainer>,
+) -> Result<impl IntoResponse, ApiError> {
+    container
+        .application_service()
+        .revoke(id, application, admin.user.id, &admin.user.permissions)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    get,
+    summary = "Get Password Policy",
+    path = "/api/users/password-policy",
+    tag = "Users",
+    responses(
+        (status = 200, description = "Current password policy", body = PasswordPolicy),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - requires the users:manage permission"),
+    ),
+    description = "Retrieves the password expiration policy. \
+        Requires a valid session_id cookie and the users:manage permission to access this endpoint."
+)]
+pub async fn get_password_policy(
+    _: RequirePermission<UsersManage>,
+    State(container): State<ServiceContainer>,
+) -> Result<Json<PasswordPolicy>, ApiError> {
+    let policy = container.user_service().get_password_policy().await?;
+    Ok(Json(policy))
+}
+
+#[utoipa::path(
+    put,
+    summary = "Update Password Policy",
+    path = "/api/users/password-policy",
+    tag = "Users",
+    request_body = PasswordPolicy,
+    responses(
+        (status = 204, description = "Password policy updated successfully"),
+        (status = 400, description = "Invalid request body"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - requires the users:manage permission"),
+    ),
+    description = "Sets how many days a password stays valid after it is changed. \
+        The policy is applied on every subsequent password change; existing expirations \
+        are left untouched. Set expirationDays to null to stop passwords from expiring. \
+        Requires a valid session_id cookie and the users:manage permission to access this endpoint."
+)]
+pub async fn update_password_policy(
+    _: RequirePermission<UsersManage>,
+    State(container): State<ServiceContainer>,
+    ValidatedJson(req): ValidatedJson<PasswordPolicy>,
 ) -> Result<impl IntoResponse, ApiError> {
-    container.user_service().delete(id).await?;
+    container.user_service().update_password_policy(req).await?;
     Ok(StatusCode::NO_CONTENT)
 }
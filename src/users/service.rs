@@ -1,44 +1,166 @@
 use std::sync::Arc;
 
-use time::OffsetDateTime;
+use serde_json::json;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
 use uuid::Uuid;
 
 use crate::{
-    errors::ServiceError,
-    roles::IRoleRepository,
+    api_tokens::IApiTokenRepository,
+    applications::IApplicationRepository,
+    audit::{AuditAction, AuditEvent, IAuditRepository},
+    config::Config,
+    errors::{RepositoryError, ServiceError},
+    roles::{IRoleRepository, Permission, ensure_can_delegate},
+    sessions::ISessionRepository,
+    shared_models::{PaginatedResponse, check_page_size},
     users::{
-        CreateUserRequest, IUserRepository, Password, UpdatePasswordRequest, UpdateUserRequest,
-        User, UserBaseResponse, UserResponse,
+        ChangePasswordRequest, CreateUserRequest, DeleteUserParams, DisableUserRequest,
+        IUserRepository, Password, PasswordPolicy, RecipeHandling, UpdatePasswordRequest,
+        UpdateUserRequest, User, UserBaseResponse, UserFilters, UserResponse,
     },
 };
 
+/// Largest page of users that can be requested at once.
+const MAX_PAGE_SIZE: i64 = 100;
+
 #[async_trait::async_trait]
 pub trait IUserService: Send + Sync {
     async fn get_by_id(&self, id: Uuid) -> Result<UserResponse, ServiceError>;
-    async fn get_all(&self) -> Result<Vec<UserBaseResponse>, ServiceError>;
-    async fn create(&self, request: CreateUserRequest) -> Result<Uuid, ServiceError>;
-    async fn update(&self, id: Uuid, request: UpdateUserRequest) -> Result<(), ServiceError>;
+    /// Get a page of users matching the filters.
+    async fn search(
+        &self,
+        filters: UserFilters,
+    ) -> Result<PaginatedResponse<UserBaseResponse>, ServiceError>;
+    /// Create a user with the requested roles, which may only carry permissions held
+    /// by the administrator, given as `admin_permissions`.
+    async fn create(
+        &self,
+        request: CreateUserRequest,
+        admin_permissions: &[Permission],
+    ) -> Result<Uuid, ServiceError>;
+    /// Update a user's profile. The methods acting on an existing account on behalf
+    /// of an administrator refuse accounts holding permissions the administrator,
+    /// given as `admin_permissions`, does not hold.
+    async fn update(
+        &self,
+        id: Uuid,
+        request: UpdateUserRequest,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError>;
+    /// Set a user's password on behalf of an administrator and revoke the user's API
+    /// tokens.
     async fn update_password_for_user(
         &self,
         id: Uuid,
         request: UpdatePasswordRequest,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError>;
+    /// Change the password of the current user after checking their current password.
+    /// Every other session and every API token of the user is revoked, and the password
+    /// change restriction is lifted from the session that made the change.
+    async fn change_own_password(
+        &self,
+        id: Uuid,
+        session_id: Uuid,
+        request: ChangePasswordRequest,
+    ) -> Result<(), ServiceError>;
+    /// Delete an account on behalf of an administrator and revoke all of its sessions
+    /// and API tokens.
+    /// The account can be restored until it is purged once the retention period is over.
+    /// The last enabled administrator cannot be deleted.
+    async fn delete(
+        &self,
+        id: Uuid,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+        params: DeleteUserParams,
+    ) -> Result<(), ServiceError>;
+    /// Restore a deleted account that has not been purged yet.
+    async fn restore(&self, id: Uuid, admin_permissions: &[Permission])
+    -> Result<(), ServiceError>;
+    /// Permanently delete every account whose retention period is over, returning how
+    /// many were purged.
+    async fn purge_deleted(&self) -> Result<usize, ServiceError>;
+    /// Lift a lockout caused by failed logins before it runs out by itself.
+    async fn unlock(&self, id: Uuid, admin_permissions: &[Permission]) -> Result<(), ServiceError>;
+    /// Disable an account on behalf of an administrator and revoke all of its sessions
+    /// and API tokens. The last enabled administrator cannot be disabled.
+    async fn disable(
+        &self,
+        id: Uuid,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+        request: DisableUserRequest,
+    ) -> Result<(), ServiceError>;
+    async fn enable(
+        &self,
+        id: Uuid,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError>;
+    /// Give a user a role. Roles are loaded on every request, so the change applies
+    /// to the user's next request. Only roles whose permissions are all held by the
+    /// administrator, given as `admin_permissions`, can be granted.
+    async fn grant_role(
+        &self,
+        id: Uuid,
+        role_id: Uuid,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
     ) -> Result<(), ServiceError>;
-    async fn delete(&self, id: Uuid) -> Result<(), ServiceError>;
+    /// Take a role away from a user, refusing to remove the last administrator.
+    async fn revoke_role(
+        &self,
+        id: Uuid,
+        role_id: Uuid,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError>;
+    async fn get_password_policy(&self) -> Result<PasswordPolicy, ServiceError>;
+    async fn update_password_policy(&self, policy: PasswordPolicy) -> Result<(), ServiceError>;
 }
 
 #[derive(Clone)]
 pub struct UserService {
     user_repo: Arc<dyn IUserRepository>,
     role_repo: Arc<dyn IRoleRepository>,
+    session_repo: Arc<dyn ISessionRepository>,
+    application_repo: Arc<dyn IApplicationRepository>,
+    api_token_repo: Arc<dyn IApiTokenRepository>,
+    audit_repo: Arc<dyn IAuditRepository>,
+    config: Arc<Config>,
 }
 
 impl UserService {
-    pub fn new(user_repo: Arc<dyn IUserRepository>, role_repo: Arc<dyn IRoleRepository>) -> Self {
+    pub fn new(
+        user_repo: Arc<dyn IUserRepository>,
+        role_repo: Arc<dyn IRoleRepository>,
+        session_repo: Arc<dyn ISessionRepository>,
+        application_repo: Arc<dyn IApplicationRepository>,
+        api_token_repo: Arc<dyn IApiTokenRepository>,
+        audit_repo: Arc<dyn IAuditRepository>,
+        config: Arc<Config>,
+    ) -> Self {
         Self {
             user_repo,
             role_repo,
+            session_repo,
+            application_repo,
+            api_token_repo,
+            audit_repo,
+            config,
         }
     }
+
+    /// Refuse to act on an account holding permissions the administrator does not.
+    async fn ensure_can_manage(
+        &self,
+        id: Uuid,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError> {
+        let permissions = self.role_repo.get_permissions_for_user(id).await?;
+        ensure_can_delegate(admin_permissions, &permissions)
+    }
 }
 
 #[async_trait::async_trait]
@@ -47,36 +169,82 @@ impl IUserService for UserService {
         let user_base = self.user_repo.get_by_id(id).await?;
 
         let roles = self.role_repo.get_by_user_id(id).await?;
+        let permissions = self.role_repo.get_permissions_for_user(id).await?;
 
         let mut user: User = user_base.into();
         user.roles = roles;
+        user.permissions = permissions;
+        user.applications = self.application_repo.get_for_user(id).await?;
 
         Ok(user.into())
     }
 
-    async fn get_all(&self) -> Result<Vec<UserBaseResponse>, ServiceError> {
-        let users = self
-            .user_repo
-            .get_all()
-            .await?
+    async fn search(
+        &self,
+        filters: UserFilters,
+    ) -> Result<PaginatedResponse<UserBaseResponse>, ServiceError> {
+        let page = filters.page;
+        let page_size = filters.page_size;
+
+        check_page_size(page_size, MAX_PAGE_SIZE)?;
+
+        let now = OffsetDateTime::now_utc();
+        let lockout = self.config.lockout;
+        let (users, total) = self.user_repo.search(&filters, &lockout, now).await?;
+
+        let data = users
             .into_iter()
-            .map(UserBaseResponse::from)
-            .collect::<Vec<_>>();
+            .map(|user| {
+                let locked_until = lockout.locked_until(
+                    user.failed_login_attempts,
+                    user.last_failed_login_attempt,
+                    now,
+                );
+                UserBaseResponse {
+                    locked_until,
+                    ..user.into()
+                }
+            })
+            .collect();
 
-        Ok(users)
+        Ok(PaginatedResponse::new(data, page, page_size, total))
     }
 
-    async fn create(&self, request: CreateUserRequest) -> Result<Uuid, ServiceError> {
+    async fn create(
+        &self,
+        request: CreateUserRequest,
+        admin_permissions: &[Permission],
+    ) -> Result<Uuid, ServiceError> {
         let role_ids = request.roles.clone();
         let mut new_user: User = request.try_into()?;
         let roles = self.role_repo.get_by_role_ids(role_ids).await?;
+        for role in &roles {
+            let permissions = self.role_repo.get_permissions(role.id).await?;
+            ensure_can_delegate(admin_permissions, &permissions)?;
+        }
         new_user.roles = roles;
         self.user_repo.create(&new_user).await?;
+
+        let role_names: Vec<&str> = new_user.roles.iter().map(|r| r.name.as_str()).collect();
+        self.audit_repo
+            .record(
+                &AuditEvent::success(AuditAction::UserCreated)
+                    .target("user", new_user.id)
+                    .details(json!({ "username": new_user.username, "roles": role_names })),
+            )
+            .await;
+
         Ok(new_user.id)
     }
 
-    async fn update(&self, id: Uuid, request: UpdateUserRequest) -> Result<(), ServiceError> {
+    async fn update(
+        &self,
+        id: Uuid,
+        request: UpdateUserRequest,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError> {
         let mut existing = self.user_repo.get_by_id(id).await?;
+        self.ensure_can_manage(id, admin_permissions).await?;
         existing.first_name = request.first_name;
         existing.last_name = request.last_name;
         existing.email = request.email;
@@ -84,6 +252,13 @@ impl IUserService for UserService {
         existing.updated_at = OffsetDateTime::now_utc();
 
         self.user_repo.update_base(&existing).await?;
+        self.audit_repo
+            .record(
+                &AuditEvent::success(AuditAction::UserUpdated)
+                    .target("user", id)
+                    .details(json!({ "username": existing.username })),
+            )
+            .await;
         Ok(())
     }
 
@@ -91,16 +266,533 @@ impl IUserService for UserService {
         &self,
         id: Uuid,
         request: UpdatePasswordRequest,
+        admin_permissions: &[Permission],
     ) -> Result<(), ServiceError> {
         let mut existing = self.user_repo.get_by_id(id).await?;
+        self.ensure_can_manage(id, admin_permissions).await?;
         let new_password = Password::new(&request.raw_password)?;
+        let policy = self.user_repo.get_password_policy().await?;
         existing.password_hash = new_password;
-        self.user_repo.update_password(&existing).await?;
+        // an administrator may pick the expiration explicitly, otherwise the policy applies
+        existing.password_expiration = request
+            .password_expiration
+            .or_else(|| policy.expiration_from(OffsetDateTime::now_utc()));
+        self.user_repo
+            .update_password(&existing, self.config.password_history_size)
+            .await?;
+        self.api_token_repo.delete_all_for_user(id).await?;
+        self.audit_repo
+            .record(&AuditEvent::success(AuditAction::PasswordSet).target("user", id))
+            .await;
+        Ok(())
+    }
+
+    async fn change_own_password(
+        &self,
+        id: Uuid,
+        session_id: Uuid,
+        request: ChangePasswordRequest,
+    ) -> Result<(), ServiceError> {
+        let mut existing = self.user_repo.get_by_id(id).await?;
+        let now = OffsetDateTime::now_utc();
+
+        if let Some(locked_until) = self.config.lockout.locked_until(
+            existing.failed_login_attempts,
+            existing.last_failed_login_attempt,
+            now,
+        ) {
+            return Err(ServiceError::AccountLocked { locked_until });
+        }
+
+        // wrong guesses count towards the login lockout, so a hijacked session
+        // cannot be used to brute force the password
+        if !existing
+            .password_hash
+            .verify(request.current_password.as_bytes())
+        {
+            self.user_repo.record_failed_login(id, now).await?;
+            self.audit_repo
+                .record(
+                    &AuditEvent::failure(AuditAction::PasswordChanged)
+                        .target("user", id)
+                        .details(json!({ "reason": "current password is incorrect" })),
+                )
+                .await;
+            return Err(ServiceError::BadRequest(
+                "current password is incorrect".into(),
+            ));
+        }
+
+        let new_password = request.raw_password.as_bytes();
+        let history = self
+            .user_repo
+            .get_password_history(id, self.config.password_history_size)
+            .await?;
+        if existing.password_hash.verify(new_password)
+            || history.iter().any(|previous| previous.verify(new_password))
+        {
+            return Err(ServiceError::BadRequest(
+                "new password must not match a recently used password".into(),
+            ));
+        }
+
+        let policy = self.user_repo.get_password_policy().await?;
+        existing.password_hash = Password::new(&request.raw_password)?;
+        existing.password_expiration = policy.expiration_from(now);
+        self.user_repo
+            .update_password(&existing, self.config.password_history_size)
+            .await?;
+
+        self.session_repo
+            .delete_all_for_user_except(id, session_id)
+            .await?;
+        self.session_repo.clear_password_change_required(id).await?;
+        self.api_token_repo.delete_all_for_user(id).await?;
+        self.audit_repo
+            .record(&AuditEvent::success(AuditAction::PasswordChanged).target("user", id))
+            .await;
+
+        tracing::info!(user_id = %id, "Password changed by user");
+
+        Ok(())
+    }
+
+    async fn delete(
+        &self,
+        id: Uuid,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+        params: DeleteUserParams,
+    ) -> Result<(), ServiceError> {
+        if id == admin_id {
+            return Err(ServiceError::BadRequest(
+                "administrators cannot delete their own account".into(),
+            ));
+        }
+        self.ensure_can_manage(id, admin_permissions).await?;
+
+        let transfer_to = match (params.recipes, params.transfer_to) {
+            (RecipeHandling::Transfer, None) => {
+                return Err(ServiceError::BadRequest(
+                    "transferTo is required when transferring recipes".into(),
+                ));
+            }
+            (RecipeHandling::Transfer, Some(recipient)) if recipient == id => {
+                return Err(ServiceError::BadRequest(
+                    "recipes cannot be transferred to the user being deleted".into(),
+                ));
+            }
+            (RecipeHandling::Transfer, Some(recipient)) => {
+                self.user_repo.get_by_id(recipient).await.map_err(|err| {
+                    if let RepositoryError::NotFound { .. } = err {
+                        return ServiceError::BadRequest(format!(
+                            "cannot transfer recipes to unknown user `{recipient}`"
+                        ));
+                    }
+
+                    err.into()
+                })?;
+                Some(recipient)
+            }
+            _ => None,
+        };
+
+        let now = OffsetDateTime::now_utc();
+        if !self
+            .user_repo
+            .mark_deleted(id, now, params.recipes, transfer_to)
+            .await?
+        {
+            return Err(ServiceError::BadRequest(
+                "cannot delete the last enabled administrator".into(),
+            ));
+        }
+        self.session_repo.delete_all_for_user(id).await?;
+        self.api_token_repo.delete_all_for_user(id).await?;
+
+        let purge_after = now.saturating_add(self.config.user_deletion_retention);
+        self.audit_repo
+            .record(
+                &AuditEvent::success(AuditAction::UserDeleted)
+                    .target("user", id)
+                    .details(json!({
+                        "recipes": params.recipes,
+                        "transferTo": transfer_to,
+                        "purgeAfter": purge_after.format(&Rfc3339).ok(),
+                    })),
+            )
+            .await;
+
+        tracing::info!(
+            user_id = %id,
+            admin_id = %admin_id,
+            recipes = params.recipes.as_str(),
+            "Account deleted by administrator"
+        );
+
+        Ok(())
+    }
+
+    async fn restore(
+        &self,
+        id: Uuid,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError> {
+        self.ensure_can_manage(id, admin_permissions).await?;
+        self.user_repo.restore(id).await?;
+        self.audit_repo
+            .record(&AuditEvent::success(AuditAction::UserRestored).target("user", id))
+            .await;
+        tracing::info!(user_id = %id, "Deleted account restored by administrator");
         Ok(())
     }
 
-    async fn delete(&self, id: Uuid) -> Result<(), ServiceError> {
-        self.user_repo.delete(id).await?;
+    async fn purge_deleted(&self) -> Result<usize, ServiceError> {
+        let deleted_before =
+            OffsetDateTime::now_utc().saturating_sub(self.config.user_deletion_retention);
+        let purged = self.user_repo.purge_deleted(deleted_before).await?;
+
+        for id in &purged {
+            self.audit_repo
+                .record(&AuditEvent::success(AuditAction::UserPurged).target("user", id))
+                .await;
+        }
+
+        Ok(purged.len())
+    }
+
+    async fn unlock(&self, id: Uuid, admin_permissions: &[Permission]) -> Result<(), ServiceError> {
+        self.ensure_can_manage(id, admin_permissions).await?;
+        self.user_repo.reset_failed_logins(id).await?;
+        self.audit_repo
+            .record(&AuditEvent::success(AuditAction::UserUnlocked).target("user", id))
+            .await;
+        tracing::info!(user_id = %id, "Account unlocked by administrator");
         Ok(())
     }
+
+    async fn disable(
+        &self,
+        id: Uuid,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+        request: DisableUserRequest,
+    ) -> Result<(), ServiceError> {
+        if id == admin_id {
+            return Err(ServiceError::BadRequest(
+                "administrators cannot disable their own account".into(),
+            ));
+        }
+        self.ensure_can_manage(id, admin_permissions).await?;
+
+        let reason = request.reason.trim();
+        if !self
+            .user_repo
+            .disable(id, reason, OffsetDateTime::now_utc())
+            .await?
+        {
+            return Err(ServiceError::BadRequest(
+                "cannot disable the last enabled administrator".into(),
+            ));
+        }
+        self.session_repo.delete_all_for_user(id).await?;
+        self.api_token_repo.delete_all_for_user(id).await?;
+        self.audit_repo
+            .record(
+                &AuditEvent::success(AuditAction::UserDisabled)
+                    .target("user", id)
+                    .details(json!({ "reason": reason })),
+            )
+            .await;
+
+        tracing::info!(
+            user_id = %id,
+            admin_id = %admin_id,
+            reason = %reason,
+            "Account disabled by administrator"
+        );
+
+        Ok(())
+    }
+
+    async fn enable(
+        &self,
+        id: Uuid,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError> {
+        self.ensure_can_manage(id, admin_permissions).await?;
+        self.user_repo.enable(id).await?;
+        self.audit_repo
+            .record(&AuditEvent::success(AuditAction::UserEnabled).target("user", id))
+            .await;
+        tracing::info!(user_id = %id, admin_id = %admin_id, "Account enabled by administrator");
+        Ok(())
+    }
+
+    async fn grant_role(
+        &self,
+        id: Uuid,
+        role_id: Uuid,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError> {
+        // make sure both exist so a bad id is reported as not found
+        self.user_repo.get_by_id(id).await?;
+        let role = self.role_repo.get_by_id(role_id).await?;
+
+        let permissions = self.role_repo.get_permissions(role_id).await?;
+        ensure_can_delegate(admin_permissions, &permissions)?;
+
+        self.role_repo.grant_to_user(id, role_id).await?;
+        self.audit_repo
+            .record(
+                &AuditEvent::success(AuditAction::RoleGranted)
+                    .target("user", id)
+                    .details(json!({ "roleId": role_id, "role": role.name })),
+            )
+            .await;
+
+        tracing::info!(
+            user_id = %id,
+            admin_id = %admin_id,
+            role = %role.name,
+            "Role granted"
+        );
+
+        Ok(())
+    }
+
+    async fn revoke_role(
+        &self,
+        id: Uuid,
+        role_id: Uuid,
+        admin_id: Uuid,
+        admin_permissions: &[Permission],
+    ) -> Result<(), ServiceError> {
+        self.user_repo.get_by_id(id).await?;
+        let role = self.role_repo.get_by_id(role_id).await?;
+        self.ensure_can_manage(id, admin_permissions).await?;
+
+        let held = self
+            .role_repo
+            .get_by_user_id(id)
+            .await?
+            .iter()
+            .any(|r| r.id == role_id);
+        if !held {
+            return Ok(());
+        }
+
+        if !self.role_repo.revoke_from_user(id, role_id).await? {
+            return Err(ServiceError::BadRequest(
+                "cannot remove the last enabled administrator".into(),
+            ));
+        }
+
+        self.audit_repo
+            .record(
+                &AuditEvent::success(AuditAction::RoleRevoked)
+                    .target("user", id)
+                    .details(json!({ "roleId": role_id, "role": role.name })),
+            )
+            .await;
+
+        tracing::info!(
+            user_id = %id,
+            admin_id = %admin_id,
+            role = %role.name,
+            "Role revoked"
+        );
+
+        Ok(())
+    }
+
+    async fn get_password_policy(&self) -> Result<PasswordPolicy, ServiceError> {
+        let policy = self.user_repo.get_password_policy().await?;
+        Ok(policy)
+    }
+
+    async fn update_password_policy(&self, policy: PasswordPolicy) -> Result<(), ServiceError> {
+        self.user_repo.update_password_policy(&policy).await?;
+        self.audit_repo
+            .record(
+                &AuditEvent::success(AuditAction::PasswordPolicyUpdated)
+                    .details(json!({ "expirationDays": policy.expiration_days })),
+            )
+            .await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database::Database,
+        roles::{ADMINISTRATOR_ROLE, RoleResponse},
+        services::ServiceContainer,
+    };
+
+    /// A user manager who can only work with recipes besides managing users.
+    const HELD: &[Permission] = &[
+        Permission::UsersManage,
+        Permission::RecipesRead,
+        Permission::RecipesWrite,
+    ];
+
+    fn request(username: &str, roles: Vec<Uuid>) -> CreateUserRequest {
+        CreateUserRequest {
+            first_name: "Test".to_string(),
+            last_name: "User".to_string(),
+            email: format!("{username}@example.com"),
+            username: username.to_string(),
+            raw_password: "Password12!!xY".to_string(),
+            password_expiration: OffsetDateTime::now_utc() + time::Duration::days(90),
+            roles,
+        }
+    }
+
+    async fn setup() -> (Arc<dyn IUserService>, Vec<RoleResponse>) {
+        let db = Database::in_memory().await;
+        let container = ServiceContainer::new(db.pool, Config::for_tests());
+        let roles = container.role_service().get_all().await.unwrap();
+        (container.user_service(), roles)
+    }
+
+    fn role_id(roles: &[RoleResponse], name: &str) -> Uuid {
+        roles.iter().find(|role| role.name == name).unwrap().id
+    }
+
+    #[tokio::test]
+    async fn test_grant_role_refuses_permissions_the_caller_lacks() {
+        let (users, roles) = setup().await;
+        let admin_id = Uuid::now_v7();
+        let id = users
+            .create(request("target", vec![role_id(&roles, "RecipeUser")]), HELD)
+            .await
+            .unwrap();
+
+        let err = users
+            .grant_role(id, role_id(&roles, ADMINISTRATOR_ROLE), admin_id, HELD)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::Forbidden(_)));
+        let granted = users.get_by_id(id).await.unwrap().roles;
+        assert!(granted.iter().all(|role| role.name != ADMINISTRATOR_ROLE));
+
+        // granting a role whose permissions the caller holds is still allowed
+        users
+            .grant_role(id, role_id(&roles, "RecipeUser"), admin_id, HELD)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_refuses_roles_with_permissions_the_caller_lacks() {
+        let (users, roles) = setup().await;
+
+        let err = users
+            .create(
+                request("target", vec![role_id(&roles, ADMINISTRATOR_ROLE)]),
+                HELD,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::Forbidden(_)));
+    }
+
+    #[tokio::test]
+    async fn test_refuses_managing_users_holding_permissions_the_caller_lacks() {
+        let (users, roles) = setup().await;
+        let admin_id = Uuid::now_v7();
+        let id = users
+            .create(
+                request("target", vec![role_id(&roles, ADMINISTRATOR_ROLE)]),
+                Permission::ALL,
+            )
+            .await
+            .unwrap();
+
+        let err = users
+            .disable(
+                id,
+                admin_id,
+                HELD,
+                DisableUserRequest {
+                    reason: "test".to_string(),
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::Forbidden(_)));
+
+        let err = users
+            .update_password_for_user(
+                id,
+                UpdatePasswordRequest {
+                    raw_password: "Another12!!xY".to_string(),
+                    password_expiration: None,
+                },
+                HELD,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::Forbidden(_)));
+    }
+
+    #[tokio::test]
+    async fn test_refuses_disabling_the_last_enabled_administrator() {
+        let (users, roles) = setup().await;
+        let admin_role = vec![role_id(&roles, ADMINISTRATOR_ROLE)];
+        let first = users
+            .create(request("first", admin_role.clone()), Permission::ALL)
+            .await
+            .unwrap();
+        let second = users
+            .create(request("second", admin_role), Permission::ALL)
+            .await
+            .unwrap();
+        let disable = || DisableUserRequest {
+            reason: "test".to_string(),
+        };
+
+        users
+            .disable(first, second, Permission::ALL, disable())
+            .await
+            .unwrap();
+        let err = users
+            .disable(second, first, Permission::ALL, disable())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::BadRequest(_)));
+    }
+
+    #[tokio::test]
+    async fn test_refuses_deleting_the_last_enabled_administrator() {
+        let (users, roles) = setup().await;
+        let admin_role = vec![role_id(&roles, ADMINISTRATOR_ROLE)];
+        let first = users
+            .create(request("first", admin_role.clone()), Permission::ALL)
+            .await
+            .unwrap();
+        let second = users
+            .create(request("second", admin_role), Permission::ALL)
+            .await
+            .unwrap();
+        let delete = || DeleteUserParams {
+            recipes: RecipeHandling::default(),
+            transfer_to: None,
+        };
+
+        users
+            .delete(first, second, Permission::ALL, delete())
+            .await
+            .unwrap();
+        let err = users
+            .delete(second, first, Permission::ALL, delete())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::BadRequest(_)));
+    }
 }
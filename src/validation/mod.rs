@@ -1,2 +1,5 @@
 pub mod password;
 pub use password::*;
+
+pub mod text;
+pub use text::*;
@@ -0,0 +1,9 @@
+use validator::ValidationError;
+
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(ValidationError::new("blank").with_message("must not be blank".into()));
+    }
+
+    Ok(())
+}